@@ -0,0 +1,35 @@
+//! Renders of the same scene with its geometry in double and single precision
+//! (see the `float` module).
+//!
+//! Run with `cargo bench`.
+
+#![feature(test)]
+
+extern crate test;
+
+use raytracer::scene::Scene;
+use test::Bencher;
+
+const WIDTH: usize = 100;
+const HEIGHT: usize = 50;
+
+fn scene() -> Scene {
+    let mut scene = Scene::parse(include_str!("../examples/scenes/scene.json")).unwrap();
+    scene.camera = scene.camera.with_size(WIDTH, HEIGHT).with_progress(false);
+    scene
+}
+
+#[bench]
+fn render_in_double_precision(bencher: &mut Bencher) {
+    let Scene { world, camera } = scene();
+
+    bencher.iter(|| camera.render(world.clone()));
+}
+
+#[bench]
+fn render_in_single_precision(bencher: &mut Bencher) {
+    let Scene { world, camera } = scene();
+    let world = world.cast::<f32>();
+
+    bencher.iter(|| camera.render(world.clone()));
+}
@@ -1,4 +1,4 @@
-//// Example from Chapter 5.
+//! Example from Chapter 5.
 
 use std::fs::File;
 
@@ -42,7 +42,7 @@ fn main() {
 
             let position = Point::new(world_x, world_y, wall_z);
             let ray = Ray::new(ray_origin, (position - ray_origin).normalize());
-            let intersections = ray.intersect(sphere);
+            let intersections = ray.intersect(&sphere);
 
             if intersections.hit().is_some() {
                 canvas.write_pixel(x, y, Color::new(1.0, 0.0, 0.0));
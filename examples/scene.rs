@@ -1,4 +1,4 @@
-//// Example from Chapter 7.
+//! Example from Chapter 7.
 
 use std::f64::consts::PI;
 use std::fs::File;
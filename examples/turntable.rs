@@ -0,0 +1,91 @@
+//! Renders a turntable of the scene from `patterns.rs`: the camera orbits
+//! around the spheres, producing a numbered sequence of frames.
+//! Running the example again resumes from the last completed frame.
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use raytracer::animation::{CameraAnimation, CameraKeyframe, Easing};
+use raytracer::color::Color;
+use raytracer::light::Light;
+use raytracer::material::Material;
+use raytracer::matrix::{transformations, Matrix};
+use raytracer::object::Object;
+use raytracer::pattern::Pattern;
+use raytracer::point::Point;
+use raytracer::vector::Vector;
+use raytracer::world::World;
+
+const CANVAS_WIDTH: usize = 400;
+const CANVAS_HEIGHT: usize = 200;
+const FRAME_COUNT: usize = 24;
+const KEYFRAME_COUNT: usize = 8;
+
+fn main() {
+    let light_source = Light::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+    let world = World::new(
+        vec![light_source],
+        vec![large_sphere(), small_sphere(), floor()],
+    );
+
+    // a full orbit around the scene, slowly rising
+    let keyframes = (0..=KEYFRAME_COUNT)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / KEYFRAME_COUNT as f64;
+            CameraKeyframe::new(
+                i as f64,
+                Point::new(5.0 * angle.sin(), 1.5 + i as f64 * 0.1, -5.0 * angle.cos()),
+                Point::new(0.0, 1.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                PI / 3.0,
+            )
+        })
+        .collect();
+    let animation =
+        CameraAnimation::new(CANVAS_WIDTH, CANVAS_HEIGHT, keyframes, Easing::CatmullRom);
+
+    let out_directory = Path::new("examples/out/turntable");
+    let rendered = animation
+        .render_sequence(&world, FRAME_COUNT, out_directory)
+        .expect("Failed to render frames");
+    println!("Rendered {rendered} frames to {}", out_directory.display());
+}
+
+fn large_sphere() -> Object {
+    Object::sphere(
+        transformations::translation(-0.5, 1.0, 0.5),
+        Material {
+            pattern: Pattern::stripe(
+                Color::white(),
+                Color::red(),
+                transformations::scaling(0.2, 0.2, 0.2),
+            ),
+            diffuse: 0.7,
+            specular: 0.3,
+            ..Material::default()
+        },
+    )
+}
+
+fn small_sphere() -> Object {
+    Object::sphere(
+        transformations::scaling(0.5, 0.5, 0.5) >> transformations::translation(1.5, 0.5, -0.5),
+        Material {
+            pattern: Pattern::solid(Color::new(0.5, 1.0, 0.1)),
+            diffuse: 0.7,
+            specular: 0.3,
+            ..Material::default()
+        },
+    )
+}
+
+fn floor() -> Object {
+    Object::plane(
+        Matrix::identity(),
+        Material {
+            pattern: Pattern::solid(Color::new(1.0, 0.9, 0.9)),
+            specular: 0.0,
+            ..Material::default()
+        },
+    )
+}
@@ -0,0 +1,488 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::error::ValidationError;
+use crate::point::Point;
+use crate::ppm_writer::PpmWriter;
+use crate::vector::Vector;
+use crate::view_transform::view_transform;
+use crate::world::World;
+
+/// How camera parameters are interpolated between keyframes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    /// Constant speed between keyframes, with sharp changes of direction on each keyframe
+    Linear,
+    /// Smooth curve passing through all keyframes.
+    /// See https://en.wikipedia.org/wiki/Cubic_Hermite_spline#Catmull%E2%80%93Rom_spline
+    CatmullRom,
+}
+
+/// Parameters of the camera (as taken by `view_transform`) at a given time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraKeyframe {
+    pub time: f64,
+    pub from: Point,
+    pub to: Point,
+    pub up: Vector,
+    pub field_of_view: f64,
+}
+
+impl CameraKeyframe {
+    pub fn new(time: f64, from: Point, to: Point, up: Vector, field_of_view: f64) -> Self {
+        Self {
+            time,
+            from,
+            to,
+            up,
+            field_of_view,
+        }
+    }
+}
+
+/// Camera moving through a sequence of keyframes
+pub struct CameraAnimation {
+    /// Horizontal size (in pixels) of the rendered frames
+    hsize: usize,
+    /// Vertical size (in pixels) of the rendered frames
+    vsize: usize,
+    /// Keyframes, sorted by time
+    keyframes: Vec<CameraKeyframe>,
+    easing: Easing,
+    /// Whether to print the progress of the frames and of each render
+    show_progress: bool,
+}
+
+impl CameraAnimation {
+    const FRAME_EXTENSION: &'static str = "ppm";
+    const PARTIAL_FRAME_EXTENSION: &'static str = "ppm.partial";
+
+    pub fn new(
+        hsize: usize,
+        vsize: usize,
+        mut keyframes: Vec<CameraKeyframe>,
+        easing: Easing,
+    ) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "Animation needs at least one keyframe"
+        );
+        keyframes.sort_by(|keyframe, other| keyframe.time.total_cmp(&other.time));
+
+        Self {
+            hsize,
+            vsize,
+            keyframes,
+            easing,
+            show_progress: true,
+        }
+    }
+
+    pub fn with_progress(self, show_progress: bool) -> Self {
+        Self {
+            show_progress,
+            ..self
+        }
+    }
+
+    pub fn start_time(&self) -> f64 {
+        self.keyframes[0].time
+    }
+
+    pub fn end_time(&self) -> f64 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    /// # Panics
+    ///
+    /// If the time isn't a finite number (see `try_keyframe_at`).
+    pub fn keyframe_at(&self, time: f64) -> CameraKeyframe {
+        self.try_keyframe_at(time)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Camera parameters interpolated at the given time, unless it isn't a finite number.
+    /// Times outside of the animation are clamped to its first or last keyframe.
+    ///
+    /// Between keyframes, the field of view stays within the range of the surrounding
+    /// keyframes, where Catmull-Rom curves could overshoot it, and `up` is normalized.
+    pub fn try_keyframe_at(&self, time: f64) -> Result<CameraKeyframe, ValidationError> {
+        ValidationError::check_finite("time", time)?;
+        let last = self.keyframes.len() - 1;
+        if time <= self.start_time() {
+            return Ok(CameraKeyframe {
+                time,
+                ..self.keyframes[0]
+            });
+        }
+        if time >= self.end_time() {
+            return Ok(CameraKeyframe {
+                time,
+                ..self.keyframes[last]
+            });
+        }
+
+        // index of the keyframe starting the segment containing `time`
+        let segment = self
+            .keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= time)
+            .unwrap();
+
+        // the four keyframes around the segment, repeating the first and last ones
+        // at the edges of the animation
+        let k0 = self.keyframes[segment.saturating_sub(1)];
+        let k1 = self.keyframes[segment];
+        let k2 = self.keyframes[segment + 1];
+        let k3 = self.keyframes[(segment + 2).min(last)];
+
+        let u = (time - k1.time) / (k2.time - k1.time);
+        let interpolate = |component: fn(&CameraKeyframe) -> f64| {
+            let values = [&k0, &k1, &k2, &k3].map(component);
+            self.easing.interpolate(values, u)
+        };
+        let fields_of_view = [k0, k1, k2, k3].map(|k| k.field_of_view);
+        let field_of_view = interpolate(|k| k.field_of_view).clamp(
+            fields_of_view.into_iter().fold(f64::INFINITY, f64::min),
+            fields_of_view.into_iter().fold(f64::NEG_INFINITY, f64::max),
+        );
+
+        Ok(CameraKeyframe {
+            time,
+            from: Point::new(
+                interpolate(|k| k.from.x),
+                interpolate(|k| k.from.y),
+                interpolate(|k| k.from.z),
+            ),
+            to: Point::new(
+                interpolate(|k| k.to.x),
+                interpolate(|k| k.to.y),
+                interpolate(|k| k.to.z),
+            ),
+            up: Vector::new(
+                interpolate(|k| k.up.x),
+                interpolate(|k| k.up.y),
+                interpolate(|k| k.up.z),
+            )
+            .normalize(),
+            field_of_view,
+        })
+    }
+
+    /// # Panics
+    ///
+    /// If the camera is invalid at the given time (see `try_camera_at`).
+    pub fn camera_at(&self, time: f64) -> Camera {
+        self.try_camera_at(time)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Camera at the given time, unless the time isn't a finite number, the field
+    /// of view isn't strictly between 0 and π, or the camera can't be oriented,
+    /// eg. when `up` is parallel to the direction of the camera
+    pub fn try_camera_at(&self, time: f64) -> Result<Camera, ValidationError> {
+        let keyframe = self.try_keyframe_at(time)?;
+        ValidationError::check_open_range("field_of_view", keyframe.field_of_view, 0.0, PI)?;
+
+        Camera::new(self.hsize, self.vsize, keyframe.field_of_view)
+            .try_with_transform(view_transform(keyframe.from, keyframe.to, keyframe.up))
+            .map(|camera| camera.with_progress(self.show_progress))
+            .map_err(|error| ValidationError::SingularTransformation {
+                field: "view transformation",
+                error,
+            })
+    }
+
+    /// Time of the given frame, with frames evenly spaced from the first to the last keyframe
+    pub fn frame_time(&self, frame: usize, frame_count: usize) -> f64 {
+        if frame_count <= 1 {
+            return self.start_time();
+        }
+        let progress = frame as f64 / (frame_count - 1) as f64;
+        self.start_time() + (self.end_time() - self.start_time()) * progress
+    }
+
+    /// Path of the given frame within `directory`, eg. `frame_0042.ppm`
+    pub fn frame_path(directory: &Path, frame: usize) -> PathBuf {
+        directory.join(format!("frame_{frame:04}.{}", Self::FRAME_EXTENSION))
+    }
+
+    /// Render `frame_count` frames of the animation as a numbered sequence of
+    /// PPM images in `directory`.
+    ///
+    /// Frames are first written to a temporary file and then renamed, so a frame
+    /// file only exists once it is complete. Frames that already exist are skipped,
+    /// which allows to resume an interrupted render by running it again.
+    ///
+    /// Returns the number of frames actually rendered, or an error of kind
+    /// `InvalidInput` if the camera is invalid at the time of a frame.
+    pub fn render_sequence(
+        &self,
+        world: &World,
+        frame_count: usize,
+        directory: &Path,
+    ) -> io::Result<usize> {
+        fs::create_dir_all(directory)?;
+
+        let mut rendered_frames = 0;
+        for frame in 0..frame_count {
+            let frame_path = Self::frame_path(directory, frame);
+            if frame_path.exists() {
+                continue;
+            }
+
+            if self.show_progress {
+                println!("Rendering frame {} of {frame_count}", frame + 1);
+            }
+            let camera = self
+                .try_camera_at(self.frame_time(frame, frame_count))
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            let canvas = camera.render_parallel(world.clone(), None);
+
+            let partial_path = frame_path.with_extension(Self::PARTIAL_FRAME_EXTENSION);
+            PpmWriter::from_writer(File::create(&partial_path)?).write_canvas(&canvas)?;
+            fs::rename(&partial_path, &frame_path)?;
+
+            rendered_frames += 1;
+        }
+
+        Ok(rendered_frames)
+    }
+}
+
+impl Easing {
+    /// Interpolate between `values[1]` (at `u` = 0) and `values[2]` (at `u` = 1).
+    /// `values[0]` and `values[3]` are the values at the previous and next keyframes,
+    /// used to smooth the curve.
+    fn interpolate(self, values: [f64; 4], u: f64) -> f64 {
+        let [p0, p1, p2, p3] = values;
+
+        match self {
+            Easing::Linear => p1 + (p2 - p1) * u,
+            Easing::CatmullRom => {
+                0.5 * ((2.0 * p1)
+                    + (-p0 + p2) * u
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u.powi(2)
+                    + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * u.powi(3))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::float_eq::FloatEq;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn keyframes() -> Vec<CameraKeyframe> {
+        let up = Vector::new(0.0, 1.0, 0.0);
+        vec![
+            CameraKeyframe::new(
+                0.0,
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                up,
+                PI / 2.0,
+            ),
+            CameraKeyframe::new(
+                1.0,
+                Point::new(2.0, 0.0, -5.0),
+                Point::origin(),
+                up,
+                PI / 3.0,
+            ),
+            CameraKeyframe::new(
+                2.0,
+                Point::new(2.0, 4.0, -5.0),
+                Point::origin(),
+                up,
+                PI / 3.0,
+            ),
+        ]
+    }
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("raytracer_animation_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn keyframes_are_reproduced_exactly() {
+        for easing in [Easing::Linear, Easing::CatmullRom] {
+            let animation = CameraAnimation::new(10, 10, keyframes(), easing);
+
+            for keyframe in keyframes() {
+                assert_eq!(keyframe, animation.keyframe_at(keyframe.time));
+            }
+        }
+    }
+
+    #[test]
+    fn linear_easing_interpolates_at_constant_speed() {
+        let animation = CameraAnimation::new(10, 10, keyframes(), Easing::Linear);
+
+        let keyframe = animation.keyframe_at(0.25);
+
+        assert_eq!(Point::new(0.5, 0.0, -5.0), keyframe.from);
+        assert!(keyframe
+            .field_of_view
+            .float_eq(PI / 2.0 - (PI / 2.0 - PI / 3.0) * 0.25));
+    }
+
+    #[test]
+    fn catmull_rom_easing_anticipates_next_keyframes() {
+        let animation = CameraAnimation::new(10, 10, keyframes(), Easing::CatmullRom);
+
+        let keyframe = animation.keyframe_at(0.5);
+
+        // the curve starts bending upwards towards the third keyframe
+        assert!(keyframe.from.y < 0.0);
+        assert!(keyframe.from.x > 0.0 && keyframe.from.x < 2.0);
+    }
+
+    #[test]
+    fn times_outside_of_animation_are_clamped() {
+        let animation = CameraAnimation::new(10, 10, keyframes(), Easing::CatmullRom);
+
+        assert_eq!(keyframes()[0].from, animation.keyframe_at(-1.0).from);
+        assert_eq!(keyframes()[2].from, animation.keyframe_at(3.0).from);
+    }
+
+    #[test]
+    fn catmull_rom_easing_keeps_field_of_view_between_keyframes() {
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let keyframe = |time, field_of_view| {
+            CameraKeyframe::new(
+                time,
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                up,
+                field_of_view,
+            )
+        };
+        // the curve would overshoot below the narrow field of view, and beyond π
+        let keyframes = vec![
+            keyframe(0.0, 3.0),
+            keyframe(1.0, 0.1),
+            keyframe(2.0, 3.1),
+            keyframe(3.0, 3.1),
+            keyframe(4.0, 0.1),
+        ];
+        let animation = CameraAnimation::new(10, 10, keyframes, Easing::CatmullRom);
+
+        for step in 0..=40 {
+            let field_of_view = animation.keyframe_at(step as f64 * 0.1).field_of_view;
+            assert!((0.1..=3.1).contains(&field_of_view), "{field_of_view}");
+            assert!(animation.try_camera_at(step as f64 * 0.1).is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid_cameras_are_reported_instead_of_panicking() {
+        let flipped_keyframes = vec![
+            keyframes()[0],
+            CameraKeyframe {
+                time: 1.0,
+                up: Vector::new(0.0, -1.0, 0.0),
+                ..keyframes()[0]
+            },
+        ];
+        let animation = CameraAnimation::new(10, 10, flipped_keyframes, Easing::Linear);
+
+        // `up` vanishes halfway through
+        assert!(matches!(
+            animation.try_camera_at(0.5),
+            Err(ValidationError::SingularTransformation { .. })
+        ));
+        assert_eq!(
+            Err(ValidationError::NotFinite { field: "time" }),
+            animation.try_keyframe_at(f64::NAN)
+        );
+        assert!(animation.try_camera_at(f64::INFINITY).is_err());
+        assert!(animation.try_camera_at(0.25).is_ok());
+    }
+
+    #[test]
+    fn interpolated_up_vector_is_normalized() {
+        let tilted_keyframes = vec![
+            keyframes()[0],
+            CameraKeyframe {
+                time: 1.0,
+                up: Vector::new(1.0, 0.0, 0.0),
+                ..keyframes()[0]
+            },
+        ];
+        let animation = CameraAnimation::new(10, 10, tilted_keyframes, Easing::Linear);
+
+        let up = animation.keyframe_at(0.5).up;
+
+        assert!(up.magnitude().float_eq(1.0));
+    }
+
+    #[test]
+    fn keyframes_are_sorted_by_time() {
+        let mut unsorted_keyframes = keyframes();
+        unsorted_keyframes.reverse();
+
+        let animation = CameraAnimation::new(10, 10, unsorted_keyframes, Easing::Linear);
+
+        assert_eq!(0.0, animation.start_time());
+        assert_eq!(2.0, animation.end_time());
+    }
+
+    #[test]
+    fn frames_are_evenly_spaced_over_the_animation() {
+        let animation = CameraAnimation::new(10, 10, keyframes(), Easing::Linear);
+
+        assert_eq!(0.0, animation.frame_time(0, 5));
+        assert_eq!(0.5, animation.frame_time(1, 5));
+        assert_eq!(2.0, animation.frame_time(4, 5));
+    }
+
+    #[test]
+    fn renders_numbered_frame_sequence() {
+        let directory = temp_directory("sequence");
+        let animation =
+            CameraAnimation::new(4, 3, keyframes(), Easing::Linear).with_progress(false);
+
+        let rendered = animation
+            .render_sequence(&World::default(), 3, &directory)
+            .unwrap();
+
+        assert_eq!(3, rendered);
+        for frame in 0..3 {
+            assert!(CameraAnimation::frame_path(&directory, frame).exists());
+        }
+        assert!(directory.join("frame_0000.ppm").exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rendering_resumes_after_last_completed_frame() {
+        let directory = temp_directory("resume");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(CameraAnimation::frame_path(&directory, 0), "done").unwrap();
+        fs::write(CameraAnimation::frame_path(&directory, 1), "done").unwrap();
+        let animation =
+            CameraAnimation::new(4, 3, keyframes(), Easing::Linear).with_progress(false);
+
+        let rendered = animation
+            .render_sequence(&World::default(), 3, &directory)
+            .unwrap();
+
+        assert_eq!(1, rendered);
+        let first_frame = fs::read_to_string(CameraAnimation::frame_path(&directory, 0)).unwrap();
+        assert_eq!("done", first_frame);
+        assert!(CameraAnimation::frame_path(&directory, 2).exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+//! Light arriving from infinitely far away, seen by rays which miss every object.
+//!
+//! The background is visible from the camera and in reflections, and lights
+//! the scene with the path tracing integrator (image-based lighting).
+
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::hdr_reader::HdrReader;
+use crate::pfm_reader::PfmReader;
+use crate::vector::Vector;
+
+pub use crate::background::sky::Sky;
+
+mod sky;
+
+#[derive(Clone, Debug)]
+pub enum Background {
+    /// Same color in every direction
+    Constant(Color),
+    /// Vertical gradient, from the `bottom` color straight down to the `top`
+    /// color straight up
+    Gradient { bottom: Color, top: Color },
+    /// Daylight sky lit by the sun (see `Sky`)
+    Sky(Sky),
+    /// Panorama surrounding the scene (see `EnvironmentMap`)
+    Image(EnvironmentMap),
+}
+
+impl Default for Background {
+    /// Black, ie. no light from the background
+    fn default() -> Self {
+        Background::Constant(Color::black())
+    }
+}
+
+impl Background {
+    /// Light arriving along `direction` (towards the background, normalized)
+    pub fn color(&self, direction: Vector) -> Color {
+        match self {
+            Background::Constant(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = (direction.normalize().y.clamp(-1.0, 1.0) + 1.0) / 2.0;
+                *bottom * (1.0 - t) + *top * t
+            }
+            Background::Sky(sky) => sky.color(direction),
+            Background::Image(environment_map) => environment_map.color(direction),
+        }
+    }
+}
+
+/// Panorama in the equirectangular (latitude-longitude) projection: the
+/// horizontal axis of the image covers 360° around the y axis, starting and
+/// ending behind the scene (towards -z), and the vertical axis goes from
+/// straight up (+y) to straight down.
+#[derive(Clone, Debug)]
+pub struct EnvironmentMap {
+    // shared, since worlds are cloned for every render thread
+    image: Arc<Canvas>,
+    path: Option<PathBuf>,
+}
+
+impl EnvironmentMap {
+    /// # Panics
+    /// If the image is empty.
+    pub fn new(image: Canvas) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "Environment maps can't be empty"
+        );
+        Self {
+            image: Arc::new(image),
+            path: None,
+        }
+    }
+
+    /// Load a high dynamic range image, in the Radiance (`.hdr`) or Portable
+    /// Float Map (`.pfm`) format
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        let file = || File::open(path);
+        let image = match extension.as_deref() {
+            Some("hdr") => HdrReader::from_reader(file()?).read_canvas()?,
+            Some("pfm") => PfmReader::from_reader(file()?).read_canvas()?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Unsupported image format, expected .hdr or .pfm",
+                ))
+            }
+        };
+        if image.width() == 0 || image.height() == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Empty image"));
+        }
+
+        Ok(Self {
+            image: Arc::new(image),
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    /// File the image was loaded from, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Color of the image in the given direction, interpolated between the
+    /// four nearest pixels
+    pub fn color(&self, direction: Vector) -> Color {
+        let direction = direction.normalize();
+        let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * PI);
+        let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+
+        let (width, height) = (self.image.width(), self.image.height());
+        // pixel centers are at half-integer coordinates
+        let x = u * width as f64 - 0.5;
+        let y = (v * height as f64 - 0.5).clamp(0.0, (height - 1) as f64);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        // wrap around horizontally, clamp vertically
+        let column = |x: f64| (x as isize).rem_euclid(width as isize) as usize;
+        let (left, right) = (column(x0), column(x0 + 1.0));
+        let (top, bottom) = (y0 as usize, (y0 as usize + 1).min(height - 1));
+
+        let upper =
+            self.image.pixel_at(left, top) * (1.0 - tx) + self.image.pixel_at(right, top) * tx;
+        let lower = self.image.pixel_at(left, bottom) * (1.0 - tx)
+            + self.image.pixel_at(right, bottom) * tx;
+        upper * (1.0 - ty) + lower * ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pfm_writer::PfmWriter;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_background_is_black() {
+        let background = Background::default();
+
+        assert_eq!(Color::black(), background.color(Vector::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn constant_background_is_the_same_in_every_direction() {
+        let color = Color::new(0.2, 0.3, 0.4);
+        let background = Background::Constant(color);
+
+        assert_eq!(color, background.color(Vector::new(0.0, 1.0, 0.0)));
+        assert_eq!(
+            color,
+            background.color(Vector::new(1.0, -1.0, 0.5).normalize())
+        );
+    }
+
+    #[test]
+    fn gradient_goes_from_bottom_to_top() {
+        let background = Background::Gradient {
+            bottom: Color::white(),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+
+        assert_eq!(
+            Color::white(),
+            background.color(Vector::new(0.0, -1.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.5, 0.5, 1.0),
+            background.color(Vector::new(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.0, 0.0, 1.0),
+            background.color(Vector::new(0.0, 1.0, 0.0))
+        );
+    }
+
+    fn compass_image() -> Canvas {
+        // pixel centers are at longitudes -135°, -45°, 45° and 135° (black),
+        // with a darker lower half
+        let mut image = Canvas::new(4, 2);
+        let columns = [
+            Color::red(),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+        ];
+        for y in 0..2 {
+            for (x, color) in columns.iter().enumerate() {
+                image.write_pixel(x, y, *color * if y == 0 { 1.0 } else { 0.5 });
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn environment_map_covers_all_directions() {
+        let environment_map = EnvironmentMap::new(compass_image());
+
+        let longitude = |degrees: f64| {
+            let radians = degrees.to_radians();
+            Vector::new(radians.sin(), 0.0, radians.cos())
+        };
+        assert_eq!(
+            Color::new(0.0, 0.75, 0.0),
+            environment_map.color(longitude(-45.0))
+        );
+        assert_eq!(
+            Color::new(0.0, 0.375, 0.375),
+            environment_map.color(longitude(0.0))
+        );
+        // wraps around behind the scene
+        assert_eq!(
+            Color::new(0.375, 0.0, 0.0),
+            environment_map.color(longitude(180.0))
+        );
+    }
+
+    #[test]
+    fn environment_map_can_be_loaded_from_file() {
+        let path = std::env::temp_dir().join("raytracer_environment_map.pfm");
+        let image = compass_image();
+        PfmWriter::from_writer(File::create(&path).unwrap())
+            .write_canvas(&image)
+            .unwrap();
+
+        let environment_map = EnvironmentMap::load(&path).unwrap();
+
+        assert_eq!(
+            format!("{image:?}"),
+            format!("{:?}", environment_map.image())
+        );
+        assert_eq!(Some(path.as_path()), environment_map.path());
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            EnvironmentMap::load("sky.png").unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn environment_map_is_upright() {
+        let environment_map = EnvironmentMap::new(compass_image());
+        let direction = Vector::new(-1.0, 0.0, 1.0).normalize();
+
+        assert_eq!(
+            Color::new(0.0, 1.0, 0.0),
+            environment_map.color(Vector::new(direction.x, 10.0, direction.z).normalize())
+        );
+        assert_eq!(
+            Color::new(0.0, 0.5, 0.0),
+            environment_map.color(Vector::new(direction.x, -10.0, direction.z).normalize())
+        );
+    }
+}
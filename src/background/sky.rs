@@ -0,0 +1,201 @@
+use std::f64::consts::PI;
+
+use crate::color::Color;
+use crate::vector::Vector;
+
+/// Analytic model of a clear daylight sky, after Preetham, Shirley and Smits,
+/// "A Practical Analytic Model for Daylight" (1999), with the sun as a small
+/// bright disk.
+///
+/// The sky only depends on the direction of the sun and on the turbidity of
+/// the atmosphere (2 for a very clear sky, around 10 for a hazy one).
+/// Below the horizon, the sky at the horizon in the same direction is used.
+///
+/// The sun is much too small to be found by the rays of the path tracer: a
+/// light placed far away in its direction gives it sharp shadows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sky {
+    /// Direction towards the sun
+    pub sun_direction: Vector,
+    pub turbidity: f64,
+    /// Scale of the colors, 1 giving a zenith of luminance around 1 with the
+    /// sun overhead
+    pub intensity: f64,
+}
+
+impl Sky {
+    pub const DEFAULT_TURBIDITY: f64 = 3.0;
+    /// Angular radius of the sun seen from the earth, in radians
+    pub const SUN_ANGULAR_RADIUS: f64 = 0.00465;
+    /// Luminance of the sun disk, relative to the sky next to it
+    const SUN_BRIGHTNESS: f64 = 100.0;
+    /// Luminance of the zenith (in kcd/m²) with the sun overhead in a clear
+    /// sky, mapped to a luminance of 1
+    const REFERENCE_LUMINANCE: f64 = 30.0;
+
+    pub fn new(sun_direction: Vector, turbidity: f64, intensity: f64) -> Self {
+        Self {
+            sun_direction,
+            turbidity,
+            intensity,
+        }
+    }
+
+    pub fn color(&self, direction: Vector) -> Color {
+        let direction = direction.normalize();
+        let sun_direction = self.sun_direction.normalize();
+        let sky = self.sky_color(direction, sun_direction);
+
+        if direction.dot(sun_direction) >= Self::SUN_ANGULAR_RADIUS.cos() {
+            sky * Self::SUN_BRIGHTNESS
+        } else {
+            sky
+        }
+    }
+
+    fn sky_color(&self, direction: Vector, sun_direction: Vector) -> Color {
+        let direction = if direction.y >= 0.0 {
+            direction
+        } else if direction.x == 0.0 && direction.z == 0.0 {
+            // straight down, where every direction of the horizon is as good
+            Vector::new(1.0, 0.0, 0.0)
+        } else {
+            Vector::new(direction.x, 0.0, direction.z).normalize()
+        };
+
+        let t = self.turbidity;
+        // angles from the zenith, of the sun (kept above the horizon) and of the direction
+        let sun_theta = sun_direction.y.clamp(0.0, 1.0).acos();
+        let theta = direction.y.clamp(0.001, 1.0).acos();
+        let gamma = direction.dot(sun_direction).clamp(-1.0, 1.0).acos();
+
+        // luminance and chromaticity at the zenith
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let powers = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+        let polynomial = |coefficients: [[f64; 4]; 3]| {
+            let [t2, t1, t0] = coefficients.map(|row| {
+                row.iter()
+                    .zip(powers)
+                    .map(|(coefficient, power)| coefficient * power)
+                    .sum::<f64>()
+            });
+            t2 * t * t + t1 * t + t0
+        };
+        let zenith_x = polynomial([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = polynomial([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        // distribution over the sky, relative to the zenith
+        let perez = |[a, b, c, d, e]: [f64; 5]| {
+            let f = |theta: f64, gamma: f64| {
+                (1.0 + a * (b / theta.cos()).exp())
+                    * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+            };
+            f(theta, gamma) / f(0.0, sun_theta)
+        };
+        let luminance = zenith_luminance
+            * perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]);
+        let x = zenith_x
+            * perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]);
+        let y = zenith_y
+            * perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]);
+
+        xyy_to_rgb(x, y, luminance.max(0.0) / Self::REFERENCE_LUMINANCE) * self.intensity
+    }
+}
+
+/// Linear sRGB color of the given CIE xyY color
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn luminance(color: Color) -> f64 {
+        0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue
+    }
+
+    #[test]
+    fn clear_sky_is_blue() {
+        let sky = Sky::new(Vector::new(1.0, 1.0, 0.0), Sky::DEFAULT_TURBIDITY, 1.0);
+
+        let zenith = sky.color(Vector::new(0.0, 1.0, 0.0));
+
+        assert!(zenith.blue > zenith.green && zenith.green > zenith.red);
+    }
+
+    #[test]
+    fn sky_is_brighter_around_the_sun() {
+        let sky = Sky::new(Vector::new(1.0, 0.5, 0.0), Sky::DEFAULT_TURBIDITY, 1.0);
+
+        let near_sun = sky.color(Vector::new(1.0, 0.6, 0.1));
+        let away_from_sun = sky.color(Vector::new(-1.0, 0.6, 0.1));
+
+        assert!(luminance(near_sun) > 2.0 * luminance(away_from_sun));
+    }
+
+    #[test]
+    fn sun_is_a_bright_disk() {
+        let sun_direction = Vector::new(0.0, 1.0, 1.0).normalize();
+        let sky = Sky::new(sun_direction, Sky::DEFAULT_TURBIDITY, 1.0);
+
+        let sun = sky.color(sun_direction);
+        let next_to_sun = sky.color(Vector::new(0.0, 1.0, 1.02).normalize());
+
+        assert!(luminance(sun) > 50.0 * luminance(next_to_sun));
+    }
+
+    #[test]
+    fn sky_below_horizon_is_the_horizon() {
+        let sky = Sky::new(Vector::new(0.0, 1.0, 1.0), Sky::DEFAULT_TURBIDITY, 1.0);
+
+        assert_eq!(
+            sky.color(Vector::new(1.0, 0.0, 0.0)),
+            sky.color(Vector::new(1.0, -1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn sky_is_darker_at_sunset() {
+        let noon = Sky::new(Vector::new(0.0, 1.0, 0.1), Sky::DEFAULT_TURBIDITY, 1.0);
+        let sunset = Sky::new(Vector::new(0.0, 0.05, 1.0), Sky::DEFAULT_TURBIDITY, 1.0);
+        let zenith = Vector::new(0.0, 1.0, 0.0);
+
+        assert!((0.5..1.0).contains(&luminance(noon.color(zenith))));
+        assert!(luminance(sunset.color(zenith)) < 0.2 * luminance(noon.color(zenith)));
+    }
+}
@@ -0,0 +1,108 @@
+//! Reflection models, describing how much of the light arriving on a surface
+//! is reflected towards the eye.
+
+use crate::color::Color;
+use crate::error::ValidationError;
+use crate::vector::Vector;
+
+pub use crate::brdf::blinn_phong::BlinnPhong;
+pub use crate::brdf::ggx::Ggx;
+pub use crate::brdf::lambert::Lambert;
+pub use crate::brdf::metallic_roughness::MetallicRoughness;
+pub use crate::brdf::oren_nayar::OrenNayar;
+pub use crate::brdf::phong::Phong;
+
+mod blinn_phong;
+mod ggx;
+mod lambert;
+mod metallic_roughness;
+mod oren_nayar;
+mod phong;
+
+/// Bidirectional reflectance distribution function.
+///
+/// All vectors are normalized: `light_v` points towards the light, `eye_v`
+/// towards the eye and `normal_v` out of the surface.
+pub trait Brdf {
+    /// Fraction of the light from a source of unit intensity which is reflected
+    /// towards the eye, already accounting for the angle of incidence of the light.
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance;
+}
+
+/// Light reflected by a surface, split between its diffuse part (scaled by the
+/// color and `diffuse` coefficient of the material) and its specular part
+/// (scaled by the color of the light and the `specular` coefficient).
+/// The specular part is a color, since metals tint their reflections.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reflectance {
+    pub diffuse: f64,
+    pub specular: Color,
+}
+
+impl Reflectance {
+    pub fn none() -> Self {
+        Self {
+            diffuse: 0.0,
+            specular: Color::black(),
+        }
+    }
+
+    /// Reflectance with an untinted specular part
+    pub fn new(diffuse: f64, specular: f64) -> Self {
+        Self {
+            diffuse,
+            specular: Color::new(specular, specular, specular),
+        }
+    }
+}
+
+/// Reflection model of a material.
+/// Phong and Blinn-Phong use the `shininess` of the material.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum BrdfModel {
+    Lambert,
+    #[default]
+    Phong,
+    BlinnPhong,
+    /// Rough diffuse surfaces, `roughness` being the standard deviation
+    /// (in radians) of the angle of the microfacets
+    OrenNayar {
+        roughness: f64,
+    },
+    /// Microfacet model used by most physically based renderers, with a
+    /// perceptual `roughness` between 0 (mirror-like) and 1
+    Ggx {
+        roughness: f64,
+    },
+    /// Physically based model with glTF-style parameters, see `MetallicRoughness`.
+    /// The base color is the color of the material.
+    MetallicRoughness {
+        metallic: f64,
+        roughness: f64,
+        ior: f64,
+    },
+}
+
+impl BrdfModel {
+    /// Check that roughness, metalness and index of refraction are in range
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match *self {
+            BrdfModel::Lambert | BrdfModel::Phong | BrdfModel::BlinnPhong => Ok(()),
+            BrdfModel::OrenNayar { roughness } => {
+                ValidationError::check_minimum("roughness", roughness, 0.0)
+            }
+            BrdfModel::Ggx { roughness } => {
+                ValidationError::check_range("roughness", roughness, 0.0, 1.0)
+            }
+            BrdfModel::MetallicRoughness {
+                metallic,
+                roughness,
+                ior,
+            } => {
+                ValidationError::check_range("metallic", metallic, 0.0, 1.0)?;
+                ValidationError::check_range("roughness", roughness, 0.0, 1.0)?;
+                ValidationError::check_minimum("index of refraction", ior, 1.0)
+            }
+        }
+    }
+}
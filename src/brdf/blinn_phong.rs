@@ -0,0 +1,75 @@
+use crate::brdf::{Brdf, Reflectance};
+use crate::vector::Vector;
+
+/// Variant of the Phong model using the half vector between the light and the eye,
+/// which gives more realistic highlights at grazing angles.
+/// For a similar highlight size, the shininess needs to be about 4 times the Phong one.
+/// See also https://en.wikipedia.org/wiki/Blinn%E2%80%93Phong_reflection_model
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlinnPhong {
+    pub shininess: f64,
+}
+
+impl BlinnPhong {
+    pub fn new(shininess: f64) -> Self {
+        Self { shininess }
+    }
+}
+
+impl Brdf for BlinnPhong {
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance {
+        let light_dot_normal = light_v.dot(normal_v);
+        if light_dot_normal < 0.0 {
+            return Reflectance::none();
+        }
+
+        let half_v = (light_v + eye_v).normalize();
+        let normal_dot_half = normal_v.dot(half_v);
+
+        let specular = if normal_dot_half <= 0.0 {
+            0.0
+        } else {
+            normal_dot_half.powf(self.shininess)
+        };
+
+        Reflectance::new(light_dot_normal, specular)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+
+    #[test]
+    fn highlight_is_strongest_in_mirror_direction() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0);
+        let mirror_v = Vector::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0);
+        let off_mirror_v = Vector::new(-0.5, (0.75_f64).sqrt(), 0.0);
+        let brdf = BlinnPhong::new(100.0);
+
+        let at_mirror = brdf.reflectance(light_v, mirror_v, normal_v).specular.red;
+        let off_mirror = brdf
+            .reflectance(light_v, off_mirror_v, normal_v)
+            .specular
+            .red;
+
+        assert!((at_mirror - 1.0).abs() < 1e-9);
+        assert!(off_mirror < at_mirror);
+    }
+
+    #[test]
+    fn specular_reflectance_is_symmetric() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.3, 0.8, 0.1).normalize();
+        let eye_v = Vector::new(-0.6, 0.5, 0.2).normalize();
+        let brdf = BlinnPhong::new(20.0);
+
+        let forward = brdf.reflectance(light_v, eye_v, normal_v).specular.red;
+        let backward = brdf.reflectance(eye_v, light_v, normal_v).specular.red;
+
+        assert!((forward - backward).abs() < 1e-12);
+    }
+}
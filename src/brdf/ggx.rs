@@ -0,0 +1,237 @@
+use std::f64::consts::PI;
+
+use crate::brdf::{Brdf, Reflectance};
+use crate::color::Color;
+use crate::vector::Vector;
+
+/// Microfacet model with the GGX (Trowbridge-Reitz) distribution of normals,
+/// Smith masking-shadowing and Schlick's approximation of the Fresnel term.
+/// See also "Microfacet Models for Refraction through Rough Surfaces" (Walter et al.)
+///
+/// The specular part is the one of a dielectric with a reflectance of 4% at normal
+/// incidence, so that a `specular` coefficient of 1 gives a physically based
+/// highlight, as with `MetallicRoughness`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ggx {
+    /// Perceptual roughness, between 0 (mirror-like) and 1
+    pub roughness: f64,
+}
+
+impl Ggx {
+    /// Reflectance at normal incidence of common dielectrics (plastic, glass, ...)
+    pub const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+    /// Lower bound of `alpha`, since a perfectly smooth surface would only
+    /// reflect point lights in a single direction
+    const MIN_ALPHA: f64 = 0.001;
+
+    pub fn new(roughness: f64) -> Self {
+        Self { roughness }
+    }
+
+    /// Width of the distribution of normals, as used in the formulas
+    pub fn alpha(self) -> f64 {
+        (self.roughness * self.roughness).max(Self::MIN_ALPHA)
+    }
+
+    /// Density of microfacets with the given half vector
+    pub fn distribution(self, normal_dot_half: f64) -> f64 {
+        let alpha_squared = self.alpha().powi(2);
+        let denominator = normal_dot_half.powi(2) * (alpha_squared - 1.0) + 1.0;
+        alpha_squared / (PI * denominator * denominator)
+    }
+
+    /// Fraction of the microfacets visible from both the light and the eye
+    pub fn masking_shadowing(self, light_dot_normal: f64, eye_dot_normal: f64) -> f64 {
+        let alpha_squared = self.alpha().powi(2);
+        let g1 = |cosine: f64| {
+            2.0 * cosine
+                / (cosine + (alpha_squared + (1.0 - alpha_squared) * cosine * cosine).sqrt())
+        };
+        g1(light_dot_normal) * g1(eye_dot_normal)
+    }
+
+    /// Schlick's approximation of the Fresnel reflectance, for each color channel
+    pub fn fresnel(reflectance_at_normal: Color, eye_dot_half: f64) -> Color {
+        let factor = (1.0 - eye_dot_half).powi(5);
+        let channel = |reflectance: f64| reflectance + (1.0 - reflectance) * factor;
+        Color::new(
+            channel(reflectance_at_normal.red),
+            channel(reflectance_at_normal.green),
+            channel(reflectance_at_normal.blue),
+        )
+    }
+
+    /// Specular BRDF, multiplied by the cosine of the angle of incidence and by π
+    /// (lights being expressed in the same units as for the Phong model), for
+    /// the given reflectance at normal incidence
+    pub fn specular(
+        self,
+        light_v: Vector,
+        eye_v: Vector,
+        normal_v: Vector,
+        reflectance_at_normal: Color,
+    ) -> Color {
+        let light_dot_normal = light_v.dot(normal_v);
+        let eye_dot_normal = eye_v.dot(normal_v);
+        if light_dot_normal <= 0.0 || eye_dot_normal <= 0.0 {
+            return Color::black();
+        }
+
+        let half_v = (light_v + eye_v).normalize();
+        let distribution = self.distribution(normal_v.dot(half_v).max(0.0));
+        let masking_shadowing = self.masking_shadowing(light_dot_normal, eye_dot_normal);
+        let fresnel = Self::fresnel(reflectance_at_normal, eye_v.dot(half_v).max(0.0));
+
+        // the cosine of the angle of incidence cancels out with the one in the
+        // denominator of the microfacet BRDF
+        fresnel * (PI * distribution * masking_shadowing / (4.0 * eye_dot_normal))
+    }
+}
+
+impl Brdf for Ggx {
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance {
+        let light_dot_normal = light_v.dot(normal_v);
+        if light_dot_normal < 0.0 {
+            return Reflectance::none();
+        }
+
+        let dielectric = Self::DIELECTRIC_REFLECTANCE;
+        let specular = self.specular(
+            light_v,
+            eye_v,
+            normal_v,
+            Color::new(dielectric, dielectric, dielectric),
+        );
+
+        Reflectance {
+            diffuse: light_dot_normal,
+            specular,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::Rng;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn direction(rng: &mut Rng) -> Vector {
+        // random direction in the upper hemisphere
+        let z: f64 = rng.next_f64();
+        let angle = 2.0 * PI * rng.next_f64();
+        let radius = (1.0 - z * z).sqrt();
+        Vector::new(radius * angle.cos(), z, radius * angle.sin())
+    }
+
+    #[test]
+    fn distribution_of_normals_is_normalized() {
+        // the projected area of the microfacets is the area of the surface
+        let mut rng = Rng::seeded(3);
+        for roughness in [0.3, 0.6, 1.0] {
+            let ggx = Ggx::new(roughness);
+            let samples = 200_000;
+            let mut sum = 0.0;
+            for _ in 0..samples {
+                let cosine = direction(&mut rng).y;
+                sum += ggx.distribution(cosine) * cosine;
+            }
+            // uniform hemisphere sampling has a density of 1/2π
+            let integral = sum / samples as f64 * 2.0 * PI;
+
+            assert!((integral - 1.0).abs() < 0.05, "{roughness}: {integral}");
+        }
+    }
+
+    #[test]
+    fn specular_reflectance_is_reciprocal() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.3, 0.8, 0.1).normalize();
+        let eye_v = Vector::new(-0.6, 0.5, 0.2).normalize();
+        let ggx = Ggx::new(0.4);
+
+        // reciprocity holds for the BRDF itself, ie. without the cosine of incidence
+        let brdf = |light_v: Vector, eye_v: Vector| {
+            let reflectance_at_normal = Color::new(0.04, 0.5, 1.0);
+            ggx.specular(light_v, eye_v, normal_v, reflectance_at_normal)
+                * (1.0 / light_v.dot(normal_v))
+        };
+
+        assert_eq!(brdf(light_v, eye_v), brdf(eye_v, light_v));
+    }
+
+    #[test]
+    fn rougher_surfaces_have_wider_and_dimmer_highlights() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.6, 0.8, 0.0);
+        let mirror_v = Vector::new(-0.6, 0.8, 0.0);
+        let off_mirror_v = normal_v;
+        let smooth = Ggx::new(0.2);
+        let rough = Ggx::new(0.8);
+
+        let specular = |ggx: Ggx, eye_v| ggx.reflectance(light_v, eye_v, normal_v).specular.red;
+
+        assert!(specular(smooth, mirror_v) > specular(rough, mirror_v));
+        assert!(specular(smooth, off_mirror_v) < specular(rough, off_mirror_v));
+    }
+
+    #[test]
+    fn highlights_do_not_reflect_more_light_than_they_receive() {
+        // light reflected in all directions by the highlight, for light coming
+        // from a given direction, estimated by uniformly sampling the hemisphere
+        let mut rng = Rng::seeded(5);
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+
+        for roughness in [0.1, 0.3, 0.6, 1.0] {
+            let ggx = Ggx::new(roughness);
+            for light_v in [
+                normal_v,
+                Vector::new(0.8, 0.6, 0.0),
+                Vector::new(0.99, 0.141, 0.0).normalize(),
+            ] {
+                let samples = 100_000;
+                let mut total = 0.0;
+                for _ in 0..samples {
+                    let eye_v = direction(&mut rng);
+                    let reflectance = ggx.reflectance(light_v, eye_v, normal_v);
+                    // reflectances are scaled by π compared to the BRDF
+                    total += reflectance.specular.red / PI * eye_v.dot(normal_v);
+                }
+                let albedo = total / samples as f64 * 2.0 * PI / light_v.dot(normal_v);
+
+                assert!(albedo < 1.0, "roughness {roughness}, {light_v:?}: {albedo}");
+                if light_v == normal_v {
+                    // about the reflectance at normal incidence, minus the light
+                    // lost between microfacets
+                    assert!(albedo < 2.0 * Ggx::DIELECTRIC_REFLECTANCE, "{albedo}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn highlight_facing_the_light_is_physically_based() {
+        // the GGX distribution peaks at 1 / (π alpha²), and the masking-shadowing
+        // term is 1 along the normal
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let ggx = Ggx::new(0.3);
+
+        let specular = ggx.reflectance(normal_v, normal_v, normal_v).specular.red;
+
+        let expected = Ggx::DIELECTRIC_REFLECTANCE / (4.0 * ggx.alpha() * ggx.alpha());
+        assert!((specular - expected).abs() < 1e-9, "{specular}");
+    }
+
+    #[test]
+    fn no_light_is_reflected_below_the_surface() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.6, 0.8, 0.0);
+
+        let reflectance =
+            Ggx::new(0.5).reflectance(light_v, Vector::new(-0.6, -0.8, 0.0), normal_v);
+
+        assert_eq!(Color::black(), reflectance.specular);
+    }
+}
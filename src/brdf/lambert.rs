@@ -0,0 +1,42 @@
+use crate::brdf::{Brdf, Reflectance};
+use crate::vector::Vector;
+
+/// Ideal diffuse surface, reflecting light equally in all directions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lambert;
+
+impl Brdf for Lambert {
+    fn reflectance(&self, light_v: Vector, _eye_v: Vector, normal_v: Vector) -> Reflectance {
+        Reflectance::new(light_v.dot(normal_v).max(0.0), 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn diffuse_reflectance_is_cosine_of_angle_of_incidence() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let eye_v = Vector::new(0.0, 0.0, -1.0);
+        let light_v = Vector::new(0.0, 0.5, -(0.75_f64).sqrt());
+
+        let reflectance = Lambert.reflectance(light_v, eye_v, normal_v);
+
+        assert_eq!(0.5, reflectance.diffuse);
+        assert_eq!(Color::black(), reflectance.specular);
+    }
+
+    #[test]
+    fn light_behind_surface_is_not_reflected() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+
+        let reflectance = Lambert.reflectance(-normal_v, normal_v, normal_v);
+
+        assert_eq!(Reflectance::none(), reflectance);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::brdf::{Brdf, Ggx, Reflectance};
+use crate::color::Color;
+use crate::vector::Vector;
+
+/// Physically based model using the parameters of the glTF metallic-roughness
+/// materials: a GGX specular lobe, on top of a Lambertian diffuse lobe for
+/// dielectrics.
+///
+/// The light reflected by the specular lobe (given by the Fresnel term) is
+/// taken away from the diffuse lobe, so that the surface never reflects more
+/// light than it receives.
+/// See also https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#appendix-b-brdf-implementation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MetallicRoughness {
+    /// Diffuse color of dielectrics, or specular color of metals
+    pub base_color: Color,
+    /// 0 for dielectrics (plastic, wood, ...), 1 for metals, in between for blends
+    pub metallic: f64,
+    /// Perceptual roughness, between 0 (mirror-like) and 1
+    pub roughness: f64,
+    /// Index of refraction of dielectrics, giving their reflectance at normal incidence
+    pub ior: f64,
+}
+
+impl MetallicRoughness {
+    /// Index of refraction of most dielectrics, giving a 4% reflectance at normal incidence
+    pub const DEFAULT_IOR: f64 = 1.5;
+
+    pub fn new(base_color: Color, metallic: f64, roughness: f64, ior: f64) -> Self {
+        Self {
+            base_color,
+            metallic,
+            roughness,
+            ior,
+        }
+    }
+
+    /// Reflectance at normal incidence of a dielectric with the given index of refraction
+    pub fn dielectric_reflectance(ior: f64) -> f64 {
+        ((ior - 1.0) / (ior + 1.0)).powi(2)
+    }
+
+    /// Index of refraction of a dielectric with the given reflectance at normal incidence
+    pub fn ior_from_reflectance(reflectance: f64) -> f64 {
+        let root = reflectance.clamp(0.0, 0.99).sqrt();
+        (1.0 + root) / (1.0 - root)
+    }
+
+    /// Specular color at normal incidence: untinted for dielectrics,
+    /// the base color for metals
+    pub fn reflectance_at_normal(&self) -> Color {
+        let dielectric = Self::dielectric_reflectance(self.ior);
+        let dielectric = Color::new(dielectric, dielectric, dielectric);
+        dielectric * (1.0 - self.metallic) + self.base_color * self.metallic
+    }
+
+    /// Fraction of the light entering the surface and diffused, for light
+    /// arriving with the given angle (cosine) to the half vector or normal
+    pub fn diffuse_weight(&self, cosine: f64) -> f64 {
+        let dielectric = Self::dielectric_reflectance(self.ior);
+        let fresnel = Ggx::fresnel(Color::new(dielectric, dielectric, dielectric), cosine);
+        (1.0 - self.metallic) * (1.0 - fresnel.red)
+    }
+
+    pub fn ggx(&self) -> Ggx {
+        Ggx::new(self.roughness)
+    }
+}
+
+impl Brdf for MetallicRoughness {
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance {
+        let light_dot_normal = light_v.dot(normal_v);
+        if light_dot_normal < 0.0 {
+            return Reflectance::none();
+        }
+
+        let half_v = (light_v + eye_v).normalize();
+        let eye_dot_half = eye_v.dot(half_v).max(0.0);
+
+        Reflectance {
+            diffuse: light_dot_normal * self.diffuse_weight(eye_dot_half),
+            specular: self
+                .ggx()
+                .specular(light_v, eye_v, normal_v, self.reflectance_at_normal()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::float_eq::FloatEq;
+    use crate::random::Rng;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_ior_reflects_4_percent_at_normal_incidence() {
+        let reflectance = MetallicRoughness::dielectric_reflectance(MetallicRoughness::DEFAULT_IOR);
+
+        assert!(reflectance.float_eq(0.04));
+        assert!(MetallicRoughness::ior_from_reflectance(reflectance).float_eq(1.5));
+    }
+
+    #[test]
+    fn metals_tint_their_reflections_and_have_no_diffuse_reflection() {
+        let gold = MetallicRoughness::new(Color::new(1.0, 0.71, 0.29), 1.0, 0.3, 1.5);
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.6, 0.8, 0.0);
+        let mirror_v = Vector::new(-0.6, 0.8, 0.0);
+
+        let reflectance = gold.reflectance(light_v, mirror_v, normal_v);
+
+        assert_eq!(0.0, reflectance.diffuse);
+        assert!(reflectance.specular.red > reflectance.specular.green);
+        assert!(reflectance.specular.green > reflectance.specular.blue);
+    }
+
+    #[test]
+    fn surfaces_do_not_reflect_more_light_than_they_receive() {
+        // light reflected in all directions by a white surface, for light coming
+        // from a given direction, estimated by uniformly sampling the hemisphere
+        let mut rng = Rng::seeded(5);
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+
+        for (metallic, roughness) in [(0.0, 0.2), (0.0, 0.9), (0.5, 0.5), (1.0, 0.6)] {
+            let brdf = MetallicRoughness::new(Color::white(), metallic, roughness, 1.5);
+            for light_v in [normal_v, Vector::new(0.8, 0.6, 0.0)] {
+                let samples = 100_000;
+                let mut total = 0.0;
+                for _ in 0..samples {
+                    let cosine = rng.next_f64();
+                    let angle = 2.0 * PI * rng.next_f64();
+                    let radius = (1.0 - cosine * cosine).sqrt();
+                    let eye_v = Vector::new(radius * angle.cos(), cosine, radius * angle.sin());
+
+                    let reflectance = brdf.reflectance(light_v, eye_v, normal_v);
+                    // reflectances are scaled by π compared to the BRDF
+                    total += (reflectance.diffuse + reflectance.specular.red) / PI * cosine;
+                }
+                let albedo = total / samples as f64 * 2.0 * PI / light_v.dot(normal_v);
+
+                assert!(
+                    albedo < 1.02,
+                    "metallic {metallic}, roughness {roughness}: {albedo}"
+                );
+            }
+        }
+    }
+}
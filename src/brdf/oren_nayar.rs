@@ -0,0 +1,87 @@
+use crate::brdf::{Brdf, Reflectance};
+use crate::vector::Vector;
+
+/// Diffuse reflection from rough surfaces (eg. clay, concrete), which look flatter
+/// than Lambertian ones and reflect more light back towards its source.
+/// Uses the qualitative model from the original paper.
+/// See also https://en.wikipedia.org/wiki/Oren%E2%80%93Nayar_reflectance_model
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrenNayar {
+    /// Standard deviation (in radians) of the angle of the microfacets.
+    /// A roughness of 0 is a Lambertian surface.
+    pub roughness: f64,
+}
+
+impl OrenNayar {
+    pub fn new(roughness: f64) -> Self {
+        Self { roughness }
+    }
+}
+
+impl Brdf for OrenNayar {
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance {
+        let cos_incidence = light_v.dot(normal_v);
+        let cos_view = eye_v.dot(normal_v);
+        if cos_incidence < 0.0 || cos_view < 0.0 {
+            return Reflectance::none();
+        }
+
+        let sigma_squared = self.roughness * self.roughness;
+        let a = 1.0 - 0.5 * sigma_squared / (sigma_squared + 0.33);
+        let b = 0.45 * sigma_squared / (sigma_squared + 0.09);
+
+        // cosine of the azimuthal angle between the light and the eye, from
+        // their projections on the surface
+        let light_tangent = light_v - normal_v * cos_incidence;
+        let eye_tangent = eye_v - normal_v * cos_view;
+        let tangents_magnitude = light_tangent.magnitude() * eye_tangent.magnitude();
+        let cos_azimuth = if tangents_magnitude > 0.0 {
+            (light_tangent.dot(eye_tangent) / tangents_magnitude).max(0.0)
+        } else {
+            0.0
+        };
+
+        let theta_incidence = cos_incidence.min(1.0).acos();
+        let theta_view = cos_view.min(1.0).acos();
+        let alpha = theta_incidence.max(theta_view);
+        let beta = theta_incidence.min(theta_view);
+
+        Reflectance::new(
+            cos_incidence * (a + b * cos_azimuth * alpha.sin() * beta.tan()),
+            0.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::brdf::Lambert;
+
+    use super::*;
+
+    #[test]
+    fn smooth_surface_is_lambertian() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.3, 0.8, 0.1).normalize();
+        let eye_v = Vector::new(-0.6, 0.5, 0.2).normalize();
+
+        let oren_nayar = OrenNayar::new(0.0).reflectance(light_v, eye_v, normal_v);
+        let lambert = Lambert.reflectance(light_v, eye_v, normal_v);
+
+        assert!((oren_nayar.diffuse - lambert.diffuse).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rough_surface_reflects_more_light_back_to_its_source() {
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.8, 0.6, 0.0);
+        let brdf = OrenNayar::new(0.5);
+
+        let back_to_source = brdf.reflectance(light_v, light_v, normal_v).diffuse;
+        let forward = brdf
+            .reflectance(light_v, Vector::new(-0.8, 0.6, 0.0), normal_v)
+            .diffuse;
+
+        assert!(back_to_source > forward);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::brdf::{Brdf, Reflectance};
+use crate::vector::Vector;
+
+/// Phong reflection model: Lambertian diffuse reflection, and a specular highlight
+/// around the mirror direction of the light.
+/// See also https://en.wikipedia.org/wiki/Phong_reflection_model
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Phong {
+    /// The larger the shininess, the smaller and sharper the highlight
+    pub shininess: f64,
+}
+
+impl Phong {
+    pub fn new(shininess: f64) -> Self {
+        Self { shininess }
+    }
+}
+
+impl Brdf for Phong {
+    fn reflectance(&self, light_v: Vector, eye_v: Vector, normal_v: Vector) -> Reflectance {
+        // light_dot_normal represents the cosine of the angle between the
+        // light vector and the normal vector.
+        // A negative number means the light is on the other side of the surface.
+        let light_dot_normal = light_v.dot(normal_v);
+        if light_dot_normal < 0.0 {
+            return Reflectance::none();
+        }
+
+        // reflect_dot_eye represents the cosine of the angle between the
+        // reflection vector and the eye vector. A negative number means the
+        // light reflects away from the eye.
+        let reflect_v = (-light_v).reflect(normal_v);
+        let reflect_dot_eye = reflect_v.dot(eye_v);
+
+        let specular = if reflect_dot_eye <= 0.0 {
+            0.0
+        } else {
+            reflect_dot_eye.powf(self.shininess)
+        };
+
+        Reflectance::new(light_dot_normal, specular)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn highlight_is_strongest_in_mirror_direction() {
+        let normal_v = Vector::new(0.0, 0.0, -1.0);
+        let light_v = Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let mirror_v = Vector::new(0.0, -FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+
+        let reflectance = Phong::new(200.0).reflectance(light_v, mirror_v, normal_v);
+
+        assert!((reflectance.specular.red - 1.0).abs() < 1e-9);
+        assert!(
+            Phong::new(200.0)
+                .reflectance(light_v, normal_v, normal_v)
+                .specular
+                .red
+                < 0.001
+        );
+    }
+
+    #[test]
+    fn light_behind_surface_is_not_reflected() {
+        let normal_v = Vector::new(0.0, 0.0, -1.0);
+
+        let reflectance = Phong::new(10.0).reflectance(-normal_v, normal_v, normal_v);
+
+        assert_eq!(Reflectance::none(), reflectance);
+    }
+}
@@ -1,11 +1,19 @@
+use std::array;
 use std::num::NonZeroUsize;
-use std::sync::{Arc, Mutex};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
 
 use crate::canvas::Canvas;
-use crate::matrix::Matrix;
+use crate::color::Color;
+use crate::float::Float;
+use crate::integrator::Integrator;
+use crate::matrix::{Matrix, SingularMatrixError};
 use crate::point::Point;
+use crate::random::Rng;
 use crate::ray::Ray;
+use crate::ray_packet::{RayPacket, PACKET_SIZE};
+use crate::render_pass::RenderPass;
 use crate::world::World;
 
 // TODO: rename fields to better clarify between camera/rendered canvas
@@ -28,6 +36,20 @@ pub struct Camera {
     half_width: f64,
     /// Half the height of the camera's canvas
     half_height: f64,
+    /// Time at which the shutter opens (0 is the start of the objects' motion)
+    shutter_open: f64,
+    /// Time at which the shutter closes (1 is the end of the objects' motion)
+    shutter_close: f64,
+    /// Number of rays cast through each pixel, each at a different time
+    /// within the shutter interval
+    samples_per_pixel: usize,
+    /// Algorithm computing the color seen along each ray
+    integrator: Integrator,
+    /// Seed of the random numbers used for sampling, renders with the same seed
+    /// (and the same number of samples) are identical
+    seed: u32,
+    /// Whether to print the progress of the render
+    show_progress: bool,
 }
 
 impl Camera {
@@ -43,6 +65,12 @@ impl Camera {
             pixel_size: 0.0,
             half_width: 0.0,
             half_height: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            samples_per_pixel: 1,
+            integrator: Integrator::default(),
+            seed: 0,
+            show_progress: true,
         };
 
         camera.set_canvas_properties();
@@ -50,46 +78,208 @@ impl Camera {
         camera
     }
 
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn transform(&self) -> Matrix<4, 4> {
+        self.transform
+    }
+
+    /// Times at which the shutter opens and closes
+    pub fn shutter(&self) -> (f64, f64) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Change the size (in pixels) of the rendered canvas, keeping the field of view
+    pub fn with_size(self, hsize: usize, vsize: usize) -> Self {
+        let mut camera = Self {
+            hsize,
+            vsize,
+            ..self
+        };
+        camera.set_canvas_properties();
+        camera
+    }
+
+    /// Replace the transformation of the camera, eg. with the result of `view_transform`
+    ///
+    /// # Panics
+    ///
+    /// If the transformation can't be inverted (see `try_with_view_transform`).
+    pub fn with_view_transform(self, transform: Matrix<4, 4>) -> Self {
+        self.try_with_view_transform(transform)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `with_view_transform`, unless the transformation can't be inverted,
+    /// eg. when looking from a point to itself
+    pub fn try_with_view_transform(
+        self,
+        transform: Matrix<4, 4>,
+    ) -> Result<Self, SingularMatrixError> {
+        transform.try_inverse()?;
+        Ok(Self { transform, ..self })
+    }
+
+    /// # Panics
+    ///
+    /// If the resulting transformation can't be inverted (see `try_with_transform`).
     pub fn with_transform(self, transform_matrix: Matrix<4, 4>) -> Self {
+        self.try_with_transform(transform_matrix)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `with_transform`, unless the resulting transformation can't be inverted
+    pub fn try_with_transform(
+        self,
+        transform_matrix: Matrix<4, 4>,
+    ) -> Result<Self, SingularMatrixError> {
+        let transform = self.transform >> transform_matrix;
+        self.try_with_view_transform(transform)
+    }
+
+    /// Keep the shutter open between times `open` and `close`, so that objects
+    /// moving in that interval will look blurred.
+    /// Times are expected to be between 0 and 1.
+    pub fn with_shutter(self, open: f64, close: f64) -> Self {
         Self {
-            transform: self.transform >> transform_matrix,
+            shutter_open: open,
+            shutter_close: close,
             ..self
         }
     }
 
-    pub fn render(&self, world: World) -> Canvas {
+    /// Number of rays to average for each pixel, spread over its area when there
+    /// are several of them. More samples smooth edges and reduce the noise of
+    /// motion blur, at the cost of a slower render.
+    pub fn with_samples_per_pixel(self, samples_per_pixel: usize) -> Self {
+        assert!(samples_per_pixel > 0, "Samples per pixel must be positive");
+
+        Self {
+            samples_per_pixel,
+            ..self
+        }
+    }
+
+    pub fn with_integrator(self, integrator: Integrator) -> Self {
+        Self { integrator, ..self }
+    }
+
+    pub fn with_seed(self, seed: u32) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn with_progress(self, show_progress: bool) -> Self {
+        Self {
+            show_progress,
+            ..self
+        }
+    }
+
+    /// Render the world, tracing rays in the precision of its geometry
+    /// (see `World::cast`)
+    pub fn render<T: Float>(&self, world: World<T>) -> Canvas {
         let mut image = Canvas::new(self.hsize, self.vsize);
 
         for y in 0..self.vsize {
             self.print_progress(y);
-
-            for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at_intersection_with(ray);
-                image.write_pixel(x, y, color);
-            }
+            self.render_row(&world, y, &mut image, y);
         }
 
-        println!("\nDone.");
+        self.print_done();
         image
     }
 
-    pub fn render_parallel(&self, world: World, num_threads: Option<NonZeroUsize>) -> Canvas {
+    pub fn render_parallel<T: Float>(
+        &self,
+        world: World<T>,
+        num_threads: Option<NonZeroUsize>,
+    ) -> Canvas {
+        let mut canvases = self.render_canvases(1, num_threads, |y, canvases, row| {
+            self.render_row(&world, y, &mut canvases[0], row);
+        });
+        canvases.remove(0)
+    }
+
+    /// Render the color, together with a canvas for each of the given passes
+    /// (see `RenderPass`)
+    pub fn render_with_passes<T: Float>(
+        &self,
+        world: World<T>,
+        passes: &[RenderPass],
+        num_threads: Option<NonZeroUsize>,
+    ) -> (Canvas, Vec<Canvas>) {
+        let time = (self.shutter_open + self.shutter_close) / 2.0;
+        let mut canvases =
+            self.render_canvases(1 + passes.len(), num_threads, |y, canvases, row| {
+                let (color, pass_canvases) = canvases.split_first_mut().unwrap();
+                self.render_row(&world, y, color, row);
+                for x in 0..self.hsize {
+                    let ray = self.ray_for_pixel(x, y).with_time(time).cast();
+                    for (pass, canvas) in passes.iter().zip(pass_canvases.iter_mut()) {
+                        canvas.write_pixel(x, row, pass.value(&world, ray));
+                    }
+                }
+            });
+        let color = canvases.remove(0);
+        (color, canvases)
+    }
+
+    /// Write the colors, foreground and alpha of row y of the image in the given row of the canvas
+    fn render_row<T: Float>(&self, world: &World<T>, y: usize, canvas: &mut Canvas, row: usize) {
+        for pixels_x in self.packets_of_row() {
+            let colors = self.colors_at_pixels(world, pixels_x.clone(), y);
+            for (x, (color, foreground, alpha)) in pixels_x.zip(colors) {
+                canvas.write_pixel(x, row, color);
+                canvas.write_foreground(x, row, foreground);
+                canvas.write_alpha(x, row, alpha);
+            }
+        }
+    }
+
+    /// Render `count` canvases in parallel, `render_row(y, canvases, row)`
+    /// writing row y of the image in the given row of each canvas
+    fn render_canvases(
+        &self,
+        count: usize,
+        num_threads: Option<NonZeroUsize>,
+        render_row: impl Fn(usize, &mut [Canvas], usize) + Sync,
+    ) -> Vec<Canvas> {
         let num_threads = num_threads.unwrap_or_else(|| thread::available_parallelism().unwrap());
         let num_threads = usize::from(num_threads);
 
-        // each thread will handle a horizontal stripe of the image
+        // each thread will render a horizontal stripe of the image in its own canvases
         let chunk_size = self.vsize / num_threads;
         let last_iteration = num_threads - 1;
-        let canvas = Arc::new(Mutex::new(Canvas::new(self.hsize, self.vsize)));
+        let completed_rows = AtomicUsize::new(0);
 
-        thread::scope(|scope| {
+        let stripes = thread::scope(|scope| {
             let mut handles = vec![];
 
-            for i in 0..num_threads.into() {
-                let hsize = self.hsize;
-                let canvas = canvas.clone();
-                let world_ref = &world;
+            for i in 0..num_threads {
+                let render_row = &render_row;
+                let completed_rows = &completed_rows;
 
                 let handle = scope.spawn(move || {
                     let chunk_start = i * chunk_size;
@@ -100,36 +290,142 @@ impl Camera {
                         chunk_start + chunk_size
                     };
 
+                    let mut stripe: Vec<Canvas> = (0..count)
+                        .map(|_| Canvas::new(self.hsize, chunk_end - chunk_start))
+                        .collect();
                     for y in chunk_start..chunk_end {
-                        for x in 0..hsize {
-                            let ray = self.ray_for_pixel(x, y);
-                            let color = world_ref.color_at_intersection_with(ray);
-                            {
-                                let mut canvas = canvas.lock().unwrap();
-                                canvas.write_pixel(x, y, color);
-                            }
-                        }
+                        render_row(y, &mut stripe, y - chunk_start);
+                        self.print_progress(completed_rows.fetch_add(1, Ordering::Relaxed) + 1);
                     }
+                    (chunk_start, stripe)
                 });
                 handles.push(handle);
             }
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
         });
 
-        Arc::try_unwrap(canvas).unwrap().into_inner().unwrap()
+        let mut canvases: Vec<Canvas> = (0..count)
+            .map(|_| Canvas::new(self.hsize, self.vsize))
+            .collect();
+        for (chunk_start, stripe) in stripes {
+            for (canvas, part) in canvases.iter_mut().zip(&stripe) {
+                canvas.paste(part, 0, chunk_start);
+            }
+        }
+
+        self.print_done();
+        canvases
     }
 
+    /// Print the percentage of the image rendered, given the number of completed rows
     fn print_progress(&self, y: usize) {
+        if !self.show_progress {
+            return;
+        }
         let pixels_count = self.hsize * self.vsize;
         let progress_percentage =
             (((self.hsize * y) as f64 / pixels_count as f64) * 100_f64).round();
         print!("\rRendering... {progress_percentage}%");
     }
 
+    fn print_done(&self) {
+        if self.show_progress {
+            println!("\nDone.");
+        }
+    }
+
+    /// Ranges of neighbouring pixels of a row, traced together as ray packets
+    fn packets_of_row(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        (0..self.hsize)
+            .step_by(PACKET_SIZE)
+            .map(|start| start..(start + PACKET_SIZE).min(self.hsize))
+    }
+
+    /// Color of each pixel of row y in the given range (of at most `PACKET_SIZE`
+    /// pixels), averaged over rays cast at different times while the shutter
+    /// is open (and, depending on the integrator, through different points of
+    /// the pixel), its foreground (the same average, counting only the samples
+    /// hitting an object), and the fraction of them hitting an object (its alpha).
+    /// The samples of the pixels are traced together, as a `RayPacket`.
+    fn colors_at_pixels<T: Float>(
+        &self,
+        world: &World<T>,
+        pixels_x: Range<usize>,
+        pixel_y: usize,
+    ) -> [(Color, Color, f64); PACKET_SIZE] {
+        // lanes past the end of the range trace the last pixel again, and are
+        // left out of the results
+        let lanes = pixels_x.len();
+        let pixel_x = |lane: usize| pixels_x.start + lane.min(lanes - 1);
+        let mut rngs: [Rng; PACKET_SIZE] =
+            array::from_fn(|lane| Rng::for_pixel(u64::from(self.seed), pixel_x(lane), pixel_y));
+
+        let mut colors = [Color::black(); PACKET_SIZE];
+        let mut foregrounds = [Color::black(); PACKET_SIZE];
+        let mut covered_samples = [0; PACKET_SIZE];
+        for sample in 0..self.samples_per_pixel {
+            let rays: [Ray<T>; PACKET_SIZE] = array::from_fn(|lane| {
+                self.sample_ray(pixel_x(lane), pixel_y, sample, &mut rngs[lane])
+                    .cast()
+            });
+            let hits = RayPacket::new(rays).hits(world);
+
+            for lane in 0..lanes {
+                let color =
+                    self.integrator
+                        .color_at_hit(world, rays[lane], hits[lane], &mut rngs[lane]);
+                if hits[lane].is_some() {
+                    covered_samples[lane] += 1;
+                    foregrounds[lane] += color;
+                }
+                colors[lane] += color;
+            }
+        }
+
+        let samples = self.samples_per_pixel as f64;
+        array::from_fn(|lane| {
+            (
+                colors[lane] * (1.0 / samples),
+                foregrounds[lane] * (1.0 / samples),
+                covered_samples[lane] as f64 / samples,
+            )
+        })
+    }
+
+    /// Ray of the given sample of pixel (x, y)
+    fn sample_ray(&self, pixel_x: usize, pixel_y: usize, sample: usize, rng: &mut Rng) -> Ray {
+        // spread samples evenly over the shutter interval, with some jitter
+        // to avoid banding in the blurred objects
+        let shutter_duration = self.shutter_close - self.shutter_open;
+        let offset = (sample as f64 + rng.next_f64()) / self.samples_per_pixel as f64;
+        let time = self.shutter_open + shutter_duration * offset;
+
+        // a single sample goes through the center of the pixel, while several
+        // samples are spread over its area to smooth edges
+        let ray = if self.samples_per_pixel > 1 {
+            let (offset_x, offset_y) = (rng.next_f64(), rng.next_f64());
+            self.ray_through(pixel_x as f64 + offset_x, pixel_y as f64 + offset_y)
+        } else {
+            self.ray_for_pixel(pixel_x, pixel_y)
+        };
+        ray.with_time(time)
+    }
+
     /// Builds a ray that starts from the camera and passes through pixel (x, y) on the canvas
     fn ray_for_pixel(&self, pixel_x: usize, pixel_y: usize) -> Ray {
-        // offsets from the edges of canvas to the pixel's center
-        let x_offset = (pixel_x as f64 + 0.5) * self.pixel_size;
-        let y_offset = (pixel_y as f64 + 0.5) * self.pixel_size;
+        self.ray_through(pixel_x as f64 + 0.5, pixel_y as f64 + 0.5)
+    }
+
+    /// Builds a ray that starts from the camera and passes through the given
+    /// point of the canvas, in (fractional) pixels from its top left corner
+    fn ray_through(&self, canvas_x: f64, canvas_y: f64) -> Ray {
+        // offsets from the edges of canvas to the point
+        let x_offset = canvas_x * self.pixel_size;
+        let y_offset = canvas_y * self.pixel_size;
 
         // untransformed coordinates of the pixel in world space
         // (camera looks toward -z, so +x is on the left)
@@ -169,7 +465,11 @@ mod tests {
 
     use crate::color::Color;
     use crate::float_eq::FloatEq;
-    use crate::matrix::transformations;
+    use crate::integrator::Integrator;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::matrix::{transformations, Matrix};
+    use crate::object::Object;
     use crate::point::Point;
     use crate::vector::Vector;
     use crate::view_transform;
@@ -249,4 +549,218 @@ mod tests {
 
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), image.pixel_at(5, 5))
     }
+
+    #[test]
+    fn changing_size_of_camera_keeps_field_of_view() {
+        let camera = Camera::new(125, 200, PI / 2.0).with_size(200, 125);
+
+        assert_eq!(200, camera.hsize());
+        assert_eq!(125, camera.vsize());
+        assert_eq!(PI / 2.0, camera.field_of_view());
+        assert!(0.01_f64.float_eq(camera.pixel_size))
+    }
+
+    #[test]
+    fn parallel_render_matches_sequential_render() {
+        let world = World::default();
+        let camera = Camera::new(11, 7, PI / 2.0)
+            .with_transform(view_transform::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_progress(false);
+
+        let sequential = camera.render(world.clone());
+        let parallel = camera.render_parallel(world, NonZeroUsize::new(3));
+
+        for y in 0..7 {
+            for x in 0..11 {
+                assert_eq!(sequential.pixel_at(x, y), parallel.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_is_the_coverage_of_pixels() {
+        let camera = || {
+            Camera::new(11, 7, PI / 2.0)
+                .with_transform(view_transform::view_transform(
+                    Point::new(0.0, 0.0, -5.0),
+                    Point::origin(),
+                    Vector::new(0.0, 1.0, 0.0),
+                ))
+                .with_progress(false)
+        };
+
+        let image = camera().render(World::default());
+
+        assert_eq!(1.0, image.alpha_at(5, 3));
+        assert_eq!(0.0, image.alpha_at(0, 0));
+
+        // jittered samples partially cover the pixels on the edge of the sphere,
+        // whatever the integrator
+        for integrator in [Integrator::Whitted, Integrator::PathTracing] {
+            let image = camera()
+                .with_integrator(integrator)
+                .with_samples_per_pixel(16)
+                .render(World::default());
+            let edge_alpha = image.alpha_at(4, 2);
+            assert!(edge_alpha > 0.0 && edge_alpha < 1.0, "{edge_alpha}");
+        }
+    }
+
+    #[test]
+    fn worlds_render_the_same_in_single_precision() {
+        let mut world = World::default();
+        world.objects.push(Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material::default(),
+        ));
+        let camera = Camera::new(11, 7, PI / 2.0)
+            .with_transform(view_transform::view_transform(
+                Point::new(0.0, 1.5, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_progress(false);
+
+        let double = camera.render(world.clone());
+        let single = camera.render(world.cast::<f32>());
+
+        for y in 0..7 {
+            for x in 0..11 {
+                let (expected, actual) = (double.pixel_at(x, y), single.pixel_at(x, y));
+                for (expected, actual) in [
+                    (expected.red, actual.red),
+                    (expected.green, actual.green),
+                    (expected.blue, actual.blue),
+                ] {
+                    assert!((expected - actual).abs() < 0.001, "({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn render_with_passes_renders_color_and_each_pass() {
+        let world = World::default();
+        let camera = Camera::new(11, 7, PI / 2.0)
+            .with_transform(view_transform::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_progress(false);
+        let passes = [RenderPass::Depth, RenderPass::ObjectId];
+
+        let color = camera.render_parallel(world.clone(), NonZeroUsize::new(2));
+        let (with_passes, pass_canvases) =
+            camera.render_with_passes(world.clone(), &passes, NonZeroUsize::new(2));
+
+        assert_eq!(2, pass_canvases.len());
+        for y in 0..7 {
+            for x in 0..11 {
+                assert_eq!(color.pixel_at(x, y), with_passes.pixel_at(x, y));
+                for (pass, canvas) in passes.iter().zip(&pass_canvases) {
+                    let expected = pass.value(&world, camera.ray_for_pixel(x, y));
+                    assert_eq!(
+                        format!("{expected:?}"),
+                        format!("{:?}", canvas.pixel_at(x, y))
+                    );
+                }
+            }
+        }
+        // the outer sphere of the default world hides the inner one
+        assert_eq!(Color::black(), pass_canvases[1].pixel_at(5, 3));
+    }
+
+    #[test]
+    fn camera_shutter_is_closed_at_time_zero_by_default() {
+        let camera = Camera::new(11, 11, PI / 2.0);
+
+        assert_eq!(0.0, camera.shutter_open);
+        assert_eq!(0.0, camera.shutter_close);
+        assert_eq!(1, camera.samples_per_pixel);
+    }
+
+    #[test]
+    fn object_moving_while_shutter_is_open_is_blurred() {
+        let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::white());
+        let sphere = Object::sphere(
+            Matrix::identity(),
+            Material {
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            },
+        )
+        .with_motion(transformations::translation(4.0, 0.0, 0.0));
+        let world = World::new(vec![light], vec![sphere]);
+        let camera = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_shutter(0.0, 1.0)
+            .with_samples_per_pixel(16);
+
+        let (color, _, alpha) = camera.colors_at_pixels(&world, 5..6, 5)[0];
+
+        // the sphere covers the center of the image only for part of the exposure
+        assert!(color.red > 0.1 && color.red < 0.9);
+        assert!(alpha > 0.1 && alpha < 0.9);
+    }
+
+    #[test]
+    fn multiple_samples_of_static_world_are_spread_over_the_pixel() {
+        let world = World::default();
+        let camera = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_shutter(0.0, 1.0)
+            .with_samples_per_pixel(4);
+
+        let (color, _, alpha) = camera.colors_at_pixels(&world, 5..6, 5)[0];
+
+        // close to the color at the center of the pixel, without being equal to it
+        let center = Color::new(0.38066, 0.47583, 0.2855);
+        assert_ne!(center, color);
+        assert!((color.red - center.red).abs() < 0.05, "{color:?}");
+        assert!((color.green - center.green).abs() < 0.05, "{color:?}");
+        assert_eq!(1.0, alpha);
+    }
+
+    #[test]
+    fn path_traced_renders_are_reproducible_for_a_given_seed() {
+        let camera = |seed| {
+            Camera::new(6, 4, PI / 2.0)
+                .with_transform(view_transform::view_transform(
+                    Point::new(0.0, 0.0, -5.0),
+                    Point::origin(),
+                    Vector::new(0.0, 1.0, 0.0),
+                ))
+                .with_integrator(Integrator::PathTracing)
+                .with_samples_per_pixel(4)
+                .with_seed(seed)
+                .with_progress(false)
+        };
+
+        let render = |camera: Camera| format!("{:?}", camera.render(World::default()));
+        let parallel_render = |camera: Camera| {
+            format!(
+                "{:?}",
+                camera.render_parallel(World::default(), NonZeroUsize::new(3))
+            )
+        };
+
+        assert_eq!(render(camera(7)), render(camera(7)));
+        assert_eq!(render(camera(7)), parallel_render(camera(7)));
+        assert_ne!(render(camera(7)), render(camera(8)));
+    }
 }
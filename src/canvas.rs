@@ -1,17 +1,33 @@
 use crate::color::Color;
 
+pub use crate::canvas::operations::{BlendMode, ResizeFilter};
+pub use crate::canvas::statistics::{Histogram, Statistics};
+
+mod operations;
+mod statistics;
+
+/// Image of `width` by `height` pixels, each with a color and an alpha
+/// (its opacity, or the fraction of it covered by objects in renders)
 #[derive(Debug)]
 pub struct Canvas {
     pixels: Vec<Vec<Color>>,
+    alpha: Vec<Vec<f64>>,
+    /// Color of the objects alone in renders, without the background and
+    /// premultiplied by the alpha (allocated when first written)
+    foreground: Option<Vec<Vec<Color>>>,
     width: usize,
     height: usize,
 }
 
 impl Canvas {
+    /// Canvas filled with opaque black pixels
     pub fn new(width: usize, height: usize) -> Self {
         let pixels = vec![vec![Color::black(); width]; height];
+        let alpha = vec![vec![1.0; width]; height];
         Self {
             pixels,
+            alpha,
+            foreground: None,
             width,
             height,
         }
@@ -34,9 +50,52 @@ impl Canvas {
         self
     }
 
-    pub fn iter_rows(&self) -> std::slice::Iter<Vec<Color>> {
+    pub fn alpha_at(&self, x: usize, y: usize) -> f64 {
+        self.alpha[y][x]
+    }
+
+    /// Set the alpha of a pixel, between 0 (transparent) and 1 (opaque)
+    pub fn write_alpha(&mut self, x: usize, y: usize, alpha: f64) -> &Self {
+        self.alpha[y][x] = alpha;
+        self
+    }
+
+    /// Color of the objects covering a pixel, without the background and
+    /// premultiplied by its alpha. It is the color of the pixel when it wasn't
+    /// written, as in renders over a black background.
+    pub fn foreground_at(&self, x: usize, y: usize) -> Color {
+        match &self.foreground {
+            Some(foreground) => foreground[y][x],
+            None => self.pixels[y][x],
+        }
+    }
+
+    pub fn write_foreground(&mut self, x: usize, y: usize, color: Color) -> &Self {
+        if self.foreground.is_none() {
+            let foreground = (0..self.height)
+                .map(|y| (0..self.width).map(|x| self.foreground_at(x, y)).collect())
+                .collect();
+            self.foreground = Some(foreground);
+        }
+        if let Some(foreground) = &mut self.foreground {
+            foreground[y][x] = color;
+        }
+        self
+    }
+
+    /// Whether some pixels aren't fully opaque
+    pub fn has_transparency(&self) -> bool {
+        self.alpha.iter().flatten().any(|alpha| *alpha < 1.0)
+    }
+
+    pub fn iter_rows(&self) -> std::slice::Iter<'_, Vec<Color>> {
         self.pixels.iter()
     }
+
+    /// Alpha of the pixels, row by row
+    pub fn iter_alpha_rows(&self) -> std::slice::Iter<'_, Vec<f64>> {
+        self.alpha.iter()
+    }
 }
 
 #[cfg(test)]
@@ -53,8 +112,10 @@ mod tests {
         for x in 0..10 {
             for y in 0..20 {
                 assert_eq!(Color::black(), canvas.pixel_at(x, y));
+                assert_eq!(1.0, canvas.alpha_at(x, y));
             }
         }
+        assert!(!canvas.has_transparency());
     }
 
     #[test]
@@ -66,4 +127,31 @@ mod tests {
 
         assert_eq!(red, canvas.pixel_at(2, 3));
     }
+
+    #[test]
+    fn writing_alpha_to_canvas() {
+        let mut canvas = Canvas::new(10, 20);
+
+        canvas.write_alpha(2, 3, 0.25);
+
+        assert_eq!(0.25, canvas.alpha_at(2, 3));
+        assert_eq!(1.0, canvas.alpha_at(3, 2));
+        assert!(canvas.has_transparency());
+    }
+
+    #[test]
+    fn foreground_is_color_until_written() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(0.5, 1.0, 0.0));
+        canvas.write_alpha(0, 0, 0.5);
+        canvas.write_pixel(1, 0, Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(Color::new(0.5, 1.0, 0.0), canvas.foreground_at(0, 0));
+
+        canvas.write_foreground(1, 0, Color::new(0.0, 0.0, 0.5));
+
+        assert_eq!(Color::new(0.5, 1.0, 0.0), canvas.foreground_at(0, 0));
+        assert_eq!(Color::new(0.0, 0.0, 0.5), canvas.foreground_at(1, 0));
+        assert_eq!(Color::new(1.0, 1.0, 1.0), canvas.pixel_at(1, 0));
+    }
 }
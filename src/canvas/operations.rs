@@ -0,0 +1,366 @@
+//! Image processing on whole canvases, returning new canvases.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+
+/// Interpolation of the pixels when resizing a canvas
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ResizeFilter {
+    /// Average of the source pixels covered by each pixel, best for thumbnails
+    #[default]
+    Box,
+    /// Interpolation between the four nearest source pixels, smoother when enlarging
+    Bilinear,
+}
+
+/// Combination of a layer with the pixels of a canvas.
+///
+/// The arithmetic modes combine the colors and keep the alpha of the canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendMode {
+    Add,
+    /// The layer is subtracted from the canvas
+    Subtract,
+    Multiply,
+    /// Linear interpolation of the colors and alpha, from the canvas (0) to the layer (1)
+    Mix(f64),
+    /// The layer is composited over the canvas, using its alpha.
+    /// Colors are premultiplied by the alpha, as in renders over a black background.
+    Over,
+}
+
+impl Canvas {
+    /// Canvas built from the color and alpha of each of its pixels
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        pixel: impl Fn(usize, usize) -> (Color, f64),
+    ) -> Self {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let (color, alpha) = pixel(x, y);
+                canvas.pixels[y][x] = color;
+                canvas.alpha[y][x] = alpha;
+            }
+        }
+        canvas
+    }
+
+    /// Rectangle of `width` by `height` pixels, starting from pixel (x, y),
+    /// with their foreground.
+    ///
+    /// # Panics
+    ///
+    /// If the rectangle doesn't fit in the canvas.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Canvas {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "Can't crop {width}x{height} pixels at ({x}, {y}) from a {}x{} canvas",
+            self.width,
+            self.height
+        );
+        let mut cropped = Canvas::from_fn(width, height, |crop_x, crop_y| {
+            self.pixel_and_alpha(x + crop_x, y + crop_y)
+        });
+        if self.foreground.is_some() {
+            for crop_y in 0..height {
+                for crop_x in 0..width {
+                    cropped.write_foreground(
+                        crop_x,
+                        crop_y,
+                        self.foreground_at(x + crop_x, y + crop_y),
+                    );
+                }
+            }
+        }
+        cropped
+    }
+
+    /// Copy the pixels of `source` (color, alpha and foreground) with its top left corner
+    /// at pixel (x, y). Pixels falling outside the canvas are ignored.
+    pub fn paste(&mut self, source: &Canvas, x: usize, y: usize) {
+        let width = source.width.min(self.width.saturating_sub(x));
+        let height = source.height.min(self.height.saturating_sub(y));
+        for source_y in 0..height {
+            for source_x in 0..width {
+                self.pixels[y + source_y][x + source_x] = source.pixels[source_y][source_x];
+                self.alpha[y + source_y][x + source_x] = source.alpha[source_y][source_x];
+                if source.foreground.is_some() {
+                    self.write_foreground(
+                        x + source_x,
+                        y + source_y,
+                        source.foreground_at(source_x, source_y),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn resize(&self, width: usize, height: usize, filter: ResizeFilter) -> Canvas {
+        let scale_x = self.width as f64 / width as f64;
+        let scale_y = self.height as f64 / height as f64;
+        match filter {
+            ResizeFilter::Box => Canvas::from_fn(width, height, |x, y| {
+                let columns = overlaps(x as f64 * scale_x, (x + 1) as f64 * scale_x);
+                let rows = overlaps(y as f64 * scale_y, (y + 1) as f64 * scale_y);
+
+                let (mut color, mut alpha, mut total_weight) = (Color::black(), 0.0, 0.0);
+                for &(source_y, row_weight) in &rows {
+                    for &(source_x, column_weight) in &columns {
+                        let weight = row_weight * column_weight;
+                        let (source_color, source_alpha) = self.pixel_and_alpha(
+                            source_x.min(self.width - 1),
+                            source_y.min(self.height - 1),
+                        );
+                        color += source_color * weight;
+                        alpha += source_alpha * weight;
+                        total_weight += weight;
+                    }
+                }
+                (color * (1.0 / total_weight), alpha / total_weight)
+            }),
+            ResizeFilter::Bilinear => Canvas::from_fn(width, height, |x, y| {
+                // centers of the pixels are aligned in both canvases
+                let source_x =
+                    ((x as f64 + 0.5) * scale_x - 0.5).clamp(0.0, (self.width - 1) as f64);
+                let source_y =
+                    ((y as f64 + 0.5) * scale_y - 0.5).clamp(0.0, (self.height - 1) as f64);
+                let (x0, y0) = (source_x.floor() as usize, source_y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                let (tx, ty) = (source_x - x0 as f64, source_y - y0 as f64);
+
+                let lerp =
+                    |(color_0, alpha_0): (Color, f64), (color_1, alpha_1): (Color, f64), t: f64| {
+                        (
+                            color_0 * (1.0 - t) + color_1 * t,
+                            alpha_0 * (1.0 - t) + alpha_1 * t,
+                        )
+                    };
+                let top = lerp(
+                    self.pixel_and_alpha(x0, y0),
+                    self.pixel_and_alpha(x1, y0),
+                    tx,
+                );
+                let bottom = lerp(
+                    self.pixel_and_alpha(x0, y1),
+                    self.pixel_and_alpha(x1, y1),
+                    tx,
+                );
+                lerp(top, bottom, ty)
+            }),
+        }
+    }
+
+    /// Mirror image, left to right
+    pub fn flip_horizontal(&self) -> Canvas {
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            self.pixel_and_alpha(self.width - 1 - x, y)
+        })
+    }
+
+    /// Upside down image
+    pub fn flip_vertical(&self) -> Canvas {
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            self.pixel_and_alpha(x, self.height - 1 - y)
+        })
+    }
+
+    /// Canvas with `f` applied to the color of every pixel, keeping their alpha
+    pub fn map(&self, f: impl Fn(Color) -> Color) -> Canvas {
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            let (color, alpha) = self.pixel_and_alpha(x, y);
+            (f(color), alpha)
+        })
+    }
+
+    /// Combine `layer` with the canvas, pixel by pixel.
+    ///
+    /// # Panics
+    ///
+    /// If the canvases have different sizes.
+    pub fn blend(&self, layer: &Canvas, mode: BlendMode) -> Canvas {
+        assert!(
+            self.width == layer.width && self.height == layer.height,
+            "Can't blend a {}x{} layer with a {}x{} canvas",
+            layer.width,
+            layer.height,
+            self.width,
+            self.height
+        );
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            let (color, alpha) = self.pixel_and_alpha(x, y);
+            let (layer_color, layer_alpha) = layer.pixel_and_alpha(x, y);
+            match mode {
+                BlendMode::Add => (color + layer_color, alpha),
+                BlendMode::Subtract => (color - layer_color, alpha),
+                BlendMode::Multiply => (color * layer_color, alpha),
+                BlendMode::Mix(t) => (
+                    color * (1.0 - t) + layer_color * t,
+                    alpha * (1.0 - t) + layer_alpha * t,
+                ),
+                BlendMode::Over => (
+                    layer_color + color * (1.0 - layer_alpha),
+                    layer_alpha + alpha * (1.0 - layer_alpha),
+                ),
+            }
+        })
+    }
+
+    fn pixel_and_alpha(&self, x: usize, y: usize) -> (Color, f64) {
+        (self.pixels[y][x], self.alpha[y][x])
+    }
+}
+
+/// Pixels overlapping the interval between `start` and `end` (in pixels),
+/// with the length of their overlap
+fn overlaps(start: f64, end: f64) -> Vec<(usize, f64)> {
+    (start.floor() as usize..end.ceil() as usize)
+        .map(|pixel| {
+            let overlap = end.min(pixel as f64 + 1.0) - start.max(pixel as f64);
+            (pixel, overlap)
+        })
+        .filter(|(_, overlap)| *overlap > 0.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    /// Canvas whose pixels are gray levels increasing from left to right and
+    /// top to bottom: (0, 1, 2) on the first row, (3, 4, 5) on the second...
+    fn numbered(width: usize, height: usize) -> Canvas {
+        Canvas::from_fn(width, height, |x, y| (gray((y * width + x) as f64), 1.0))
+    }
+
+    fn gray(value: f64) -> Color {
+        Color::new(value, value, value)
+    }
+
+    fn grays(canvas: &Canvas) -> Vec<Vec<f64>> {
+        canvas
+            .iter_rows()
+            .map(|row| row.iter().map(|pixel| pixel.red).collect())
+            .collect()
+    }
+
+    #[test]
+    fn cropping_keeps_a_rectangle() {
+        let mut canvas = numbered(4, 3);
+        canvas.write_alpha(2, 2, 0.5);
+
+        let cropped = canvas.crop(1, 1, 2, 2);
+
+        assert_eq!(vec![vec![5.0, 6.0], vec![9.0, 10.0]], grays(&cropped));
+        assert_eq!(0.5, cropped.alpha_at(1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't crop 2x2 pixels at (3, 0) from a 4x3 canvas")]
+    fn cropping_outside_of_canvas_panics() {
+        numbered(4, 3).crop(3, 0, 2, 2);
+    }
+
+    #[test]
+    fn pasting_is_clipped_to_canvas() {
+        let mut canvas = Canvas::new(3, 2);
+        let mut source = numbered(2, 2);
+        source.write_alpha(0, 0, 0.0);
+
+        canvas.paste(&source, 2, 1);
+
+        assert_eq!(
+            vec![vec![0.0, 0.0, 0.0], vec![0.0, 0.0, 0.0]],
+            grays(&canvas)
+        );
+        assert_eq!(0.0, canvas.alpha_at(2, 1));
+        assert_eq!(1.0, canvas.alpha_at(1, 1));
+    }
+
+    #[test]
+    fn box_filter_averages_covered_pixels() {
+        let shrunk = numbered(4, 2).resize(2, 1, ResizeFilter::Box);
+
+        // (0 + 1 + 4 + 5) / 4 and (2 + 3 + 6 + 7) / 4
+        assert_eq!(vec![vec![2.5, 4.5]], grays(&shrunk));
+    }
+
+    #[test]
+    fn box_filter_weights_partially_covered_pixels() {
+        let shrunk = numbered(3, 1).resize(2, 1, ResizeFilter::Box);
+
+        // 0 and half of 1, half of 1 and 2
+        assert_eq!(gray(0.5 / 1.5), shrunk.pixel_at(0, 0));
+        assert_eq!(gray(2.5 / 1.5), shrunk.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn bilinear_filter_interpolates_between_pixels() {
+        let enlarged = numbered(2, 1).resize(4, 1, ResizeFilter::Bilinear);
+
+        assert_eq!(vec![vec![0.0, 0.25, 0.75, 1.0]], grays(&enlarged));
+    }
+
+    #[test_case(ResizeFilter::Box)]
+    #[test_case(ResizeFilter::Bilinear)]
+    fn resizing_to_same_size_keeps_pixels(filter: ResizeFilter) {
+        let canvas = numbered(3, 2);
+
+        assert_eq!(grays(&canvas), grays(&canvas.resize(3, 2, filter)));
+    }
+
+    #[test]
+    fn flipping_mirrors_pixels() {
+        let canvas = numbered(3, 2);
+
+        assert_eq!(
+            vec![vec![2.0, 1.0, 0.0], vec![5.0, 4.0, 3.0]],
+            grays(&canvas.flip_horizontal())
+        );
+        assert_eq!(
+            vec![vec![3.0, 4.0, 5.0], vec![0.0, 1.0, 2.0]],
+            grays(&canvas.flip_vertical())
+        );
+    }
+
+    #[test]
+    fn mapping_colors_keeps_alpha() {
+        let mut canvas = numbered(2, 1);
+        canvas.write_alpha(1, 0, 0.25);
+
+        let doubled = canvas.map(|color| color * 2.0);
+
+        assert_eq!(vec![vec![0.0, 2.0]], grays(&doubled));
+        assert_eq!(0.25, doubled.alpha_at(1, 0));
+    }
+
+    #[test_case(BlendMode::Add, 2.5, 1.0)]
+    #[test_case(BlendMode::Subtract, 1.5, 1.0)]
+    #[test_case(BlendMode::Multiply, 1.0, 1.0)]
+    #[test_case(BlendMode::Mix(0.25), 1.625, 0.875)]
+    #[test_case(BlendMode::Over, 1.5, 1.0)]
+    fn blending_layers(mode: BlendMode, expected_gray: f64, expected_alpha: f64) {
+        let canvas = Canvas::from_fn(1, 1, |_, _| (gray(2.0), 1.0));
+        let layer = Canvas::from_fn(1, 1, |_, _| (gray(0.5), 0.5));
+
+        let blended = canvas.blend(&layer, mode);
+
+        assert_eq!(gray(expected_gray), blended.pixel_at(0, 0));
+        assert_eq!(expected_alpha, blended.alpha_at(0, 0));
+    }
+
+    #[test]
+    fn layers_are_composited_over_transparent_pixels() {
+        let canvas = Canvas::from_fn(1, 1, |_, _| (Color::black(), 0.0));
+        let layer = Canvas::from_fn(1, 1, |_, _| (gray(0.25), 0.5));
+
+        let blended = canvas.blend(&layer, BlendMode::Over);
+
+        assert_eq!(gray(0.25), blended.pixel_at(0, 0));
+        assert_eq!(0.5, blended.alpha_at(0, 0));
+    }
+}
@@ -0,0 +1,120 @@
+//! Summaries of the brightness of the pixels of a canvas, eg. to choose an exposure.
+
+use std::ops::Range;
+
+use crate::canvas::Canvas;
+
+/// Luminance (see `Color::luminance`) of the pixels of a canvas
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Statistics {
+    pub min_luminance: f64,
+    pub max_luminance: f64,
+    pub mean_luminance: f64,
+}
+
+/// Number of pixels of a canvas in successive, evenly sized ranges of luminance
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub counts: Vec<usize>,
+    pub range: Range<f64>,
+}
+
+impl Histogram {
+    /// Range of luminance of the pixels counted in a bin
+    pub fn bin_range(&self, bin: usize) -> Range<f64> {
+        let width = (self.range.end - self.range.start) / self.counts.len() as f64;
+        let start = self.range.start + width * bin as f64;
+        start..start + width
+    }
+}
+
+impl Canvas {
+    /// # Panics
+    ///
+    /// If the canvas is empty.
+    pub fn statistics(&self) -> Statistics {
+        assert!(
+            self.width > 0 && self.height > 0,
+            "Can't compute statistics of an empty canvas"
+        );
+        let mut statistics = Statistics {
+            min_luminance: f64::INFINITY,
+            max_luminance: f64::NEG_INFINITY,
+            mean_luminance: 0.0,
+        };
+        for luminance in self.luminances() {
+            statistics.min_luminance = statistics.min_luminance.min(luminance);
+            statistics.max_luminance = statistics.max_luminance.max(luminance);
+            statistics.mean_luminance += luminance;
+        }
+        statistics.mean_luminance /= (self.width * self.height) as f64;
+        statistics
+    }
+
+    /// Histogram of the luminance of the pixels over `range`, split in `bins`.
+    /// Pixels outside of the range are counted in the first or last bin.
+    ///
+    /// # Panics
+    ///
+    /// If there are no bins, or the range is empty.
+    pub fn luminance_histogram(&self, bins: usize, range: Range<f64>) -> Histogram {
+        assert!(bins > 0, "A histogram needs at least one bin");
+        assert!(range.start < range.end, "Empty histogram range {range:?}");
+
+        let mut counts = vec![0; bins];
+        let width = (range.end - range.start) / bins as f64;
+        for luminance in self.luminances() {
+            let bin = ((luminance - range.start) / width).floor();
+            counts[(bin.max(0.0) as usize).min(bins - 1)] += 1;
+        }
+        Histogram { counts, range }
+    }
+
+    fn luminances(&self) -> impl Iterator<Item = f64> + '_ {
+        self.pixels.iter().flatten().map(|pixel| pixel.luminance())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn canvas(grays: &[f64]) -> Canvas {
+        Canvas::from_fn(grays.len(), 1, |x, _| {
+            (Color::new(grays[x], grays[x], grays[x]), 1.0)
+        })
+    }
+
+    #[test]
+    fn statistics_of_luminance() {
+        let statistics = canvas(&[0.25, 1.0, 4.0, 0.75]).statistics();
+
+        assert_eq!(
+            Statistics {
+                min_luminance: 0.25,
+                max_luminance: 4.0,
+                mean_luminance: 1.5,
+            },
+            statistics
+        );
+    }
+
+    #[test]
+    fn histogram_counts_pixels_in_bins() {
+        let histogram = canvas(&[0.1, 0.3, 0.35, 0.9, 0.5]).luminance_histogram(4, 0.0..1.0);
+
+        assert_eq!(vec![1, 2, 1, 1], histogram.counts);
+        assert_eq!(0.5..0.75, histogram.bin_range(2));
+    }
+
+    #[test]
+    fn histogram_clamps_pixels_out_of_range() {
+        let histogram = canvas(&[-1.0, 0.5, 3.0, 8.0]).luminance_histogram(2, 0.0..2.0);
+
+        assert_eq!(vec![2, 2], histogram.counts);
+    }
+}
@@ -1,32 +1,33 @@
+use crate::float::Float;
 use crate::float_eq::FloatEq;
 use std::ops;
 
 #[derive(Copy, Clone, Debug)]
-pub struct Color {
-    pub red: f64,
-    pub green: f64,
-    pub blue: f64,
+pub struct Color<T: Float = f64> {
+    pub red: T,
+    pub green: T,
+    pub blue: T,
 }
 
-impl Color {
-    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+impl<T: Float> Color<T> {
+    pub fn new(red: T, green: T, blue: T) -> Self {
         Self { red, green, blue }
     }
 
     pub fn black() -> Self {
-        Color::new(0.0, 0.0, 0.0)
+        Color::new(T::ZERO, T::ZERO, T::ZERO)
     }
 
     pub fn white() -> Self {
-        Color::new(1.0, 1.0, 1.0)
+        Color::new(T::ONE, T::ONE, T::ONE)
     }
 
     pub fn red() -> Self {
-        Color::new(1.0, 0.0, 0.0)
+        Color::new(T::ONE, T::ZERO, T::ZERO)
     }
 
     /// Scale color values between `min` and `max`
-    pub fn scale(&self, min: f64, max: f64) -> Self {
+    pub fn scale(&self, min: T, max: T) -> Self {
         Self {
             red: Self::scale_component(self.red, min, max),
             green: Self::scale_component(self.green, min, max),
@@ -34,12 +35,19 @@ impl Color {
         }
     }
 
-    fn scale_component(component: f64, min: f64, max: f64) -> f64 {
-        min + (component.clamp(0.0, 1.0) * (max - min))
+    /// Perceived brightness of the color, with the Rec. 709 weights of the sRGB primaries
+    pub fn luminance(&self) -> T {
+        T::from_f64(0.2126) * self.red
+            + T::from_f64(0.7152) * self.green
+            + T::from_f64(0.0722) * self.blue
+    }
+
+    fn scale_component(component: T, min: T, max: T) -> T {
+        min + (component.clamp(T::ZERO, T::ONE) * (max - min))
     }
 }
 
-impl PartialEq for Color {
+impl<T: Float> PartialEq for Color<T> {
     fn eq(&self, other: &Self) -> bool {
         self.red.float_eq(other.red)
             && self.green.float_eq(other.green)
@@ -47,8 +55,8 @@ impl PartialEq for Color {
     }
 }
 
-impl ops::Add for Color {
-    type Output = Color;
+impl<T: Float> ops::Add for Color<T> {
+    type Output = Color<T>;
 
     fn add(self, other: Self) -> Self::Output {
         Self::new(
@@ -59,7 +67,7 @@ impl ops::Add for Color {
     }
 }
 
-impl ops::AddAssign for Color {
+impl<T: Float> ops::AddAssign for Color<T> {
     fn add_assign(&mut self, other: Self) {
         self.red += other.red;
         self.green += other.green;
@@ -67,8 +75,8 @@ impl ops::AddAssign for Color {
     }
 }
 
-impl ops::Sub for Color {
-    type Output = Color;
+impl<T: Float> ops::Sub for Color<T> {
+    type Output = Color<T>;
 
     fn sub(self, other: Self) -> Self::Output {
         Self::new(
@@ -79,18 +87,18 @@ impl ops::Sub for Color {
     }
 }
 
-impl ops::Mul<f64> for Color {
-    type Output = Color;
+impl<T: Float> ops::Mul<T> for Color<T> {
+    type Output = Color<T>;
 
-    fn mul(self, factor: f64) -> Self::Output {
+    fn mul(self, factor: T) -> Self::Output {
         Color::new(self.red * factor, self.green * factor, self.blue * factor)
     }
 }
 
 /// Blends two colors together.
 /// Aka Hadamar product, or Schur product
-impl ops::Mul for Color {
-    type Output = Color;
+impl<T: Float> ops::Mul for Color<T> {
+    type Output = Color<T>;
 
     fn mul(self, other: Self) -> Self::Output {
         Self::new(
@@ -116,6 +124,13 @@ mod tests {
         assert_eq!(1.7, color.blue);
     }
 
+    #[test]
+    fn luminance_weights_green_most() {
+        assert_eq!(1.0, Color::white().luminance());
+        assert_eq!(0.7152, Color::new(0.0, 1.0, 0.0).luminance());
+        assert_eq!(0.0361, Color::new(0.0, 0.0, 0.5).luminance());
+    }
+
     #[test]
     fn colors_can_be_added() {
         let c1 = Color::new(0.9, 0.6, 0.75);
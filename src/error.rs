@@ -0,0 +1,233 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::color::Color;
+use crate::matrix::{Matrix, MotionError, SingularMatrixError};
+use crate::point::Point;
+
+/// Value which can't be rendered, eg. a negative coefficient in a material or
+/// a transformation flattening an object
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// NaN or infinite value
+    NotFinite { field: &'static str },
+    BelowMinimum {
+        field: &'static str,
+        value: f64,
+        minimum: f64,
+    },
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        minimum: f64,
+        maximum: f64,
+    },
+    /// Value which must be strictly between the bounds, eg. a field of view
+    /// which can be neither 0 nor half a turn
+    NotStrictlyBetween {
+        field: &'static str,
+        value: f64,
+        minimum: f64,
+        maximum: f64,
+    },
+    SingularTransformation {
+        field: &'static str,
+        error: SingularMatrixError,
+    },
+    /// Motion flattening the object at some time (see `Matrix::check_motion`)
+    InvalidMotion { error: MotionError },
+    /// Invalid value in the object with the given index in the world
+    Object {
+        index: usize,
+        error: Box<ValidationError>,
+    },
+    /// Invalid value in the light with the given index in the world
+    Light {
+        index: usize,
+        error: Box<ValidationError>,
+    },
+}
+
+impl ValidationError {
+    pub(crate) fn check_finite(field: &'static str, value: f64) -> Result<(), Self> {
+        if value.is_finite() {
+            Ok(())
+        } else {
+            Err(Self::NotFinite { field })
+        }
+    }
+
+    pub(crate) fn check_minimum(field: &'static str, value: f64, minimum: f64) -> Result<(), Self> {
+        Self::check_finite(field, value)?;
+        if value >= minimum {
+            Ok(())
+        } else {
+            Err(Self::BelowMinimum {
+                field,
+                value,
+                minimum,
+            })
+        }
+    }
+
+    pub(crate) fn check_range(
+        field: &'static str,
+        value: f64,
+        minimum: f64,
+        maximum: f64,
+    ) -> Result<(), Self> {
+        Self::check_finite(field, value)?;
+        if (minimum..=maximum).contains(&value) {
+            Ok(())
+        } else {
+            Err(Self::OutOfRange {
+                field,
+                value,
+                minimum,
+                maximum,
+            })
+        }
+    }
+
+    pub(crate) fn check_open_range(
+        field: &'static str,
+        value: f64,
+        minimum: f64,
+        maximum: f64,
+    ) -> Result<(), Self> {
+        Self::check_finite(field, value)?;
+        if minimum < value && value < maximum {
+            Ok(())
+        } else {
+            Err(Self::NotStrictlyBetween {
+                field,
+                value,
+                minimum,
+                maximum,
+            })
+        }
+    }
+
+    /// Colors may be brighter than white, but not negative
+    pub(crate) fn check_color(field: &'static str, color: Color) -> Result<(), Self> {
+        for component in [color.red, color.green, color.blue] {
+            Self::check_minimum(field, component, 0.0)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_point(field: &'static str, point: Point) -> Result<(), Self> {
+        for coordinate in [point.x, point.y, point.z] {
+            Self::check_finite(field, coordinate)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_motion(start: Matrix<4, 4>, end: Matrix<4, 4>) -> Result<(), Self> {
+        start
+            .check_motion(end)
+            .map_err(|error| Self::InvalidMotion { error })
+    }
+
+    pub(crate) fn check_transformation(
+        field: &'static str,
+        transformation: Matrix<4, 4>,
+    ) -> Result<(), Self> {
+        match transformation.try_inverse() {
+            Ok(_) => Ok(()),
+            Err(error) => Err(Self::SingularTransformation { field, error }),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFinite { field } => write!(f, "{field} must be a finite number"),
+            Self::BelowMinimum {
+                field,
+                value,
+                minimum,
+            } => write!(f, "{field} must be at least {minimum}, found {value}"),
+            Self::OutOfRange {
+                field,
+                value,
+                minimum,
+                maximum,
+            } => write!(
+                f,
+                "{field} must be between {minimum} and {maximum}, found {value}"
+            ),
+            Self::NotStrictlyBetween {
+                field,
+                value,
+                minimum,
+                maximum,
+            } => write!(
+                f,
+                "{field} must be strictly between {minimum} and {maximum}, found {value}"
+            ),
+            Self::SingularTransformation { field, error } => write!(
+                f,
+                "{field} can't be inverted (determinant {})",
+                error.determinant
+            ),
+            Self::InvalidMotion { error } => write!(f, "invalid motion: {error}"),
+            Self::Object { index, error } => write!(f, "object {index}: {error}"),
+            Self::Light { index, error } => write!(f, "light {index}: {error}"),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::transformations;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn messages_name_the_invalid_field() {
+        assert_eq!(
+            "ambient must be at least 0, found -1",
+            ValidationError::check_minimum("ambient", -1.0, 0.0)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "light 2: intensity must be a finite number",
+            ValidationError::Light {
+                index: 2,
+                error: Box::new(
+                    ValidationError::check_color("intensity", Color::new(1.0, f64::NAN, 1.0))
+                        .unwrap_err()
+                )
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "roughness must be between 0 and 1, found 1.5",
+            ValidationError::check_range("roughness", 1.5, 0.0, 1.0)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "field of view must be strictly between 0 and 3, found 3",
+            ValidationError::check_open_range("field of view", 3.0, 0.0, 3.0)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "transformation can't be inverted (determinant 0)",
+            ValidationError::check_transformation(
+                "transformation",
+                transformations::scaling(0.0, 1.0, 1.0)
+            )
+            .unwrap_err()
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Floating-point precision of the geometry: points, vectors, colors, matrices,
+//! and the rays, objects and worlds built from them.
+//!
+//! The renderer works with `f64`, which is the default precision of these types,
+//! while `f32` halves the memory they take. Worlds are built in `f64` and can be
+//! converted to `f32` for rendering (see `World::cast`), shading being always
+//! computed in `f64`.
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::simd::Simd;
+
+use crate::ray_packet::{SimdLanes, PACKET_SIZE};
+
+pub trait Float:
+    Copy
+    + Debug
+    + Display
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+{
+    /// Largest difference between two values considered equal (see `FloatEq`)
+    const ERROR_MARGIN: Self;
+    /// Start of the interval of rays cast from a surface, relative to the
+    /// magnitude of their origin (see `ray::epsilon_at`)
+    const RAY_EPSILON: Self;
+    const ZERO: Self;
+    const ONE: Self;
+    const INFINITY: Self;
+
+    /// One value for each ray of a packet (see `RayPacket`)
+    type Lanes: SimdLanes<Self>;
+
+    /// Nearest value of this precision
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powi(self, exponent: i32) -> Self;
+    fn clamp(self, min: Self, max: Self) -> Self;
+    fn is_finite(self) -> bool;
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! impl_float {
+    ($type:ty, $error_margin:expr, $ray_epsilon:expr) => {
+        impl Float for $type {
+            const ERROR_MARGIN: Self = $error_margin;
+            const RAY_EPSILON: Self = $ray_epsilon;
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const INFINITY: Self = <$type>::INFINITY;
+
+            type Lanes = Simd<$type, PACKET_SIZE>;
+
+            fn from_f64(value: f64) -> Self {
+                value as Self
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn abs(self) -> Self {
+                <$type>::abs(self)
+            }
+
+            fn sqrt(self) -> Self {
+                <$type>::sqrt(self)
+            }
+
+            fn powi(self, exponent: i32) -> Self {
+                <$type>::powi(self, exponent)
+            }
+
+            fn clamp(self, min: Self, max: Self) -> Self {
+                <$type>::clamp(self, min, max)
+            }
+
+            fn is_finite(self) -> bool {
+                <$type>::is_finite(self)
+            }
+
+            fn total_cmp(&self, other: &Self) -> Ordering {
+                <$type>::total_cmp(self, other)
+            }
+        }
+    };
+}
+
+// f32 has about 7 significant digits, so values of a few hundred units
+// (eg. distances in a scene) are only accurate to about 1e-4
+impl_float!(f32, 0.001, 0.0001);
+impl_float!(f64, 0.0001, crate::ray::EPSILON);
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::float_eq::FloatEq;
+    use crate::matrix::Matrix;
+    use crate::point::Point;
+    use crate::vector::Vector;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn error_margin_depends_on_precision() {
+        assert!(1.0_f32.float_eq(1.0005));
+        assert!(!1.0_f64.float_eq(1.0005));
+        assert!(1.0_f64.float_eq(1.00005));
+    }
+
+    #[test]
+    fn geometry_works_in_single_precision() {
+        let matrix: Matrix<4, 4, f32> = Matrix::new([
+            [2.0, 0.0, 0.0, 1.0],
+            [0.0, 4.0, 0.0, -2.0],
+            [0.0, 0.0, 0.5, 3.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let point = Point::new(1.0_f32, 2.0, 3.0);
+
+        assert_eq!(Point::new(3.0, 6.0, 4.5), matrix * point);
+        assert_eq!(point, matrix.inverse() * (matrix * point));
+        assert_eq!(
+            Vector::new(0.0_f32, 0.6, 0.8),
+            (matrix * Vector::new(0.0, 3.0, 32.0)).normalize()
+        );
+        assert_eq!(
+            Color::new(0.5_f32, 0.25, 1.0),
+            Color::new(1.0, 0.5, 2.0) * 0.5
+        );
+    }
+
+    #[test]
+    fn small_scaling_can_be_inverted_in_single_precision() {
+        // the determinant is 0.001, the error margin of f32
+        let scaling: Matrix<4, 4, f32> = Matrix::new([
+            [0.1, 0.0, 0.0, 0.0],
+            [0.0, 0.1, 0.0, 0.0],
+            [0.0, 0.0, 0.1, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        assert_eq!(
+            Point::new(10.0_f32, 20.0, 30.0),
+            scaling.try_inverse().unwrap() * Point::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn conversion_rounds_to_nearest_value() {
+        assert_eq!(0.1_f32, f32::from_f64(0.1));
+        assert_eq!(0.5, 0.5_f32.to_f64());
+    }
+}
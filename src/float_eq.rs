@@ -1,11 +1,11 @@
+use crate::float::Float;
+
 pub trait FloatEq {
     fn float_eq(&self, rhs: Self) -> bool;
 }
 
-const ERROR_MARGIN: f64 = 0.0001;
-
-impl FloatEq for f64 {
+impl<T: Float> FloatEq for T {
     fn float_eq(&self, other: Self) -> bool {
-        (self - other).abs() < ERROR_MARGIN
+        (*self - other).abs() < T::ERROR_MARGIN
     }
 }
@@ -0,0 +1,219 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io;
+use std::io::{BufRead, Read};
+
+/// Reads canvases in the Radiance RGBE format (`.hdr`), with flat or
+/// run-length encoded scanlines, as written by most tools.
+///
+/// Only the standard orientation (`-Y height +X width`) is supported, and
+/// not the old run-length encoding of the first versions of Radiance.
+pub struct HdrReader<R: io::Read> {
+    reader: io::BufReader<R>,
+}
+
+impl<R: io::Read> HdrReader<R> {
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader: io::BufReader::new(reader),
+        }
+    }
+
+    pub fn read_canvas(&mut self) -> io::Result<Canvas> {
+        let (width, height) = self.read_header()?;
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for (x, pixel) in self.read_scanline(width)?.into_iter().enumerate() {
+                canvas.write_pixel(x, y, from_rgbe(pixel));
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Size of the image, after checking its format
+    fn read_header(&mut self) -> io::Result<(usize, usize)> {
+        let magic = self.read_line()?;
+        if magic != "#?RADIANCE" && magic != "#?RGBE" {
+            return Err(invalid_data("Not a Radiance HDR file"));
+        }
+        loop {
+            let line = self.read_line()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid_data(format!("Unsupported pixel format {format}")));
+                }
+            }
+        }
+
+        let resolution = self.read_line()?;
+        match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => match (height.parse(), width.parse()) {
+                (Ok(height), Ok(width)) => Ok((width, height)),
+                _ => Err(invalid_data(format!("Invalid resolution \"{resolution}\""))),
+            },
+            _ => Err(invalid_data(format!(
+                "Unsupported resolution \"{resolution}\", expected \"-Y <height> +X <width>\""
+            ))),
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(line.trim_end_matches('\n').to_string())
+    }
+
+    fn read_scanline(&mut self, width: usize) -> io::Result<Vec<[u8; 4]>> {
+        let mut first_pixel = [0; 4];
+        self.reader.read_exact(&mut first_pixel)?;
+
+        let encoded = first_pixel[0] == 2 && first_pixel[1] == 2 && first_pixel[2] < 128;
+        if !encoded {
+            let mut pixels = vec![first_pixel];
+            for _ in 1..width {
+                let mut pixel = [0; 4];
+                self.reader.read_exact(&mut pixel)?;
+                pixels.push(pixel);
+            }
+            return Ok(pixels);
+        }
+
+        let encoded_width = usize::from(first_pixel[2]) << 8 | usize::from(first_pixel[3]);
+        if encoded_width != width {
+            return Err(invalid_data(format!(
+                "Scanline of {encoded_width} pixels in an image {width} pixels wide"
+            )));
+        }
+        let mut pixels = vec![[0; 4]; width];
+        for component in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let mut count = [0; 1];
+                self.reader.read_exact(&mut count)?;
+                let (is_run, length) = match count[0] {
+                    count @ 129.. => (true, usize::from(count - 128)),
+                    count => (false, usize::from(count)),
+                };
+                if length == 0 || x + length > width {
+                    return Err(invalid_data("Invalid run length in scanline"));
+                }
+
+                if is_run {
+                    let mut value = [0; 1];
+                    self.reader.read_exact(&mut value)?;
+                    for pixel in &mut pixels[x..x + length] {
+                        pixel[component] = value[0];
+                    }
+                } else {
+                    let mut values = vec![0; length];
+                    self.reader.read_exact(&mut values)?;
+                    for (pixel, value) in pixels[x..x + length].iter_mut().zip(values) {
+                        pixel[component] = value;
+                    }
+                }
+                x += length;
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+fn from_rgbe([red, green, blue, exponent]: [u8; 4]) -> Color {
+    if exponent == 0 {
+        return Color::black();
+    }
+    // values are rounded down when written, so the middle of the interval is closest
+    let scale = 2f64.powi(i32::from(exponent) - (128 + 8));
+    Color::new(
+        (f64::from(red) + 0.5) * scale,
+        (f64::from(green) + 0.5) * scale,
+        (f64::from(blue) + 0.5) * scale,
+    )
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hdr_writer::HdrWriter;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn round_trip(canvas: &Canvas) -> Canvas {
+        let mut hdr_buffer: Vec<u8> = vec![];
+        HdrWriter::from_writer(&mut hdr_buffer)
+            .write_canvas(canvas)
+            .unwrap();
+        HdrReader::from_reader(hdr_buffer.as_slice())
+            .read_canvas()
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_back_written_canvas() {
+        for width in [3, 20] {
+            let mut canvas = Canvas::new(width, 2);
+            canvas.write_pixel(0, 0, Color::new(1000.0, 0.5, 0.25));
+            canvas.write_pixel(2, 1, Color::new(0.001, 0.002, 0.003));
+            canvas.write_pixel(width - 1, 0, Color::new(1.0, 1.0, 1.0));
+
+            let read = round_trip(&canvas);
+
+            assert_eq!((width, 2), (read.width(), read.height()));
+            for (row, read_row) in canvas.iter_rows().zip(read.iter_rows()) {
+                for (color, read_color) in row.iter().zip(read_row) {
+                    // 8 bits of precision relative to the brightest component
+                    let tolerance = color.red.max(color.green).max(color.blue) / 128.0;
+                    for (component, read_component) in [
+                        (color.red, read_color.red),
+                        (color.green, read_color.green),
+                        (color.blue, read_color.blue),
+                    ] {
+                        assert!(
+                            (component - read_component).abs() <= tolerance,
+                            "{color:?} read as {read_color:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_colors_brighter_than_white() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(16.0, 4.0, 1.0));
+
+        // up to the rounding of the 8-bit mantissas
+        assert_eq!(
+            Color::new(16.0625, 4.0625, 1.0625),
+            round_trip(&canvas).pixel_at(0, 0)
+        );
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        let error = HdrReader::from_reader(b"P3\n1 1\n255\n0 0 0\n".as_slice())
+            .read_canvas()
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let error = HdrReader::from_reader(b"#?RADIANCE\n\n-Y 2 +X 2\n\x80\x80".as_slice())
+            .read_canvas()
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+    }
+}
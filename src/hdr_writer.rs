@@ -0,0 +1,183 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io;
+use std::io::Write;
+
+/// Smallest scanline length which can be run-length encoded
+const MIN_ENCODED_WIDTH: usize = 8;
+/// Largest scanline length which can be run-length encoded
+const MAX_ENCODED_WIDTH: usize = 0x7fff;
+/// Longest run of identical (or different) bytes in a run-length encoded scanline
+const MAX_RUN_LENGTH: usize = 127;
+/// Shortest run worth encoding as a run rather than as literal bytes
+const MIN_RUN_LENGTH: usize = 4;
+
+/// Writes canvases in the Radiance RGBE format (`.hdr`), which keeps the
+/// full range of the colors with a shared exponent per pixel.
+///
+/// Negative components are written as 0.
+/// See also https://www.graphics.cornell.edu/~bjw/rgbe.html
+pub struct HdrWriter<W: io::Write> {
+    writer: io::BufWriter<W>,
+}
+
+impl<W: io::Write> HdrWriter<W> {
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            writer: io::BufWriter::new(writer),
+        }
+    }
+
+    pub fn write_canvas(&mut self, canvas: &Canvas) -> io::Result<()> {
+        self.write_header(canvas)?;
+        for row in canvas.iter_rows() {
+            let pixels: Vec<[u8; 4]> = row.iter().map(|color| to_rgbe(*color)).collect();
+            if (MIN_ENCODED_WIDTH..=MAX_ENCODED_WIDTH).contains(&pixels.len()) {
+                self.write_encoded_scanline(&pixels)?;
+            } else {
+                for pixel in pixels {
+                    self.writer.write_all(&pixel)?;
+                }
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_header(&mut self, canvas: &Canvas) -> io::Result<()> {
+        write!(
+            self.writer,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            canvas.height(),
+            canvas.width()
+        )
+    }
+
+    /// Scanline with its components stored one after the other, each run-length encoded
+    fn write_encoded_scanline(&mut self, pixels: &[[u8; 4]]) -> io::Result<()> {
+        let width = pixels.len() as u16;
+        self.writer
+            .write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+
+        for component in 0..4 {
+            let bytes: Vec<u8> = pixels.iter().map(|pixel| pixel[component]).collect();
+            self.write_run_length_encoded(&bytes)?;
+        }
+        Ok(())
+    }
+
+    fn write_run_length_encoded(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut start = 0;
+        while start < bytes.len() {
+            // look for the next run long enough to be worth encoding
+            let mut run_start = start;
+            let mut run_length = 0;
+            while run_start < bytes.len() {
+                run_length = bytes[run_start..]
+                    .iter()
+                    .take(MAX_RUN_LENGTH)
+                    .take_while(|byte| **byte == bytes[run_start])
+                    .count();
+                if run_length >= MIN_RUN_LENGTH {
+                    break;
+                }
+                run_start += run_length;
+            }
+
+            // literal bytes before the run
+            for literals in bytes[start..run_start].chunks(MAX_RUN_LENGTH) {
+                self.writer.write_all(&[literals.len() as u8])?;
+                self.writer.write_all(literals)?;
+            }
+            if run_length >= MIN_RUN_LENGTH {
+                self.writer
+                    .write_all(&[128 + run_length as u8, bytes[run_start]])?;
+                run_start += run_length;
+            }
+            start = run_start;
+        }
+        Ok(())
+    }
+}
+
+/// Mantissas of the components, with their shared exponent
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let [red, green, blue] = [color.red, color.green, color.blue].map(|c| c.max(0.0));
+    let max = red.max(green).max(blue);
+    if max < 1e-32 || !max.is_finite() {
+        return [0, 0, 0, 0];
+    }
+
+    // max = mantissa * 2^exponent, with the mantissa in [0.5, 1)
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2f64.powi(exponent);
+    [
+        (red * scale) as u8,
+        (green * scale) as u8,
+        (blue * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn constructs_hdr_header() {
+        let canvas = Canvas::new(5, 3);
+        let mut hdr_buffer: Vec<u8> = vec![];
+
+        HdrWriter::from_writer(&mut hdr_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        assert!(hdr_buffer.starts_with(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 3 +X 5\n"));
+    }
+
+    #[test]
+    fn colors_share_an_exponent() {
+        assert_eq!([128, 64, 0, 129], to_rgbe(Color::new(1.0, 0.5, 0.0)));
+        assert_eq!([160, 0, 0, 132], to_rgbe(Color::new(10.0, -1.0, 0.0)));
+        assert_eq!([0, 0, 0, 0], to_rgbe(Color::black()));
+    }
+
+    #[test]
+    fn narrow_scanlines_are_written_flat() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(1.0, 0.5, 0.0));
+        let mut hdr_buffer: Vec<u8> = vec![];
+
+        HdrWriter::from_writer(&mut hdr_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        assert!(hdr_buffer.ends_with(b"\n\0\0\0\0\x80\x40\0\x81"));
+    }
+
+    #[test]
+    fn scanlines_are_run_length_encoded() {
+        let mut canvas = Canvas::new(10, 1);
+        for x in 0..10 {
+            canvas.write_pixel(x, 0, Color::new(1.0, 0.5, x as f64 / 16.0));
+        }
+        let mut hdr_buffer: Vec<u8> = vec![];
+
+        HdrWriter::from_writer(&mut hdr_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let mut expected = vec![2, 2, 0, 10];
+        // runs of red, green and exponent, blue as literal values
+        expected.extend([128 + 10, 128]);
+        expected.extend([128 + 10, 64]);
+        expected.extend([10, 0, 8, 16, 24, 32, 40, 48, 56, 64, 72]);
+        expected.extend([128 + 10, 129]);
+        assert!(hdr_buffer.ends_with(&expected), "{hdr_buffer:?}");
+    }
+}
@@ -0,0 +1,43 @@
+use crate::color::Color;
+use crate::float::Float;
+use crate::intersection::Intersection;
+use crate::random::Rng;
+use crate::ray::Ray;
+use crate::world::World;
+
+mod path_tracer;
+
+/// Algorithm computing the light arriving at the camera along a ray
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Integrator {
+    /// Direct lighting only, with the Phong reflection model.
+    /// Indirect light is approximated by the constant ambient term of each material.
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing, simulating light bouncing between diffuse surfaces.
+    /// Noisy with few samples per pixel, but accounts for indirect light.
+    PathTracing,
+}
+
+impl Integrator {
+    pub fn color<T: Float>(self, world: &World<T>, ray: Ray<T>, rng: &mut Rng) -> Color {
+        match self {
+            Integrator::Whitted => world.color_at_intersection_with(ray),
+            Integrator::PathTracing => path_tracer::color(world, ray, rng),
+        }
+    }
+
+    /// Same as `color`, with the closest hit of the ray already known
+    pub(crate) fn color_at_hit<T: Float>(
+        self,
+        world: &World<T>,
+        ray: Ray<T>,
+        hit: Option<Intersection<T>>,
+        rng: &mut Rng,
+    ) -> Color {
+        match self {
+            Integrator::Whitted => world.color_at_hit(ray, hit),
+            Integrator::PathTracing => path_tracer::color_at_hit(world, ray, hit, rng),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! Monte Carlo path tracer.
+//!
+//! Paths start from the camera and bounce on surfaces in random directions,
+//! sampled proportionally to the cosine with the normal and weighted by the
+//! reflection model of the material, highlights included. Sharp highlights
+//! are rarely sampled this way, so they converge slowly.
+//! Metallic/roughness materials pick either their diffuse or their specular
+//! lobe at each bounce, the latter sampling the GGX distribution of normals.
+//! At each bounce, the light received directly from every light source is
+//! added (next event estimation), since point lights can't be hit by chance.
+//! Paths are terminated with Russian roulette, which keeps the estimate unbiased.
+//!
+//! The background lights the scene when paths escape, which gives image-based
+//! lighting for free.
+//!
+//! Emissive surfaces are found by the bounces themselves rather than sampled
+//! explicitly, so any shape can act as a light source, at the cost of more
+//! noise for small emitters.
+//!
+//! Direct light uses the same Phong model as the Whitted integrator (without
+//! the ambient term), so both integrators give surfaces similar brightness.
+
+use std::f64::consts::PI;
+
+use crate::brdf::{BrdfModel, Ggx, MetallicRoughness};
+use crate::color::Color;
+use crate::float::Float;
+use crate::intersection::Intersection;
+use crate::intersection_state::IntersectionState;
+use crate::lighting::lighting;
+use crate::random::Rng;
+use crate::ray::{self, Ray};
+use crate::vector::Vector;
+use crate::world::World;
+
+/// Bounces always followed before Russian roulette can terminate the path
+const MIN_BOUNCES: usize = 3;
+/// Hard limit on the length of paths, in case of very bright materials
+const MAX_BOUNCES: usize = 64;
+/// Lower bound on the probability of continuing a path, so that dark paths
+/// are not terminated too early
+const MIN_SURVIVAL_PROBABILITY: f64 = 0.05;
+
+pub fn color<T: Float>(world: &World<T>, ray: Ray<T>, rng: &mut Rng) -> Color {
+    color_at_hit(world, ray, ray.intersect_world(world).hit(), rng)
+}
+
+/// Color of the path starting with the given ray, whose closest hit is known
+pub fn color_at_hit<T: Float>(
+    world: &World<T>,
+    ray: Ray<T>,
+    hit: Option<Intersection<T>>,
+    rng: &mut Rng,
+) -> Color {
+    let mut color = Color::black();
+    // fraction of the light carried back along the path so far
+    let mut throughput = Color::white();
+    let mut ray = ray;
+    let mut hit = hit;
+
+    for bounce in 0..MAX_BOUNCES {
+        let Some(current_hit) = hit else {
+            color += throughput * world.background.color(ray.direction.cast());
+            break;
+        };
+        let state = IntersectionState::prepare(current_hit, ray);
+
+        color += throughput * (state.object.material.emission + direct_light(world, &state));
+
+        let Some((direction, weight)) = scatter(&state, rng) else {
+            break;
+        };
+        throughput = throughput * weight;
+
+        if bounce + 1 >= MIN_BOUNCES {
+            let survival_probability =
+                max_component(throughput).clamp(MIN_SURVIVAL_PROBABILITY, 1.0);
+            if rng.next_f64() >= survival_probability {
+                break;
+            }
+            throughput = throughput * (1.0 / survival_probability);
+        }
+
+        ray = Ray::new(state.point, direction)
+            .with_time(state.time)
+            .cast()
+            .with_interval(ray::epsilon_at(state.point), T::INFINITY);
+        hit = ray.intersect_world(world).hit();
+    }
+
+    color
+}
+
+/// Random direction in which the path continues, with the fraction of the
+/// light coming from that direction reflected towards the eye, divided by the
+/// probability of the direction.
+/// Returns `None` if the path is absorbed.
+fn scatter<T: Float>(state: &IntersectionState<T>, rng: &mut Rng) -> Option<(Vector, Color)> {
+    let material = state.object.material;
+    let surface_color = state.surface_color;
+    let BrdfModel::MetallicRoughness {
+        metallic,
+        roughness,
+        ior,
+    } = material.brdf
+    else {
+        // with cosine-weighted sampling, the probability of the direction is its
+        // cosine divided by π, which cancels out with the π already included in
+        // the reflectance, leaving the reflectance divided by the cosine
+        let direction = cosine_weighted_direction(state.normal_v, rng);
+        let light_dot_normal = direction.dot(state.normal_v);
+        if light_dot_normal <= 0.0 {
+            return None;
+        }
+        let reflectance =
+            material.reflectance(surface_color, direction, state.eye_v, state.normal_v);
+        let reflected = surface_color * (material.diffuse * reflectance.diffuse)
+            + reflectance.specular * material.specular;
+        return Some((direction, reflected * (1.0 / light_dot_normal)));
+    };
+
+    let brdf = MetallicRoughness::new(surface_color, metallic, roughness, ior);
+    let eye_dot_normal = state.eye_v.dot(state.normal_v);
+    if eye_dot_normal <= 0.0 {
+        return None;
+    }
+
+    // the lobe is chosen in proportion to its albedo, the diffuse one being
+    // approximated with the Fresnel term towards the eye
+    let specular_albedo =
+        Ggx::fresnel(brdf.reflectance_at_normal(), eye_dot_normal) * material.specular;
+    let diffuse_albedo = surface_color * (brdf.diffuse_weight(eye_dot_normal) * material.diffuse);
+    let total_albedo = max_component(specular_albedo) + max_component(diffuse_albedo);
+    if total_albedo <= 0.0 {
+        return None;
+    }
+    let specular_probability = max_component(specular_albedo) / total_albedo;
+
+    if rng.next_f64() >= specular_probability {
+        let direction = cosine_weighted_direction(state.normal_v, rng);
+        return Some((
+            direction,
+            diffuse_albedo * (1.0 / (1.0 - specular_probability)),
+        ));
+    }
+
+    let ggx = brdf.ggx();
+    let half_v = ggx_half_vector(state.normal_v, ggx.alpha(), rng);
+    let direction = (-state.eye_v).reflect(half_v);
+    let light_dot_normal = direction.dot(state.normal_v);
+    if light_dot_normal <= 0.0 {
+        return None;
+    }
+
+    // the distribution of normals cancels out with the probability of the
+    // direction, leaving the Fresnel and masking-shadowing terms
+    let eye_dot_half = state.eye_v.dot(half_v).max(0.0);
+    let normal_dot_half = state.normal_v.dot(half_v);
+    let weight = ggx.masking_shadowing(light_dot_normal, eye_dot_normal) * eye_dot_half
+        / (eye_dot_normal * normal_dot_half)
+        * material.specular
+        / specular_probability;
+    let fresnel = Ggx::fresnel(brdf.reflectance_at_normal(), eye_dot_half);
+    Some((direction, fresnel * weight))
+}
+
+/// Light received directly from the light sources and reflected towards the eye
+fn direct_light<T: Float>(world: &World<T>, state: &IntersectionState<T>) -> Color {
+    let mut material = state.object.material;
+    material.ambient = 0.0;
+
+    world.lights.iter().fold(Color::black(), |color, light| {
+        let in_shadow = world.is_shadowed(*light, state.point, state.time);
+        if in_shadow {
+            return color;
+        }
+        color
+            + lighting(
+                material,
+                state.surface_color,
+                *light,
+                state.point,
+                state.eye_v,
+                state.normal_v,
+                false,
+            )
+    })
+}
+
+/// Random direction in the hemisphere around `normal`, with a probability
+/// proportional to the cosine of its angle with the normal
+fn cosine_weighted_direction(normal: Vector, rng: &mut Rng) -> Vector {
+    // uniform point on the unit disk, projected up onto the hemisphere
+    let angle = 2.0 * PI * rng.next_f64();
+    let radius_squared = rng.next_f64();
+    let radius = radius_squared.sqrt();
+    let (x, y, z) = (
+        radius * angle.cos(),
+        radius * angle.sin(),
+        (1.0 - radius_squared).sqrt(),
+    );
+
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+/// Random half vector around `normal`, with a probability proportional to the
+/// GGX distribution of normals (of width `alpha`) times the cosine with the normal
+fn ggx_half_vector(normal: Vector, alpha: f64, rng: &mut Rng) -> Vector {
+    let angle = 2.0 * PI * rng.next_f64();
+    let u = rng.next_f64();
+    let cos_theta_squared = (1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u);
+    let cos_theta = cos_theta_squared.sqrt();
+    let sin_theta = (1.0 - cos_theta_squared).max(0.0).sqrt();
+
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (sin_theta * angle.cos())
+        + bitangent * (sin_theta * angle.sin())
+        + normal * cos_theta)
+        .normalize()
+}
+
+/// Two unit vectors, perpendicular to each other and to `normal`
+fn orthonormal_basis(normal: Vector) -> (Vector, Vector) {
+    // any vector not parallel to the normal will do
+    let helper = if normal.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+    (tangent, bitangent)
+}
+
+fn max_component(color: Color) -> f64 {
+    color.red.max(color.green).max(color.blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::background::Background;
+    use crate::float_eq::FloatEq;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::matrix::{transformations, Matrix};
+    use crate::object::Object;
+    use crate::pattern::Pattern;
+    use crate::point::Point;
+
+    use super::*;
+
+    #[test]
+    fn cosine_weighted_directions_are_in_hemisphere_around_normal() {
+        let mut rng = Rng::seeded(1);
+        let normal = Vector::new(1.0, 2.0, -1.0).normalize();
+
+        let mut cosine_sum = 0.0;
+        let samples = 2000;
+        for _ in 0..samples {
+            let direction = cosine_weighted_direction(normal, &mut rng);
+            assert!(direction.magnitude().float_eq(1.0));
+            assert!(direction.dot(normal) >= 0.0);
+            cosine_sum += direction.dot(normal);
+        }
+
+        // the mean cosine of a cosine-weighted distribution is 2/3
+        assert!((cosine_sum / samples as f64 - 2.0 / 3.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn ray_missing_everything_is_black() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(Color::black(), color(&world, ray, &mut Rng::seeded(0)));
+    }
+
+    #[test]
+    fn background_lights_the_scene() {
+        let floor = Object::plane(
+            Matrix::identity(),
+            Material {
+                diffuse: 0.5,
+                specular: 0.0,
+                ..Material::default()
+            },
+        );
+        let world =
+            World::new(vec![], vec![floor]).with_background(Background::Constant(Color::white()));
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+
+        let mut rng = Rng::seeded(0);
+        let samples = 256;
+        let mut total = Color::black();
+        for _ in 0..samples {
+            total += color(&world, ray, &mut rng);
+        }
+
+        // a floor under a uniform sky reflects the albedo of the sky
+        assert!((total.red / samples as f64 - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn emissive_objects_light_the_scene() {
+        let floor = Object::plane(Matrix::identity(), Material::default());
+        let lamp = Object::sphere(
+            transformations::translation(0.0, 2.0, 0.0),
+            Material {
+                emission: Color::new(5.0, 5.0, 5.0),
+                ..Material::default()
+            },
+        );
+        let world = World::new(vec![], vec![floor, lamp]);
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -3.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+
+        let mut rng = Rng::seeded(0);
+        let mut total = Color::black();
+        for _ in 0..64 {
+            total += color(&world, ray, &mut rng);
+        }
+
+        assert!(total.red > 0.0);
+    }
+
+    #[test]
+    fn ggx_half_vectors_are_around_normal() {
+        let mut rng = Rng::seeded(1);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+
+        for _ in 0..200 {
+            assert!(ggx_half_vector(normal, 0.5, &mut rng).dot(normal) >= 0.0);
+        }
+        // a perfectly smooth surface only has microfacets facing the normal
+        assert_eq!(normal, ggx_half_vector(normal, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn specular_highlights_reflect_indirect_light() {
+        // only a Phong highlight, seen along the normal
+        let shininess = 10.0;
+        let material = Material {
+            diffuse: 0.0,
+            specular: 0.9,
+            shininess,
+            ..Material::default()
+        };
+        let floor = Object::plane(Matrix::identity(), material);
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = ray.intersect(&floor).hit().unwrap();
+        let state = IntersectionState::prepare(hit, ray);
+
+        let mut rng = Rng::seeded(0);
+        let samples = 20_000;
+        let mut total = Color::black();
+        for _ in 0..samples {
+            if let Some((_, weight)) = scatter(&state, &mut rng) {
+                total += weight;
+            }
+        }
+
+        // the Phong lobe integrates to 2π / (shininess + 1) over the hemisphere,
+        // divided by the π included in the reflectance
+        let expected = 0.9 * 2.0 / (shininess + 1.0);
+        let mean = total.red / samples as f64;
+        assert!((mean - expected).abs() < 0.01, "{mean}");
+    }
+
+    #[test]
+    fn smooth_metals_reflect_in_mirror_direction() {
+        let material = Material::metallic_roughness(
+            Pattern::solid(Color::new(1.0, 0.8, 0.5)),
+            1.0,
+            0.0,
+            MetallicRoughness::DEFAULT_IOR,
+        );
+        let floor = Object::plane(Matrix::identity(), material);
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let hit = ray.intersect(&floor).hit().unwrap();
+        let state = IntersectionState::prepare(hit, ray);
+
+        let (direction, weight) = scatter(&state, &mut Rng::seeded(0)).unwrap();
+
+        // up to the small width of the distribution kept for point lights
+        assert!(direction.dot(Vector::new(0.0, 1.0, 1.0).normalize()) > 0.999);
+        // the Fresnel reflectance, tinted by the metal
+        let expected = Color::new(1.0, 0.80043, 0.50108);
+        let difference = weight - expected;
+        assert!(
+            [difference.red, difference.green, difference.blue]
+                .iter()
+                .all(|channel| channel.abs() < 0.001),
+            "{weight:?}"
+        );
+    }
+
+    #[test]
+    fn metals_reflect_emissive_objects() {
+        let floor = Object::plane(
+            Matrix::identity(),
+            Material::metallic_roughness(
+                Pattern::solid(Color::white()),
+                1.0,
+                0.3,
+                MetallicRoughness::DEFAULT_IOR,
+            ),
+        );
+        let lamp = Object::sphere(
+            transformations::translation(0.0, 2.0, 2.0),
+            Material {
+                emission: Color::new(5.0, 5.0, 5.0),
+                ..Material::default()
+            },
+        );
+        let world = World::new(vec![], vec![floor, lamp]);
+        // looking at the reflection of the lamp
+        let ray = Ray::new(
+            Point::new(0.0, 2.0, -2.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+
+        let mut rng = Rng::seeded(0);
+        let mut total = Color::black();
+        for _ in 0..64 {
+            total += color(&world, ray, &mut rng);
+        }
+
+        assert!(total.red / 64.0 > 1.0);
+    }
+
+    #[test]
+    fn shadowed_surfaces_receive_indirect_light() {
+        let light = Light::new(Point::new(0.0, 10.0, 0.0), Color::white());
+        let floor = Object::plane(
+            transformations::translation(0.0, 0.0, 0.0),
+            Material::default(),
+        );
+        let blocker = Object::sphere(
+            transformations::scaling(2.0, 2.0, 2.0) >> transformations::translation(0.0, 3.0, 0.0),
+            Material::default(),
+        );
+        let world = World::new(vec![light], vec![floor, blocker]);
+        // looking down at the floor, right below the blocker
+        let ray = Ray::new(
+            Point::new(0.0, 0.9, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        assert!(world.is_shadowed(light, Point::new(0.0, 0.0, 0.1), 0.0));
+
+        let mut rng = Rng::seeded(0);
+        let mut total = Color::black();
+        for _ in 0..64 {
+            total += color(&world, ray, &mut rng);
+        }
+
+        assert!(total.red > 0.0);
+    }
+}
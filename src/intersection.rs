@@ -1,19 +1,23 @@
+use crate::float::Float;
 use crate::float_eq::FloatEq;
 use crate::object::Object;
 
+/// Point at distance `t` along a ray where it intersects an object.
+/// Moving objects are referred to as a whole: their position at the time of
+/// the ray is computed when needed (see `IntersectionState`).
 #[derive(Copy, Clone, Debug)]
-pub struct Intersection {
-    pub t: f64,
-    pub object: Object,
+pub struct Intersection<'a, T: Float = f64> {
+    pub t: T,
+    pub object: &'a Object<T>,
 }
 
-impl Intersection {
-    pub fn new(t: f64, object: Object) -> Self {
+impl<'a, T: Float> Intersection<'a, T> {
+    pub fn new(t: T, object: &'a Object<T>) -> Self {
         Self { t, object }
     }
 }
 
-impl PartialEq for Intersection {
+impl<T: Float> PartialEq for Intersection<'_, T> {
     fn eq(&self, other: &Self) -> bool {
         self.t.float_eq(other.t) && self.object == other.object
     }
@@ -28,9 +32,9 @@ mod tests {
     #[test]
     fn intersectin_encapsulates_t_and_object() {
         let sphere = Object::default();
-        let intersection = Intersection::new(3.5, sphere);
+        let intersection = Intersection::new(3.5, &sphere);
 
         assert_eq!(3.5, intersection.t);
-        assert_eq!(sphere, intersection.object);
+        assert_eq!(&sphere, intersection.object);
     }
 }
@@ -1,36 +1,48 @@
+use crate::color::Color;
+use crate::float::Float;
 use crate::intersection::Intersection;
 use crate::object::Object;
 use crate::point::Point;
 use crate::ray::Ray;
 use crate::vector::Vector;
 
-const EPSILON: f64 = 0.000000001;
-
-/// Precomputed state for an intersection
+/// Precomputed state for an intersection, in `f64` whatever the precision
+/// of the intersected object
 #[derive(Clone, Debug)]
-pub struct IntersectionState {
+pub struct IntersectionState<'a, T: Float = f64> {
     pub t: f64,
-    pub object: Object,
+    pub object: &'a Object<T>,
+    /// secondary rays start from this point, with an interval excluding the
+    /// surface itself (see `ray::epsilon_at`)
     pub point: Point,
-    /// point slightly moved towards the direction of the normal
-    /// this will be used when testing for shadows, in order to bump
-    /// the point over the surface and avoid self-shadowing due to
-    /// unreliable floating point computations.
-    pub over_point: Point,
     pub eye_v: Vector,
     pub normal_v: Vector,
+    /// color of the pattern of the object at the point
+    pub surface_color: Color,
+    /// direction of the ray reflected by the surface
+    pub reflect_v: Vector,
     /// whether the hit occurred inside the object
     pub inside: bool,
+    /// time at which the intersecting ray was cast
+    pub time: f64,
 }
 
-impl IntersectionState {
-    pub fn prepare(intersection: Intersection, ray: Ray) -> Self {
+impl<'a, T: Float> IntersectionState<'a, T> {
+    pub fn prepare(intersection: Intersection<'a, T>, ray: Ray<T>) -> Self {
         let t = intersection.t;
         let object = intersection.object;
+        // moving objects are shaded in the position they have at the time of the ray,
+        // which can be inverted since the ray intersected the object in it
+        let inverse_transformation = object
+            .inverse_transformation_at(ray.time)
+            .expect("Intersected objects have an inverse transformation");
         let point = ray.position(t);
+        let normal_v = object.normal_with_inverse(inverse_transformation, point);
+        let surface_color = object.color_with_inverse(inverse_transformation, point);
 
+        let (point, mut normal_v) = (point.cast(), normal_v.cast());
+        let ray = ray.cast::<f64>();
         let eye_v = -ray.direction;
-        let mut normal_v = object.normal_at(point);
 
         let mut inside = false;
 
@@ -40,16 +52,18 @@ impl IntersectionState {
             normal_v = -normal_v;
         }
 
-        let over_point = point + normal_v * EPSILON;
+        let reflect_v = ray.direction.reflect(normal_v);
 
         Self {
-            t,
+            t: t.to_f64(),
             object,
             point,
-            over_point,
             eye_v,
             normal_v,
+            surface_color,
+            reflect_v,
             inside,
+            time: ray.time,
         }
     }
 
@@ -64,7 +78,10 @@ impl IntersectionState {
 
 #[cfg(test)]
 mod tests {
-    use crate::matrix::transformations;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::material::Material;
+    use crate::matrix::Matrix;
 
     use super::*;
 
@@ -72,7 +89,7 @@ mod tests {
     fn precomputing_the_state_of_an_intersection() {
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let object = Object::default();
-        let intersection = Intersection::new(4.0, object);
+        let intersection = Intersection::new(4.0, &object);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
 
@@ -87,44 +104,41 @@ mod tests {
     fn if_hit_happened_outside_the_object_inside_is_false() {
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let object = Object::default();
-        let intersection = Intersection::new(4.0, object);
+        let intersection = Intersection::new(4.0, &object);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
 
-        assert_eq!(false, intersection_state.inside);
+        assert!(!intersection_state.inside);
     }
 
     #[test]
     fn if_hit_happened_inside_the_object_inside_is_true_and_normal_is_inverted() {
         let ray = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
         let object = Object::default();
-        let intersection = Intersection::new(1.0, object);
+        let intersection = Intersection::new(1.0, &object);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
 
-        assert_eq!(true, intersection_state.inside);
+        assert!(intersection_state.inside);
         assert_eq!(Point::new(0.0, 0.0, 1.0), intersection_state.point);
         assert_eq!(Vector::new(0.0, 0.0, -1.0), intersection_state.eye_v);
         assert_eq!(Vector::new(0.0, 0.0, -1.0), intersection_state.normal_v);
     }
 
     #[test]
-    fn hit_should_offset_the_point() {
-        // over_point should be slightly bumped in the direction of the normal
-        // compared to the point, to move it above the surface and thus avoid
-        // bad results when testing for shadows due to unreliability of float
-        // point operations.
-
-        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let shape = Object {
-            transformation: transformations::translation(0.0, 0.0, 1.0),
-            ..Object::default()
-        };
-        let intersection = Intersection::new(5.0, shape);
+    fn precomputing_the_reflection_vector() {
+        let shape = Object::plane(Matrix::identity(), Material::default());
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let intersection = Intersection::new(2.0_f64.sqrt(), &shape);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
 
-        assert!(intersection_state.over_point.z < -EPSILON / 2.0);
-        assert!(intersection_state.point.z > intersection_state.over_point.z)
+        assert_eq!(
+            Vector::new(0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+            intersection_state.reflect_v
+        );
     }
 }
@@ -1,55 +1,94 @@
 use std::ops;
 
+use crate::float::Float;
 use crate::intersection::Intersection;
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Intersections(Vec<Intersection>);
+/// Maximum number of intersections kept, see `Intersections::CAPACITY`
+const CAPACITY: usize = 4;
 
-impl Intersections {
-    pub fn of(intersections: &[Intersection]) -> Self {
-        Self(intersections.to_vec()).sorted_by_t()
+/// Intersections sorted by t, in a buffer of fixed capacity which doesn't
+/// allocate, so that it can be used for each ray
+#[derive(Copy, Clone, Debug)]
+pub struct Intersections<'a, T: Float = f64> {
+    intersections: [Option<Intersection<'a, T>>; CAPACITY],
+    count: usize,
+}
+
+impl Intersections<'_> {
+    /// Maximum number of intersections kept, enough for all the intersections
+    /// of a ray with any single shape
+    pub const CAPACITY: usize = CAPACITY;
+}
+
+impl<'a, T: Float> Intersections<'a, T> {
+    /// # Panics
+    ///
+    /// If there are more than `CAPACITY` intersections.
+    pub fn of(intersections: &[Intersection<'a, T>]) -> Self {
+        assert!(
+            intersections.len() <= CAPACITY,
+            "Expected at most {} intersections, found {}",
+            CAPACITY,
+            intersections.len()
+        );
+        let mut result = Self::empty();
+        for intersection in intersections {
+            result.push(*intersection);
+        }
+        result
     }
 
     pub fn empty() -> Self {
-        Self(vec![])
+        Self {
+            intersections: [None; CAPACITY],
+            count: 0,
+        }
     }
 
-    /// merge multiple Intersections objects
-    /// and sort the resulting Intersections by t
-    pub fn merge(intersections: Vec<Self>) -> Self {
-        let inner_intersections: Vec<Intersection> = intersections
-            .into_iter()
-            .flat_map(|intersection| intersection.0)
-            .collect();
-
-        Self::of(&inner_intersections)
+    /// Insert the intersection in order of t, after those with the same t.
+    /// When the buffer is full, the intersection with the largest t is dropped.
+    pub fn push(&mut self, intersection: Intersection<'a, T>) {
+        let position = self
+            .iter()
+            .position(|other| other.t > intersection.t)
+            .unwrap_or(self.count);
+        if position == CAPACITY {
+            return;
+        }
+
+        let last = self.count.min(CAPACITY - 1);
+        self.intersections[position..=last].rotate_right(1);
+        self.intersections[position] = Some(intersection);
+        self.count = (self.count + 1).min(CAPACITY);
     }
 
     pub fn count(&self) -> usize {
-        self.0.len()
+        self.count
     }
 
-    pub fn hit(&self) -> Option<Intersection> {
-        self.0
-            .iter()
-            .filter(|intersection| intersection.t >= 0.0)
-            .min_by(|i1, i2| i1.t.total_cmp(&i2.t))
-            .copied()
+    pub fn iter(&self) -> impl Iterator<Item = Intersection<'a, T>> + '_ {
+        self.intersections[..self.count].iter().flatten().copied()
     }
 
-    pub fn sorted_by_t(mut self) -> Self {
-        self.0
-            .sort_by(|intersection, other| intersection.t.total_cmp(&other.t));
+    /// Intersection with the lowest non-negative t
+    pub fn hit(&self) -> Option<Intersection<'a, T>> {
+        self.iter().find(|intersection| intersection.t >= T::ZERO)
+    }
+}
 
-        self
+impl<T: Float> PartialEq for Intersections<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.count == other.count && self.iter().eq(other.iter())
     }
 }
 
-impl ops::Index<usize> for Intersections {
-    type Output = Intersection;
+impl<'a, T: Float> ops::Index<usize> for Intersections<'a, T> {
+    type Output = Intersection<'a, T>;
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
+        self.intersections[..self.count][index]
+            .as_ref()
+            .expect("Intersections are stored contiguously")
     }
 }
 
@@ -64,8 +103,8 @@ mod tests {
     #[test]
     fn aggregates_intersections() {
         let sphere = Object::default();
-        let intersection_1 = Intersection::new(1.0, sphere);
-        let intersection_2 = Intersection::new(2.0, sphere);
+        let intersection_1 = Intersection::new(1.0, &sphere);
+        let intersection_2 = Intersection::new(2.0, &sphere);
 
         let intersections = Intersections::of(&[intersection_1, intersection_2]);
         assert_eq!(2, intersections.count());
@@ -76,8 +115,8 @@ mod tests {
     #[test]
     fn when_all_ts_are_positive_hit_is_intersection_with_lowest_t() {
         let sphere = Object::default();
-        let intersection_1 = Intersection::new(1.0, sphere);
-        let intersection_2 = Intersection::new(2.0, sphere);
+        let intersection_1 = Intersection::new(1.0, &sphere);
+        let intersection_2 = Intersection::new(2.0, &sphere);
 
         let intersections = Intersections::of(&[intersection_1, intersection_2]);
 
@@ -87,8 +126,8 @@ mod tests {
     #[test]
     fn when_some_ts_are_negative_hit_is_intersection_with_lowest_nonnegative_t() {
         let sphere = Object::default();
-        let intersection_1 = Intersection::new(-1.0, sphere);
-        let intersection_2 = Intersection::new(1.0, sphere);
+        let intersection_1 = Intersection::new(-1.0, &sphere);
+        let intersection_2 = Intersection::new(1.0, &sphere);
 
         let intersections = Intersections::of(&[intersection_1, intersection_2]);
 
@@ -98,8 +137,8 @@ mod tests {
     #[test]
     fn when_all_ts_are_negative_intersection_is_nothing() {
         let sphere = Object::default();
-        let intersection_1 = Intersection::new(-2.0, sphere);
-        let intersection_2 = Intersection::new(-1.0, sphere);
+        let intersection_1 = Intersection::new(-2.0, &sphere);
+        let intersection_2 = Intersection::new(-1.0, &sphere);
 
         let intersections = Intersections::of(&[intersection_1, intersection_2]);
 
@@ -109,10 +148,10 @@ mod tests {
     #[test]
     fn hit_is_always_intersection_with_lowest_nonnegative_t() {
         let sphere = Object::default();
-        let intersection_1 = Intersection::new(5.0, sphere);
-        let intersection_2 = Intersection::new(7.0, sphere);
-        let intersection_3 = Intersection::new(-3.0, sphere);
-        let intersection_4 = Intersection::new(2.0, sphere);
+        let intersection_1 = Intersection::new(5.0, &sphere);
+        let intersection_2 = Intersection::new(7.0, &sphere);
+        let intersection_3 = Intersection::new(-3.0, &sphere);
+        let intersection_4 = Intersection::new(2.0, &sphere);
 
         let intersections = Intersections::of(&[
             intersection_1,
@@ -123,4 +162,23 @@ mod tests {
 
         assert_eq!(Some(intersection_4), intersections.hit())
     }
+
+    #[test]
+    fn full_buffer_keeps_the_closest_intersections() {
+        let sphere = Object::default();
+        let mut intersections = Intersections::empty();
+
+        for t in [5.0, 3.0, 9.0, 1.0, 7.0, 2.0] {
+            intersections.push(Intersection::new(t, &sphere));
+        }
+
+        assert_eq!(Intersections::CAPACITY, intersections.count());
+        assert_eq!(
+            vec![1.0, 2.0, 3.0, 5.0],
+            intersections
+                .iter()
+                .map(|intersection| intersection.t)
+                .collect::<Vec<_>>()
+        );
+    }
 }
@@ -1,9 +1,19 @@
 #![feature(generic_const_exprs)]
+#![feature(portable_simd)]
+#![allow(incomplete_features)]
 
+pub mod animation;
+pub mod background;
+pub mod brdf;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod error;
+pub mod float;
 pub mod float_eq;
+pub mod hdr_reader;
+pub mod hdr_writer;
+pub mod integrator;
 pub mod intersection;
 pub mod intersection_state;
 pub mod intersections;
@@ -12,10 +22,21 @@ pub mod lighting;
 pub mod material;
 pub mod matrix;
 pub mod object;
+pub mod output_transform;
+pub mod pam_writer;
 pub mod pattern;
+pub mod pfm_reader;
+pub mod pfm_writer;
 pub mod point;
+pub mod ppm_reader;
 pub mod ppm_writer;
+pub mod quaternion;
+pub mod random;
 pub mod ray;
+pub mod ray_packet;
+pub mod regression;
+pub mod render_pass;
+pub mod scene;
 pub mod shape;
 pub mod vector;
 pub mod view_transform;
@@ -1,4 +1,4 @@
-use crate::{color::Color, point::Point};
+use crate::{color::Color, error::ValidationError, point::Point};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Light {
@@ -13,6 +13,12 @@ impl Light {
             intensity,
         }
     }
+
+    /// Check that the position is finite and that the intensity isn't negative
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        ValidationError::check_point("position", self.position)?;
+        ValidationError::check_color("intensity", self.intensity)
+    }
 }
 
 #[cfg(test)]
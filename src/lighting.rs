@@ -1,13 +1,15 @@
 use crate::color::Color;
 use crate::light::Light;
-use crate::object::Object;
+use crate::material::Material;
 use crate::point::Point;
 use crate::vector::Vector;
 
-/// Implementation of the Phong reflection model
-/// See also https://en.wikipedia.org/wiki/Phong_reflection_model
+/// Light reflected towards the eye from a single light source, using the
+/// reflection model of the material (Phong by default), plus the ambient term.
+/// The surface color is the color of the pattern of the material at the position.
 pub fn lighting(
-    intersected_object: Object,
+    material: Material,
+    surface_color: Color,
     light: Light,
     position: Point,
     eye_vector: Vector,
@@ -15,52 +17,31 @@ pub fn lighting(
     in_shadow: bool,
 ) -> Color {
     // combine the surface color with the light's color/intensity
-    let effective_color = intersected_object.object_color_at(position) * light.intensity;
+    let effective_color = surface_color * light.intensity;
 
     // direction to the light source
     let light_vector = (light.position - position).normalize();
 
     // ambient contribution
-    let material = intersected_object.material;
     let ambient = effective_color * material.ambient;
 
     if in_shadow {
         return ambient;
     }
 
-    // light_dot_normal represents the cosine of the angle between the
-    // light vector and the normal vector.
-    // A negative number means the light is on the other side of the surface.
-    let light_dot_normal = light_vector.dot(normal_vector);
-
-    let (diffuse, specular) = if light_dot_normal < 0.0 {
-        (Color::black(), Color::black())
-    } else {
-        let diffuse = effective_color * material.diffuse * light_dot_normal;
-
-        // reflect_dot_eye represents the cosine of the angle between the
-        // reflection vector and the eye vector. A negative number means the
-        // light reflects away from the eye.
-        let reflect_vector = (-light_vector).reflect(normal_vector);
-        let reflect_dot_eye = reflect_vector.dot(eye_vector);
-
-        let specular = if reflect_dot_eye <= 0.0 {
-            Color::black()
-        } else {
-            let factor = reflect_dot_eye.powf(material.shininess);
-            light.intensity * material.specular * factor
-        };
+    let reflectance = material.reflectance(surface_color, light_vector, eye_vector, normal_vector);
+    let diffuse = effective_color * material.diffuse * reflectance.diffuse;
+    let specular = light.intensity * material.specular * reflectance.specular;
 
-        (diffuse, specular)
-    };
     ambient + diffuse + specular
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::material::Material;
+    use crate::brdf::BrdfModel;
     use crate::matrix::Matrix;
+    use crate::object::Object;
     use crate::pattern::Pattern;
 
     use super::*;
@@ -70,13 +51,21 @@ mod tests {
     #[test]
     fn test_lighting_with_eye_between_light_and_surface() {
         // ambient, diffuse and specular components at full strength
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eye_v = Vector::new(0.0, 0.0, -1.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eye_v, normal_v, false);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            false,
+        );
 
         assert_eq!(Color::new(1.9, 1.9, 1.9), result);
     }
@@ -84,13 +73,21 @@ mod tests {
     #[test]
     fn lighting_with_eye_between_light_and_surface_with_eye_offset_45_degrees() {
         // specular component at roughly zero, ambient and diffuse at full strength
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eye_v = Vector::new(0.0, (2.0_f64).sqrt() / 2.0, -(2.0_f64).sqrt() / 2.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eye_v, normal_v, false);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            false,
+        );
 
         assert_eq!(Color::new(1.0, 1.0, 1.0), result);
     }
@@ -98,13 +95,21 @@ mod tests {
     #[test]
     fn lighting_with_eye_opposite_surface_light_offset_45() {
         // specular component at roughly zero
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eye_v = Vector::new(0.0, 0.0, -1.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eye_v, normal_v, false);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            false,
+        );
 
         assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
     }
@@ -112,27 +117,68 @@ mod tests {
     #[test]
     fn lighting_with_eye_in_path_of_reflection_vector() {
         // specular component at full strength, ambient and diffuse same as previous test
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eyev = Vector::new(0.0, -(2.0_f64).sqrt() / 2.0, -(2.0_f64).sqrt() / 2.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eyev, normalv, false);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eyev,
+            normalv,
+            false,
+        );
 
         assert_eq!(Color::new(1.63638, 1.63638, 1.63638), result);
     }
 
+    #[test]
+    fn lighting_uses_reflection_model_of_material() {
+        // same as previous test, but a Lambertian surface has no specular highlight
+        let material = Material {
+            brdf: BrdfModel::Lambert,
+            ..Material::default()
+        };
+        let position = Point::origin();
+        let eye_v = Vector::new(0.0, -(2.0_f64).sqrt() / 2.0, -(2.0_f64).sqrt() / 2.0);
+        let normal_v = Vector::new(0.0, 0.0, -1.0);
+        let light = Light::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            false,
+        );
+
+        assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
+    }
+
     #[test]
     fn lighting_with_light_behind_surface() {
         // in this case only the ambient lighting will be considered
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eye_v = Vector::new(0.0, 0.0, -1.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eye_v, normal_v, false);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            false,
+        );
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
@@ -140,13 +186,21 @@ mod tests {
     #[test]
     fn test_lighting_with_surface_in_shadow() {
         // in this case only ambient contribution is considered
-        let object = Object::default();
+        let material = Material::default();
         let position = Point::origin();
         let eye_v = Vector::new(0.0, 0.0, -1.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
-        let result = lighting(object, light, position, eye_v, normal_v, true);
+        let result = lighting(
+            material,
+            Color::white(),
+            light,
+            position,
+            eye_v,
+            normal_v,
+            true,
+        );
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
@@ -161,16 +215,14 @@ mod tests {
             specular: 0.0,
             ..Material::default()
         };
-        let object = Object {
-            material,
-            ..Object::default()
-        };
+        let object = Object::sphere(Matrix::identity(), material);
         let eye_v = Vector::new(0.0, 0.0, -1.0);
         let normal_v = Vector::new(0.0, 0.0, -1.0);
         let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         let color_1 = lighting(
-            object,
+            material,
+            object.object_color_at(Point::new(0.9, 0.0, 0.0)),
             light,
             Point::new(0.9, 0.0, 0.0),
             eye_v,
@@ -178,7 +230,8 @@ mod tests {
             true,
         );
         let color_2 = lighting(
-            object,
+            material,
+            object.object_color_at(Point::new(1.1, 0.0, 0.0)),
             light,
             Point::new(1.1, 0.0, 0.0),
             eye_v,
@@ -1,3 +1,532 @@
-fn main() {
-    println!("Hello, world!");
+//! Command line renderer.
+//!
+//! Renders one of the demo scenes from `examples/scenes` by name, or any scene
+//! file (see the `scene` module for its format), to an image file.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use raytracer::canvas::Canvas;
+use raytracer::hdr_writer::HdrWriter;
+use raytracer::integrator::Integrator;
+use raytracer::output_transform::{Encoding, OutputTransform, ToneMapping};
+use raytracer::pam_writer::PamWriter;
+use raytracer::pfm_writer::PfmWriter;
+use raytracer::ppm_writer::PpmWriter;
+use raytracer::render_pass::RenderPass;
+use raytracer::scene::{Scene, SceneError};
+
+const USAGE: &str = "\
+Usage: raytracer [OPTIONS] <SCENE>
+
+Renders SCENE, either the name of a demo scene or the path of a scene file.
+
+Demo scenes: scene, patterns, flat_sphere, glow, brdfs, outdoors
+
+Options:
+  -o, --output <PATH>    Output file [default: <SCENE>.<FORMAT>]
+  -f, --format <FORMAT>  Output format: ppm, pam with an alpha channel, or hdr and
+                         pfm to keep colors brighter than white
+                         [default: from output extension, or ppm]
+      --width <PIXELS>   Override the width of the image
+      --height <PIXELS>  Override the height of the image
+                         (the aspect ratio is kept if only one is given)
+      --exposure <STOPS> Brighten (or darken, if negative) 8-bit images [default: 0]
+      --tone-map <OP>    Compression of colors brighter than white in 8-bit images:
+                         clamp, reinhard, aces or filmic [default: clamp]
+      --encoding <ENC>   Encoding of 8-bit images: linear or srgb [default: linear]
+      --passes <LIST>    Also write these comma-separated passes, each to
+                         <OUTPUT STEM>.<PASS>.<FORMAT>, without the 8-bit transforms:
+                         depth, normal, albedo, object_id, shadowed, unshadowed
+  -j, --threads <N>      Number of render threads [default: available cores]
+  -s, --samples <N>      Samples per pixel
+  -i, --integrator <I>   Rendering algorithm: whitted or path_tracing
+      --seed <SEED>      Seed of the random sampling
+  -q, --quiet            Don't print progress
+  -h, --help             Print this help
+
+Exit codes:
+  0  success
+  2  invalid command line arguments
+  3  invalid scene description
+  4  failed to read the scene or to write the image";
+
+/// Scenes from `examples/scenes`, available by name
+const DEMO_SCENES: [(&str, &str); 6] = [
+    ("scene", include_str!("../examples/scenes/scene.json")),
+    ("patterns", include_str!("../examples/scenes/patterns.json")),
+    (
+        "flat_sphere",
+        include_str!("../examples/scenes/flat_sphere.json"),
+    ),
+    ("glow", include_str!("../examples/scenes/glow.json")),
+    ("brdfs", include_str!("../examples/scenes/brdfs.json")),
+    ("outdoors", include_str!("../examples/scenes/outdoors.json")),
+];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = parse_args(&args).and_then(|options| match options {
+        None => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(options) => render(&options),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            if let CliError::Usage(_) = error {
+                eprintln!("\nRun with --help for usage");
+            }
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Ppm,
+    Pam,
+    Hdr,
+    Pfm,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ppm" => Some(Format::Ppm),
+            "pam" => Some(Format::Pam),
+            "hdr" => Some(Format::Hdr),
+            "pfm" => Some(Format::Pfm),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            Format::Pam => "pam",
+            Format::Hdr => "hdr",
+            Format::Pfm => "pfm",
+        }
+    }
+
+    /// Write the canvas, applying the output transform to 8-bit formats
+    fn write(
+        self,
+        canvas: &Canvas,
+        output_transform: OutputTransform,
+        file: File,
+    ) -> io::Result<()> {
+        match self {
+            Format::Ppm => PpmWriter::from_writer(file)
+                .with_output_transform(output_transform)
+                .write_canvas(canvas),
+            Format::Pam => PamWriter::from_writer(file)
+                .with_output_transform(output_transform)
+                .write_canvas(canvas),
+            Format::Hdr => HdrWriter::from_writer(file).write_canvas(canvas),
+            Format::Pfm => PfmWriter::from_writer(file).write_canvas(canvas),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    scene: String,
+    output: Option<PathBuf>,
+    format: Option<Format>,
+    output_transform: OutputTransform,
+    passes: Vec<RenderPass>,
+    width: Option<usize>,
+    height: Option<usize>,
+    threads: Option<NonZeroUsize>,
+    samples: Option<NonZeroUsize>,
+    integrator: Option<Integrator>,
+    seed: Option<u32>,
+    quiet: bool,
+}
+
+impl Options {
+    fn format(&self) -> Format {
+        self.format
+            .or_else(|| {
+                self.output
+                    .as_ref()
+                    .and_then(|output| output.extension())
+                    .and_then(|extension| Format::from_name(&extension.to_string_lossy()))
+            })
+            .unwrap_or(Format::Ppm)
+    }
+
+    fn output(&self) -> PathBuf {
+        self.output.clone().unwrap_or_else(|| {
+            let scene_name = Path::new(&self.scene)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "render".to_string());
+            PathBuf::from(format!("{scene_name}.{}", self.format().extension()))
+        })
+    }
+
+    /// Output file of a render pass, next to the main output
+    fn pass_output(&self, pass: RenderPass) -> PathBuf {
+        let output = self.output();
+        let stem = output
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        output.with_file_name(format!(
+            "{stem}.{}.{}",
+            pass.name(),
+            self.format().extension()
+        ))
+    }
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Scene {
+        source_name: String,
+        error: SceneError,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Scene { .. } => 3,
+            CliError::Io { .. } => 4,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{message}"),
+            CliError::Scene { source_name, error } => write!(f, "{source_name}:{error}"),
+            CliError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+/// Parse the command line arguments.
+/// Returns `None` if the usage should be printed instead of rendering.
+fn parse_args(args: &[String]) -> Result<Option<Options>, CliError> {
+    let mut options = Options::default();
+    let mut scene = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("Missing value for {arg}")))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-q" | "--quiet" => options.quiet = true,
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let name = value()?;
+                let format = Format::from_name(name)
+                    .ok_or_else(|| CliError::Usage(format!("Unknown format \"{name}\"")))?;
+                options.format = Some(format);
+            }
+            "--exposure" => {
+                let exposure = value()?;
+                options.output_transform.exposure = exposure
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|exposure| exposure.is_finite())
+                    .ok_or_else(|| {
+                        CliError::Usage(format!(
+                            "Invalid value \"{exposure}\" for {arg}, expected a number"
+                        ))
+                    })?;
+            }
+            "--tone-map" => {
+                options.output_transform.tone_mapping = match value()?.as_str() {
+                    "clamp" => ToneMapping::Clamp,
+                    "reinhard" => ToneMapping::Reinhard,
+                    "aces" => ToneMapping::AcesFitted,
+                    "filmic" => ToneMapping::Filmic,
+                    other => {
+                        return Err(CliError::Usage(format!("Unknown tone mapping \"{other}\"")))
+                    }
+                }
+            }
+            "--encoding" => {
+                options.output_transform.encoding = match value()?.as_str() {
+                    "linear" => Encoding::Linear,
+                    "srgb" => Encoding::Srgb,
+                    other => return Err(CliError::Usage(format!("Unknown encoding \"{other}\""))),
+                }
+            }
+            "--passes" => {
+                options.passes = value()?
+                    .split(',')
+                    .map(|name| {
+                        RenderPass::from_name(name).ok_or_else(|| {
+                            CliError::Usage(format!("Unknown render pass \"{name}\""))
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--width" => options.width = Some(parse_number(arg, value()?)?.get()),
+            "--height" => options.height = Some(parse_number(arg, value()?)?.get()),
+            "-j" | "--threads" => options.threads = Some(parse_number(arg, value()?)?),
+            "-s" | "--samples" => options.samples = Some(parse_number(arg, value()?)?),
+            "-i" | "--integrator" => {
+                options.integrator = Some(match value()?.as_str() {
+                    "whitted" => Integrator::Whitted,
+                    "path_tracing" => Integrator::PathTracing,
+                    other => {
+                        return Err(CliError::Usage(format!("Unknown integrator \"{other}\"")))
+                    }
+                })
+            }
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(seed.parse().map_err(|_| {
+                    CliError::Usage(format!(
+                        "Invalid value \"{seed}\" for {arg}, expected a nonnegative integer"
+                    ))
+                })?)
+            }
+            _ if arg.starts_with('-') => {
+                return Err(CliError::Usage(format!("Unknown option {arg}")))
+            }
+            _ if scene.is_some() => {
+                return Err(CliError::Usage(format!("Unexpected argument {arg}")))
+            }
+            _ => scene = Some(arg.clone()),
+        }
+    }
+
+    options.scene = scene.ok_or_else(|| CliError::Usage("Missing scene".to_string()))?;
+    Ok(Some(options))
+}
+
+fn parse_number(option: &str, value: &str) -> Result<NonZeroUsize, CliError> {
+    value.parse().map_err(|_| {
+        CliError::Usage(format!(
+            "Invalid value \"{value}\" for {option}, expected a positive integer"
+        ))
+    })
+}
+
+fn load_scene(name: &str) -> Result<Scene, CliError> {
+    let source = match DEMO_SCENES.iter().find(|(demo_name, _)| *demo_name == name) {
+        Some((_, source)) => source.to_string(),
+        None => fs::read_to_string(name).map_err(|error| CliError::Io {
+            path: PathBuf::from(name),
+            error,
+        })?,
+    };
+
+    Scene::parse(&source).map_err(|error| CliError::Scene {
+        source_name: name.to_string(),
+        error,
+    })
+}
+
+fn render(options: &Options) -> Result<(), CliError> {
+    let Scene { world, mut camera } = load_scene(&options.scene)?;
+
+    let aspect_ratio = camera.hsize() as f64 / camera.vsize() as f64;
+    let (width, height) = match (options.width, options.height) {
+        (None, None) => (camera.hsize(), camera.vsize()),
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, scaled_size(width as f64 / aspect_ratio)),
+        (None, Some(height)) => (scaled_size(height as f64 * aspect_ratio), height),
+    };
+    camera = camera
+        .with_size(width, height)
+        .with_progress(!options.quiet);
+    if let Some(samples) = options.samples {
+        camera = camera.with_samples_per_pixel(samples.get());
+    }
+    if let Some(integrator) = options.integrator {
+        camera = camera.with_integrator(integrator);
+    }
+    if let Some(seed) = options.seed {
+        camera = camera.with_seed(seed);
+    }
+
+    let (canvas, pass_canvases) =
+        camera.render_with_passes(world, &options.passes, options.threads);
+
+    write_image(options, &canvas, options.output_transform, options.output())?;
+    for (pass, pass_canvas) in options.passes.iter().zip(&pass_canvases) {
+        write_image(
+            options,
+            pass_canvas,
+            OutputTransform::default(),
+            options.pass_output(*pass),
+        )?;
+    }
+    Ok(())
+}
+
+fn write_image(
+    options: &Options,
+    canvas: &Canvas,
+    output_transform: OutputTransform,
+    output: PathBuf,
+) -> Result<(), CliError> {
+    let io_error = |error| CliError::Io {
+        path: output.clone(),
+        error,
+    };
+    let file = File::create(&output).map_err(io_error)?;
+    options
+        .format()
+        .write(canvas, output_transform, file)
+        .map_err(io_error)?;
+
+    if !options.quiet {
+        println!("Written to {}", output.display());
+    }
+    Ok(())
+}
+
+fn scaled_size(size: f64) -> usize {
+    (size.round() as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_all_options() {
+        let options = parse_args(&args(
+            "-o out.ppm --format PPM --width 20 --height 10 -j 2 -s 4 \
+             -i path_tracing --seed 7 --exposure -1.5 --tone-map aces \
+             --encoding srgb --passes depth,object_id -q scene.json",
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            Options {
+                scene: "scene.json".to_string(),
+                output: Some(PathBuf::from("out.ppm")),
+                format: Some(Format::Ppm),
+                output_transform: OutputTransform::display().with_exposure(-1.5),
+                passes: vec![RenderPass::Depth, RenderPass::ObjectId],
+                width: Some(20),
+                height: Some(10),
+                threads: NonZeroUsize::new(2),
+                samples: NonZeroUsize::new(4),
+                integrator: Some(Integrator::PathTracing),
+                seed: Some(7),
+                quiet: true,
+            },
+            options
+        );
+    }
+
+    #[test]
+    fn help_skips_rendering() {
+        assert_eq!(None, parse_args(&args("patterns --help")).unwrap());
+    }
+
+    #[test]
+    fn invalid_arguments_are_usage_errors() {
+        for invalid_args in [
+            "",
+            "scene other_scene",
+            "scene --bogus",
+            "scene --width",
+            "scene --threads 0",
+            "scene --format gif",
+            "scene --integrator raster",
+            "scene --seed -1",
+            "scene --exposure bright",
+            "scene --tone-map gamma",
+            "scene --encoding rec709",
+            "scene --passes depth,beauty",
+        ] {
+            let error = parse_args(&args(invalid_args)).unwrap_err();
+            assert_eq!(2, error.exit_code(), "\"{invalid_args}\"");
+        }
+    }
+
+    #[test]
+    fn output_defaults_to_scene_name_and_format() {
+        let options = Options {
+            scene: "scenes/room.json".to_string(),
+            ..Options::default()
+        };
+
+        assert_eq!(PathBuf::from("room.ppm"), options.output());
+    }
+
+    #[test]
+    fn format_defaults_to_output_extension() {
+        let options = parse_args(&args("-o render.HDR scene")).unwrap().unwrap();
+        assert_eq!(Format::Hdr, options.format());
+
+        let options = parse_args(&args("-f pfm scene")).unwrap().unwrap();
+        assert_eq!(PathBuf::from("scene.pfm"), options.output());
+
+        let options = parse_args(&args("-o render.pam scene")).unwrap().unwrap();
+        assert_eq!(Format::Pam, options.format());
+    }
+
+    #[test]
+    fn passes_are_written_next_to_output() {
+        let options = parse_args(&args("-o renders/room.pfm scene"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            PathBuf::from("renders/room.normal.pfm"),
+            options.pass_output(RenderPass::Normal)
+        );
+    }
+
+    #[test]
+    fn demo_scenes_are_valid() {
+        for (name, _) in DEMO_SCENES {
+            assert!(load_scene(name).is_ok(), "{name} should load");
+        }
+    }
+
+    #[test]
+    fn scene_errors_and_io_errors_have_different_exit_codes() {
+        let missing_file = load_scene("does/not/exist.json").err().unwrap();
+        let invalid_scene = CliError::Scene {
+            source_name: "scene".to_string(),
+            error: Scene::parse("{}").err().unwrap(),
+        };
+
+        assert_eq!(4, missing_file.exit_code());
+        assert_eq!(3, invalid_scene.exit_code());
+    }
 }
@@ -1,6 +1,11 @@
+use crate::brdf::{
+    BlinnPhong, Brdf, BrdfModel, Ggx, Lambert, MetallicRoughness, OrenNayar, Phong, Reflectance,
+};
 use crate::color::Color;
+use crate::error::ValidationError;
 use crate::float_eq::FloatEq;
 use crate::pattern::Pattern;
+use crate::vector::Vector;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Material {
@@ -9,21 +14,171 @@ pub struct Material {
     pub diffuse: f64,   // usually 0..1
     pub specular: f64,  // usually 0..1
     pub shininess: f64, // usually 10..200
+    /// Light emitted by the surface, independently of any light source
+    pub emission: Color,
+    /// How light is reflected by the surface
+    pub brdf: BrdfModel,
+}
+
+/// Material whose values are checked when it's built, starting from the default material
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MaterialBuilder {
+    material: Material,
+}
+
+impl MaterialBuilder {
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.material.pattern = pattern;
+        self
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        self.with_pattern(Pattern::solid(color))
+    }
+
+    pub fn with_ambient(mut self, ambient: f64) -> Self {
+        self.material.ambient = ambient;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: f64) -> Self {
+        self.material.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f64) -> Self {
+        self.material.specular = specular;
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: f64) -> Self {
+        self.material.shininess = shininess;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.material.emission = emission;
+        self
+    }
+
+    pub fn with_brdf(mut self, brdf: BrdfModel) -> Self {
+        self.material.brdf = brdf;
+        self
+    }
+
+    pub fn build(self) -> Result<Material, ValidationError> {
+        self.material.validate()?;
+        Ok(self.material)
+    }
 }
 
 impl Material {
-    fn new(pattern: Pattern, ambient: f64, diffuse: f64, specular: f64, shininess: f64) -> Self {
-        assert!(ambient >= 0.0, "Ambient must be nonnegative");
-        assert!(diffuse >= 0.0, "Diffuse must be nonnegative");
-        assert!(specular >= 0.0, "Specular must be nonnegative");
-        assert!(shininess >= 0.0, "Shininess must be nonnegative");
+    pub fn builder() -> MaterialBuilder {
+        MaterialBuilder::default()
+    }
+
+    /// Check that coefficients and colors are neither negative nor NaN,
+    /// and that the reflection model and pattern are valid
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.pattern.validate()?;
+        ValidationError::check_minimum("ambient", self.ambient, 0.0)?;
+        ValidationError::check_minimum("diffuse", self.diffuse, 0.0)?;
+        ValidationError::check_minimum("specular", self.specular, 0.0)?;
+        ValidationError::check_minimum("shininess", self.shininess, 0.0)?;
+        ValidationError::check_color("emission", self.emission)?;
+        self.brdf.validate()
+    }
 
+    /// Physically based material, with glTF-style parameters (see `MetallicRoughness`)
+    pub fn metallic_roughness(pattern: Pattern, metallic: f64, roughness: f64, ior: f64) -> Self {
         Self {
             pattern,
-            ambient,
-            diffuse,
+            diffuse: 1.0,
+            specular: 1.0,
+            brdf: BrdfModel::MetallicRoughness {
+                metallic,
+                roughness,
+                ior,
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Equivalent physically based material, with the same color, ambient and
+    /// diffuse coefficients.
+    ///
+    /// Phong and Blinn-Phong materials become dielectrics (`metallic` = 0):
+    /// * their shininess gives the roughness, following the usual equivalence
+    ///   between a Blinn-Phong exponent `n` and a microfacet distribution of width
+    ///   `alpha` = sqrt(2 / (n + 2)), the Phong exponent being about a quarter of
+    ///   the Blinn-Phong one;
+    /// * their specular coefficient gives the reflectance at normal incidence
+    ///   (and so the index of refraction), chosen so that a light facing the
+    ///   surface makes a highlight as bright as with the original model.
+    ///
+    /// GGX materials keep their roughness and specular coefficient, and so the same
+    /// highlight. Other models become rough dielectrics without highlights.
+    pub fn to_metallic_roughness(self) -> Self {
+        let blinn_phong_alpha = |exponent: f64| (2.0 / (exponent + 2.0)).sqrt();
+        let (alpha, reflectance, specular) = match self.brdf {
+            BrdfModel::MetallicRoughness { .. } => return self,
+            BrdfModel::Phong | BrdfModel::BlinnPhong => {
+                let exponent = if self.brdf == BrdfModel::Phong {
+                    4.0 * self.shininess
+                } else {
+                    self.shininess
+                };
+                let alpha = blinn_phong_alpha(exponent);
+                // the GGX highlight peaks at reflectance / (4 alpha²) for a light
+                // facing the surface, while the Phong one peaks at `specular`
+                (alpha, 4.0 * alpha * alpha * self.specular, 1.0)
+            }
+            BrdfModel::Ggx { roughness } => (
+                roughness * roughness,
+                Ggx::DIELECTRIC_REFLECTANCE,
+                self.specular,
+            ),
+            BrdfModel::Lambert | BrdfModel::OrenNayar { .. } => (1.0, 0.0, 1.0),
+        };
+
+        Self {
             specular,
-            shininess,
+            brdf: BrdfModel::MetallicRoughness {
+                metallic: 0.0,
+                roughness: alpha.sqrt().min(1.0),
+                ior: MetallicRoughness::ior_from_reflectance(reflectance),
+            },
+            ..self
+        }
+    }
+
+    /// Light reflected towards the eye by the surface, of the given color, for
+    /// light of unit intensity arriving from `light_v` (see `Brdf`)
+    pub fn reflectance(
+        &self,
+        surface_color: Color,
+        light_v: Vector,
+        eye_v: Vector,
+        normal_v: Vector,
+    ) -> Reflectance {
+        match self.brdf {
+            BrdfModel::Lambert => Lambert.reflectance(light_v, eye_v, normal_v),
+            BrdfModel::Phong => Phong::new(self.shininess).reflectance(light_v, eye_v, normal_v),
+            BrdfModel::BlinnPhong => {
+                BlinnPhong::new(self.shininess).reflectance(light_v, eye_v, normal_v)
+            }
+            BrdfModel::OrenNayar { roughness } => {
+                OrenNayar::new(roughness).reflectance(light_v, eye_v, normal_v)
+            }
+            BrdfModel::Ggx { roughness } => {
+                Ggx::new(roughness).reflectance(light_v, eye_v, normal_v)
+            }
+            BrdfModel::MetallicRoughness {
+                metallic,
+                roughness,
+                ior,
+            } => MetallicRoughness::new(surface_color, metallic, roughness, ior)
+                .reflectance(light_v, eye_v, normal_v),
         }
     }
 }
@@ -36,6 +191,8 @@ impl Default for Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            emission: Color::black(),
+            brdf: BrdfModel::default(),
         }
     }
 }
@@ -47,6 +204,8 @@ impl PartialEq for Material {
             && self.diffuse.float_eq(other.diffuse)
             && self.specular.float_eq(other.specular)
             && self.shininess.float_eq(other.shininess)
+            && self.emission == other.emission
+            && self.brdf == other.brdf
     }
 }
 
@@ -63,5 +222,103 @@ mod tests {
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.emission, Color::black());
+        assert_eq!(m.brdf, BrdfModel::Phong);
+    }
+
+    #[test]
+    fn builder_checks_values() {
+        let material = Material::builder()
+            .with_color(Color::red())
+            .with_ambient(0.2)
+            .with_shininess(50.0)
+            .build();
+
+        assert_eq!(
+            Ok(Material {
+                pattern: Pattern::solid(Color::red()),
+                ambient: 0.2,
+                shininess: 50.0,
+                ..Material::default()
+            }),
+            material
+        );
+        assert_eq!(
+            Err(ValidationError::BelowMinimum {
+                field: "diffuse",
+                value: -0.5,
+                minimum: 0.0
+            }),
+            Material::builder().with_diffuse(-0.5).build()
+        );
+        assert_eq!(
+            Err(ValidationError::NotFinite { field: "specular" }),
+            Material::builder().with_specular(f64::NAN).build()
+        );
+        assert!(Material::builder()
+            .with_emission(Color::new(0.0, -1.0, 0.0))
+            .build()
+            .is_err());
+        assert!(Material::builder()
+            .with_brdf(BrdfModel::Ggx { roughness: 2.0 })
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn converting_phong_material_to_metallic_roughness() {
+        let phong = Material::default();
+
+        let converted = phong.to_metallic_roughness();
+
+        let BrdfModel::MetallicRoughness {
+            metallic,
+            roughness,
+            ..
+        } = converted.brdf
+        else {
+            panic!("{:?} is not physically based", converted.brdf);
+        };
+        assert_eq!(0.0, metallic);
+        assert!(roughness.float_eq(0.22349));
+        assert_eq!(phong.diffuse, converted.diffuse);
+
+        // same highlight, for a light behind the eye
+        let normal_v = Vector::new(0.0, 0.0, -1.0);
+        let highlight = |material: Material| {
+            material
+                .reflectance(Color::white(), normal_v, normal_v, normal_v)
+                .specular
+                * material.specular
+        };
+        assert_eq!(highlight(phong), highlight(converted));
+    }
+
+    #[test]
+    fn converting_ggx_material_to_metallic_roughness_keeps_its_highlight() {
+        let ggx = Material {
+            specular: 0.7,
+            brdf: BrdfModel::Ggx { roughness: 0.3 },
+            ..Material::default()
+        };
+
+        let converted = ggx.to_metallic_roughness();
+
+        let normal_v = Vector::new(0.0, 1.0, 0.0);
+        let light_v = Vector::new(0.6, 0.8, 0.0);
+        let highlight = |material: Material, eye_v: Vector| {
+            material
+                .reflectance(Color::white(), light_v, eye_v, normal_v)
+                .specular
+                * material.specular
+        };
+        for eye_v in [
+            Vector::new(-0.6, 0.8, 0.0),
+            normal_v,
+            Vector::new(-0.99, 0.141, 0.0).normalize(),
+        ] {
+            let difference = highlight(ggx, eye_v) - highlight(converted, eye_v);
+            assert!(difference.red.abs() < 1e-9, "{eye_v:?}: {difference:?}");
+        }
     }
 }
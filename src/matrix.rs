@@ -1,27 +1,48 @@
+use std::error::Error;
+use std::fmt;
 use std::ops;
 
+use crate::float::Float;
 use crate::float_eq::FloatEq;
 
+pub use crate::matrix::transform::{Decomposition, MotionError, Transform};
+
 mod matrix_2x2;
 mod matrix_3x3;
 mod matrix_4x4;
 mod matrix_nxn;
+mod transform;
 pub mod transformations;
 
 #[derive(Copy, Clone, Debug)]
-pub struct Matrix<const ROWS: usize, const COLS: usize>([[f64; COLS]; ROWS]);
+pub struct Matrix<const ROWS: usize, const COLS: usize, T: Float = f64>([[T; COLS]; ROWS]);
+
+/// Matrix which can't be inverted, eg. a transformation flattening objects
+/// with a scale of 0
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SingularMatrixError {
+    pub determinant: f64,
+}
 
-impl<const ROWS: usize, const COLS: usize> Matrix<ROWS, COLS> {
-    pub fn new(content: [[f64; COLS]; ROWS]) -> Self {
+impl<const ROWS: usize, const COLS: usize, T: Float> Matrix<ROWS, COLS, T> {
+    pub fn new(content: [[T; COLS]; ROWS]) -> Self {
         Self(content)
     }
 
     pub fn zeros() -> Self {
-        Self([[0.0; COLS]; ROWS])
+        Self([[T::ZERO; COLS]; ROWS])
+    }
+
+    /// Same matrix in precision `U`
+    pub fn cast<U: Float>(self) -> Matrix<ROWS, COLS, U> {
+        Matrix(
+            self.0
+                .map(|row| row.map(|value| U::from_f64(value.to_f64()))),
+        )
     }
 
-    pub fn transpose(self) -> Matrix<COLS, ROWS> {
-        let mut res: Matrix<COLS, ROWS> = Matrix::zeros();
+    pub fn transpose(self) -> Matrix<COLS, ROWS, T> {
+        let mut res: Matrix<COLS, ROWS, T> = Matrix::zeros();
         for r in 0..ROWS {
             for c in 0..COLS {
                 res[(c, r)] = self[(r, c)]
@@ -30,14 +51,18 @@ impl<const ROWS: usize, const COLS: usize> Matrix<ROWS, COLS> {
         res
     }
 
-    fn submatrix(
+    /// Matrix without the given row and column.
+    /// The size of the result is given explicitly, and checked at compile time.
+    fn submatrix<const SUB_ROWS: usize, const SUB_COLS: usize>(
         self,
         row_to_delete: usize,
         column_to_delete: usize,
-    ) -> Matrix<{ ROWS - 1 }, { COLS - 1 }> {
-        let mut res: Matrix<{ ROWS - 1 }, { COLS - 1 }> = Matrix::zeros();
-        for row in 0..(ROWS - 1) {
-            for col in 0..(COLS - 1) {
+    ) -> Matrix<SUB_ROWS, SUB_COLS, T> {
+        #[allow(clippy::let_unit_value)]
+        let () = SubmatrixSize::<ROWS, COLS, SUB_ROWS, SUB_COLS>::CHECK;
+        let mut res: Matrix<SUB_ROWS, SUB_COLS, T> = Matrix::zeros();
+        for row in 0..SUB_ROWS {
+            for col in 0..SUB_COLS {
                 let source_row = if row < row_to_delete { row } else { row + 1 };
                 let source_col = if col < column_to_delete { col } else { col + 1 };
 
@@ -48,7 +73,25 @@ impl<const ROWS: usize, const COLS: usize> Matrix<ROWS, COLS> {
     }
 }
 
-impl<const ROWS: usize, const COLS: usize> PartialEq for Matrix<ROWS, COLS> {
+/// Compile-time check of the size of submatrices, as `generic_const_exprs`
+/// doesn't allow `const { assert!(..) }` blocks in generic functions
+struct SubmatrixSize<
+    const ROWS: usize,
+    const COLS: usize,
+    const SUB_ROWS: usize,
+    const SUB_COLS: usize,
+>;
+
+impl<const ROWS: usize, const COLS: usize, const SUB_ROWS: usize, const SUB_COLS: usize>
+    SubmatrixSize<ROWS, COLS, SUB_ROWS, SUB_COLS>
+{
+    const CHECK: () = assert!(
+        SUB_ROWS + 1 == ROWS && SUB_COLS + 1 == COLS,
+        "A submatrix has one row and one column less than its matrix"
+    );
+}
+
+impl<const ROWS: usize, const COLS: usize, T: Float> PartialEq for Matrix<ROWS, COLS, T> {
     fn eq(&self, other: &Self) -> bool {
         for r in 0..ROWS {
             for c in 0..COLS {
@@ -61,27 +104,43 @@ impl<const ROWS: usize, const COLS: usize> PartialEq for Matrix<ROWS, COLS> {
     }
 }
 
-impl<const ROWS: usize, const COLS: usize> ops::Index<(usize, usize)> for Matrix<ROWS, COLS> {
-    type Output = f64;
+impl fmt::Display for SingularMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot invert matrix with determinant {}",
+            self.determinant
+        )
+    }
+}
+
+impl Error for SingularMatrixError {}
+
+impl<const ROWS: usize, const COLS: usize, T: Float> ops::Index<(usize, usize)>
+    for Matrix<ROWS, COLS, T>
+{
+    type Output = T;
 
     fn index(&self, index: (usize, usize)) -> &Self::Output {
         &self.0[index.0][index.1]
     }
 }
 
-impl<const ROWS: usize, const COLS: usize> ops::IndexMut<(usize, usize)> for Matrix<ROWS, COLS> {
+impl<const ROWS: usize, const COLS: usize, T: Float> ops::IndexMut<(usize, usize)>
+    for Matrix<ROWS, COLS, T>
+{
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
         &mut self.0[index.0][index.1]
     }
 }
 
-impl<const ROWS: usize, const COLS: usize, const OTHER_COLS: usize>
-    ops::Mul<Matrix<COLS, OTHER_COLS>> for Matrix<ROWS, COLS>
+impl<const ROWS: usize, const COLS: usize, const OTHER_COLS: usize, T: Float>
+    ops::Mul<Matrix<COLS, OTHER_COLS, T>> for Matrix<ROWS, COLS, T>
 {
-    type Output = Matrix<ROWS, OTHER_COLS>;
+    type Output = Matrix<ROWS, OTHER_COLS, T>;
 
-    fn mul(self, other: Matrix<COLS, OTHER_COLS>) -> Self::Output {
-        let mut result = Matrix::<ROWS, OTHER_COLS>::zeros();
+    fn mul(self, other: Matrix<COLS, OTHER_COLS, T>) -> Self::Output {
+        let mut result = Matrix::<ROWS, OTHER_COLS, T>::zeros();
 
         for r in 0..ROWS {
             for c in 0..OTHER_COLS {
@@ -92,14 +151,19 @@ impl<const ROWS: usize, const COLS: usize, const OTHER_COLS: usize>
     }
 }
 
-fn multiplication_element<const A_ROWS: usize, const A_COLS: usize, const B_COLS: usize>(
-    m1: Matrix<A_ROWS, A_COLS>,
-    m2: Matrix<A_COLS, B_COLS>,
+fn multiplication_element<
+    const A_ROWS: usize,
+    const A_COLS: usize,
+    const B_COLS: usize,
+    T: Float,
+>(
+    m1: Matrix<A_ROWS, A_COLS, T>,
+    m2: Matrix<A_COLS, B_COLS, T>,
     index: (usize, usize),
-) -> f64 {
+) -> T {
     let (target_row, target_col) = index;
 
-    let mut result = 0.0;
+    let mut result = T::ZERO;
     for i in 0..A_COLS {
         let m1_elem = m1[(target_row, i)];
         let m2_elem = m2[(i, target_col)];
@@ -257,7 +321,7 @@ mod tests {
 
         assert_eq!(
             Matrix::new([[-3.0, 2.0], [0.0, 6.0]]),
-            matrix.submatrix(0, 2)
+            matrix.submatrix::<2, 2>(0, 2)
         )
     }
 
@@ -271,6 +335,6 @@ mod tests {
         ]);
 
         let expected_result = Matrix::new([[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]]);
-        assert_eq!(expected_result, matrix.submatrix(2, 1))
+        assert_eq!(expected_result, matrix.submatrix::<3, 3>(2, 1))
     }
 }
@@ -1,9 +1,10 @@
+use crate::float::Float;
 use crate::matrix::Matrix;
 
 const SIZE: usize = 2;
 
-impl Matrix<SIZE, SIZE> {
-    pub fn determinant(self) -> f64 {
+impl<T: Float> Matrix<SIZE, SIZE, T> {
+    pub fn determinant(self) -> T {
         self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)]
     }
 }
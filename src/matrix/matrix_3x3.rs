@@ -1,23 +1,24 @@
+use crate::float::Float;
 use crate::matrix::Matrix;
 
 const SIZE: usize = 3;
 
-impl Matrix<SIZE, SIZE> {
-    pub fn minor(self, row: usize, column: usize) -> f64 {
-        self.submatrix(row, column).determinant()
+impl<T: Float> Matrix<SIZE, SIZE, T> {
+    pub fn minor(self, row: usize, column: usize) -> T {
+        self.submatrix::<2, 2>(row, column).determinant()
     }
 
-    pub fn cofactor(self, row: usize, column: usize) -> f64 {
+    pub fn cofactor(self, row: usize, column: usize) -> T {
         let res = self.minor(row, column);
 
-        if (row + column) % 2 == 0 {
+        if (row + column).is_multiple_of(2) {
             res
         } else {
             -res
         }
     }
 
-    pub fn determinant(self) -> f64 {
+    pub fn determinant(self) -> T {
         (0..SIZE)
             .map(|row| self[(row, 0)] * self.cofactor(row, 0))
             .sum()
@@ -30,7 +31,7 @@ mod tests {
     #[test]
     fn minor_of_3x3_matrix() {
         let matrix = Matrix::new([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
-        let sub = matrix.submatrix(1, 0);
+        let sub = matrix.submatrix::<2, 2>(1, 0);
 
         assert_eq!(25.0, sub.determinant());
         assert_eq!(25.0, matrix.minor(1, 0));
@@ -1,37 +1,51 @@
 use std::ops;
 
-use crate::float_eq::FloatEq;
-use crate::matrix::Matrix;
+use crate::float::Float;
+use crate::matrix::{Matrix, SingularMatrixError};
 use crate::point::Point;
 use crate::vector::Vector;
 
 const SIZE: usize = 4;
 
-impl Matrix<SIZE, SIZE> {
-    pub fn minor(self, row: usize, column: usize) -> f64 {
-        self.submatrix(row, column).determinant()
+impl<T: Float> Matrix<SIZE, SIZE, T> {
+    pub fn minor(self, row: usize, column: usize) -> T {
+        self.submatrix::<3, 3>(row, column).determinant()
     }
 
-    pub fn cofactor(self, row: usize, column: usize) -> f64 {
+    pub fn cofactor(self, row: usize, column: usize) -> T {
         let res = self.minor(row, column);
 
-        if (row + column) % 2 == 0 {
+        if (row + column).is_multiple_of(2) {
             res
         } else {
             -res
         }
     }
 
-    pub fn determinant(self) -> f64 {
+    pub fn determinant(self) -> T {
         (0..SIZE)
             .map(|row| self[(row, 0)] * self.cofactor(row, 0))
             .sum()
     }
 
+    /// # Panics
+    ///
+    /// If the matrix can't be inverted (see `try_inverse`).
     pub fn inverse(self) -> Self {
+        self.try_inverse().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Inverse of the matrix, unless its determinant is 0 (or not a number,
+    /// eg. for transformations computed from degenerate inputs).
+    ///
+    /// The determinant is compared with 0 exactly: small determinants are
+    /// legitimate, eg. 0.001 for a uniform scaling by 0.1.
+    pub fn try_inverse(self) -> Result<Self, SingularMatrixError> {
         let determinant = self.determinant();
-        if determinant.float_eq(0.0) {
-            panic!("Cannot invert matrix with determinant 0");
+        if determinant == T::ZERO || !determinant.is_finite() {
+            return Err(SingularMatrixError {
+                determinant: determinant.to_f64(),
+            });
         }
         let mut res = self.cofactor_matrix().transpose();
         for r in 0..SIZE {
@@ -39,7 +53,7 @@ impl Matrix<SIZE, SIZE> {
                 res[(r, c)] /= determinant;
             }
         }
-        res
+        Ok(res)
     }
 
     fn cofactor_matrix(self) -> Self {
@@ -53,11 +67,11 @@ impl Matrix<SIZE, SIZE> {
     }
 }
 
-impl ops::Mul<Point> for Matrix<4, 4> {
-    type Output = Point;
+impl<T: Float> ops::Mul<Point<T>> for Matrix<4, 4, T> {
+    type Output = Point<T>;
 
-    fn mul(self, point: Point) -> Self::Output {
-        let point_as_4x1_matrix = Matrix::new([[point.x], [point.y], [point.z], [Point::W]]);
+    fn mul(self, point: Point<T>) -> Self::Output {
+        let point_as_4x1_matrix = Matrix::new([[point.x], [point.y], [point.z], [Point::<T>::W]]);
         let result_as_matrix = self * point_as_4x1_matrix;
 
         Point::new(
@@ -68,11 +82,12 @@ impl ops::Mul<Point> for Matrix<4, 4> {
     }
 }
 
-impl ops::Mul<Vector> for Matrix<4, 4> {
-    type Output = Vector;
+impl<T: Float> ops::Mul<Vector<T>> for Matrix<4, 4, T> {
+    type Output = Vector<T>;
 
-    fn mul(self, vector: Vector) -> Self::Output {
-        let vector_as_4x1_matrix = Matrix::new([[vector.x], [vector.y], [vector.z], [Vector::W]]);
+    fn mul(self, vector: Vector<T>) -> Self::Output {
+        let vector_as_4x1_matrix =
+            Matrix::new([[vector.x], [vector.y], [vector.z], [Vector::<T>::W]]);
         let result_as_matrix = self * vector_as_4x1_matrix;
 
         Vector::new(
@@ -83,13 +98,13 @@ impl ops::Mul<Vector> for Matrix<4, 4> {
     }
 }
 
-impl ops::Shr<Matrix<4, 4>> for Matrix<4, 4> {
-    type Output = Matrix<4, 4>;
+impl<T: Float> ops::Shr<Matrix<4, 4, T>> for Matrix<4, 4, T> {
+    type Output = Matrix<4, 4, T>;
 
     /// Append a transformation matrix to another via the `>>` operator.
     /// The rhs transformation will be logically executed *after* the lhs one.
     #[allow(clippy::suspicious_arithmetic_impl)]
-    fn shr(self, other: Matrix<4, 4>) -> Self::Output {
+    fn shr(self, other: Matrix<4, 4, T>) -> Self::Output {
         other * self
     }
 }
@@ -97,6 +112,8 @@ impl ops::Shr<Matrix<4, 4>> for Matrix<4, 4> {
 #[cfg(test)]
 mod tests {
 
+    use crate::float_eq::FloatEq;
+
     use super::*;
 
     #[test]
@@ -199,7 +216,24 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn try_inverse_reports_singular_matrices() {
+        let flattening = crate::matrix::transformations::scaling(1.0, 0.0, 1.0);
+        let mut undefined = Matrix::identity();
+        undefined[(0, 0)] = f64::NAN;
+
+        assert_eq!(
+            Err(SingularMatrixError { determinant: 0.0 }),
+            flattening.try_inverse()
+        );
+        assert!(undefined.try_inverse().is_err());
+        assert_eq!(
+            Ok(Matrix::identity()),
+            Matrix::<4, 4>::identity().try_inverse()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot invert matrix with determinant 0")]
     fn inversion_panics_when_matrix_is_not_invertible() {
         let matrix = Matrix::new([
             [-4.0, 2.0, -2.0, 3.0],
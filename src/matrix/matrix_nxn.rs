@@ -1,10 +1,11 @@
+use crate::float::Float;
 use crate::matrix::Matrix;
 
-impl<const SIZE: usize> Matrix<SIZE, SIZE> {
+impl<const SIZE: usize, T: Float> Matrix<SIZE, SIZE, T> {
     pub fn identity() -> Self {
-        let mut res: Matrix<SIZE, SIZE> = Self::zeros();
+        let mut res: Matrix<SIZE, SIZE, T> = Self::zeros();
         for i in 0..SIZE {
-            res[(i, i)] = 1.0;
+            res[(i, i)] = T::ONE;
         }
         res
     }
@@ -0,0 +1,480 @@
+//! Building transformation matrices step by step, and breaking them down
+//! into their components.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::matrix::transformations;
+use crate::matrix::{Matrix, SingularMatrixError};
+use crate::quaternion::Quaternion;
+use crate::vector::Vector;
+
+/// Transformation built by applying operations in the order they are written:
+/// `Transform::identity().scale(2.0, 2.0, 2.0).translate(0.0, 1.0, 0.0)`
+/// scales objects *then* moves them up.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform(Matrix<4, 4>);
+
+/// Components of a transformation matrix, applied in this order:
+/// scale, shear, rotation around x, y and z, and translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decomposition {
+    pub scale: Vector,
+    /// `x_to_y`, `x_to_z` and `y_to_z` factors of `transformations::shearing`
+    pub shear: (f64, f64, f64),
+    /// Angles of the rotations around the x, y and z axes, in radians
+    pub rotation: Vector,
+    pub translation: Vector,
+}
+
+/// Motion between two transformations which can't be interpolated without
+/// flattening objects on the way (see `Matrix::check_motion`)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MotionError {
+    /// Either transformation can't be inverted
+    Singular(SingularMatrixError),
+    /// Either transformation isn't made of scale, shear, rotation and
+    /// translation, eg. a projection
+    NotDecomposable,
+    /// Only one of the transformations mirrors objects, which would be
+    /// flattened halfway through
+    MirroringChange,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self(Matrix::identity())
+    }
+
+    /// Apply `matrix` after the operations so far
+    pub fn then(self, matrix: Matrix<4, 4>) -> Self {
+        Self(self.0 >> matrix)
+    }
+
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(transformations::translation(x, y, z))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(transformations::scaling(x, y, z))
+    }
+
+    pub fn rotate_x(self, radians: f64) -> Self {
+        self.then(transformations::rotation_x(radians))
+    }
+
+    pub fn rotate_y(self, radians: f64) -> Self {
+        self.then(transformations::rotation_y(radians))
+    }
+
+    pub fn rotate_z(self, radians: f64) -> Self {
+        self.then(transformations::rotation_z(radians))
+    }
+
+    /// Rotation around an arbitrary axis through the origin
+    pub fn rotate(self, axis: Vector, radians: f64) -> Self {
+        self.then(transformations::rotation(axis, radians))
+    }
+
+    pub fn shear(
+        self,
+        x_to_y: f64,
+        x_to_z: f64,
+        y_to_x: f64,
+        y_to_z: f64,
+        z_to_x: f64,
+        z_to_y: f64,
+    ) -> Self {
+        self.then(transformations::shearing(
+            x_to_y, x_to_z, y_to_x, y_to_z, z_to_x, z_to_y,
+        ))
+    }
+
+    pub fn matrix(self) -> Matrix<4, 4> {
+        self.0
+    }
+}
+
+impl From<Transform> for Matrix<4, 4> {
+    fn from(transform: Transform) -> Self {
+        transform.matrix()
+    }
+}
+
+impl Decomposition {
+    /// Recompose the transformation
+    pub fn transform(self) -> Transform {
+        let (x_to_y, x_to_z, y_to_z) = self.shear;
+        Transform::identity()
+            .scale(self.scale.x, self.scale.y, self.scale.z)
+            .shear(x_to_y, x_to_z, 0.0, y_to_z, 0.0, 0.0)
+            .rotate_x(self.rotation.x)
+            .rotate_y(self.rotation.y)
+            .rotate_z(self.rotation.z)
+            .translate(self.translation.x, self.translation.y, self.translation.z)
+    }
+
+    pub fn rotation_quaternion(self) -> Quaternion {
+        let axis_rotation =
+            |x, y, z, radians| Quaternion::from_axis_angle(Vector::new(x, y, z), radians);
+        axis_rotation(0.0, 0.0, 1.0, self.rotation.z)
+            * axis_rotation(0.0, 1.0, 0.0, self.rotation.y)
+            * axis_rotation(1.0, 0.0, 0.0, self.rotation.x)
+    }
+}
+
+impl Matrix<4, 4> {
+    /// Break the transformation down into its scale, shear, rotation and translation.
+    ///
+    /// Returns `None` for matrices which aren't affine transformations (with a
+    /// last row other than `[0, 0, 0, 1]`), or which flatten objects (with a
+    /// scale of 0).
+    /// Mirroring transformations are given a negative scale along x.
+    pub fn decompose(self) -> Option<Decomposition> {
+        if [self[(3, 0)], self[(3, 1)], self[(3, 2)], self[(3, 3)]] != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+        let column = |c| Vector::new(self[(0, c)], self[(1, c)], self[(2, c)]);
+
+        // Gram-Schmidt orthonormalization of the columns: the rotation maps the
+        // axes to the resulting `axis_*`, the scale and shear are what remains
+        let mut scale_x = column(0).magnitude();
+        if scale_x < f64::EPSILON {
+            return None;
+        }
+        let mut axis_x = column(0) * (1.0 / scale_x);
+
+        let xy = axis_x.dot(column(1));
+        let orthogonal_y = column(1) - axis_x * xy;
+        let scale_y = orthogonal_y.magnitude();
+        if scale_y < f64::EPSILON {
+            return None;
+        }
+        let axis_y = orthogonal_y * (1.0 / scale_y);
+
+        let xz = axis_x.dot(column(2));
+        let yz = axis_y.dot(column(2));
+        let orthogonal_z = column(2) - axis_x * xz - axis_y * yz;
+        let scale_z = orthogonal_z.magnitude();
+        if scale_z < f64::EPSILON {
+            return None;
+        }
+        let axis_z = orthogonal_z * (1.0 / scale_z);
+
+        let mut shear = (xy / scale_y, xz / scale_z, yz / scale_z);
+        if axis_x.cross(axis_y).dot(axis_z) < 0.0 {
+            // mirrored: flip the x axis, to keep a rotation
+            axis_x = axis_x * -1.0;
+            scale_x = -scale_x;
+            shear = (-shear.0, -shear.1, shear.2);
+        }
+
+        Some(Decomposition {
+            scale: Vector::new(scale_x, scale_y, scale_z),
+            shear,
+            rotation: euler_angles([axis_x, axis_y, axis_z]),
+            translation: Vector::new(self[(0, 3)], self[(1, 3)], self[(2, 3)]),
+        })
+    }
+
+    /// Check that every transformation in between `self` and `other` can be
+    /// inverted, ie. that both can be inverted and decomposed, and that they
+    /// either both mirror objects or both don't
+    pub fn check_motion(self, other: Self) -> Result<(), MotionError> {
+        self.try_inverse()?;
+        other.try_inverse()?;
+        self.try_interpolate_transformation(other, 0.0)?;
+        Ok(())
+    }
+
+    /// Transformation in between `self` (`t` = 0) and `other` (`t` = 1),
+    /// interpolating their scale, shear and translation linearly and their
+    /// rotation along the shortest arc (see `Quaternion::slerp`).
+    ///
+    /// # Panics
+    ///
+    /// If the transformations can't be interpolated (see `check_motion`).
+    pub fn interpolate_transformation(self, other: Self, t: f64) -> Self {
+        self.try_interpolate_transformation(other, t)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `interpolate_transformation`, unless either transformation can't
+    /// be decomposed or only one of them mirrors objects.
+    ///
+    /// The scale along x is the only negative one of mirroring transformations
+    /// (see `decompose`), so the interpolated scales never go through 0.
+    pub fn try_interpolate_transformation(self, other: Self, t: f64) -> Result<Self, MotionError> {
+        let (Some(start), Some(end)) = (self.decompose(), other.decompose()) else {
+            return Err(MotionError::NotDecomposable);
+        };
+        if (start.scale.x < 0.0) != (end.scale.x < 0.0) {
+            return Err(MotionError::MirroringChange);
+        }
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let lerp_vector = |a: Vector, b: Vector| a + (b - a) * t;
+
+        let scale = lerp_vector(start.scale, end.scale);
+        let shear = (
+            lerp(start.shear.0, end.shear.0),
+            lerp(start.shear.1, end.shear.1),
+            lerp(start.shear.2, end.shear.2),
+        );
+        let rotation = start
+            .rotation_quaternion()
+            .slerp(end.rotation_quaternion(), t);
+        let translation = lerp_vector(start.translation, end.translation);
+
+        Ok(Transform::identity()
+            .scale(scale.x, scale.y, scale.z)
+            .shear(shear.0, shear.1, 0.0, shear.2, 0.0, 0.0)
+            .then(rotation.to_matrix())
+            .translate(translation.x, translation.y, translation.z)
+            .matrix())
+    }
+}
+
+impl From<SingularMatrixError> for MotionError {
+    fn from(error: SingularMatrixError) -> Self {
+        MotionError::Singular(error)
+    }
+}
+
+impl fmt::Display for MotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MotionError::Singular(error) => write!(f, "{error}"),
+            MotionError::NotDecomposable => write!(
+                f,
+                "Cannot interpolate transformations which aren't made of scale, shear, rotation and translation"
+            ),
+            MotionError::MirroringChange => write!(
+                f,
+                "Cannot interpolate between a mirroring transformation and one which doesn't mirror"
+            ),
+        }
+    }
+}
+
+impl Error for MotionError {}
+
+/// Angles of the rotations around x, then y, then z, giving the rotation
+/// matrix with the given columns
+fn euler_angles([column_x, column_y, column_z]: [Vector; 3]) -> Vector {
+    let y = (-column_x.z).clamp(-1.0, 1.0).asin();
+    if y.cos() > 1e-6 {
+        Vector::new(
+            column_y.z.atan2(column_z.z),
+            y,
+            column_x.y.atan2(column_x.x),
+        )
+    } else {
+        // gimbal lock: the rotations around x and z are around the same axis
+        Vector::new((-column_z.y).atan2(column_y.y), y, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::float_eq::FloatEq;
+    use crate::point::Point;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test]
+    fn operations_are_applied_in_reading_order() {
+        let transform = Transform::identity()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+
+        assert_eq!(
+            Point::new(15.0, 0.0, 7.0),
+            transform.matrix() * Point::new(1.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            transformations::rotation_x(PI / 2.0)
+                >> transformations::scaling(5.0, 5.0, 5.0)
+                >> transformations::translation(10.0, 5.0, 7.0),
+            Matrix::from(transform)
+        );
+    }
+
+    #[test]
+    fn decomposing_identity() {
+        assert_eq!(
+            Some(Decomposition {
+                scale: Vector::new(1.0, 1.0, 1.0),
+                shear: (0.0, 0.0, 0.0),
+                rotation: Vector::new(0.0, 0.0, 0.0),
+                translation: Vector::new(0.0, 0.0, 0.0),
+            }),
+            Matrix::identity().decompose()
+        );
+    }
+
+    #[test]
+    fn decomposing_scale_rotation_and_translation() {
+        let transform = Transform::identity()
+            .scale(2.0, 3.0, 0.5)
+            .rotate_x(0.3)
+            .rotate_y(-1.1)
+            .rotate_z(2.0)
+            .translate(1.0, -2.0, 3.0);
+
+        assert_eq!(
+            Some(Decomposition {
+                scale: Vector::new(2.0, 3.0, 0.5),
+                shear: (0.0, 0.0, 0.0),
+                rotation: Vector::new(0.3, -1.1, 2.0),
+                translation: Vector::new(1.0, -2.0, 3.0),
+            }),
+            transform.matrix().decompose()
+        );
+    }
+
+    #[test_case(Transform::identity().shear(0.5, -0.25, 0.0, 1.5, 0.0, 0.0).rotate_y(0.7) ; "upper shear")]
+    #[test_case(Transform::identity().shear(0.0, 0.0, 0.4, 0.0, 0.2, -0.3) ; "lower shear")]
+    #[test_case(Transform::identity().scale(-1.0, 2.0, 2.0).rotate_z(0.5) ; "mirror")]
+    #[test_case(Transform::identity().rotate_x(0.5).rotate_y(PI / 2.0).rotate_z(0.2) ; "gimbal lock")]
+    #[test_case(Transform::identity().translate(1.0, 2.0, 3.0).rotate_x(1.0).scale(1.0, 4.0, 2.0) ; "scale after rotation")]
+    fn decomposition_recomposes_the_matrix(transform: Transform) {
+        let decomposition = transform.matrix().decompose().unwrap();
+
+        assert_eq!(transform.matrix(), decomposition.transform().matrix());
+    }
+
+    #[test]
+    fn rotating_around_arbitrary_axis() {
+        let transform = Transform::identity()
+            .rotate(Vector::new(0.0, 0.0, 2.0), PI / 2.0)
+            .translate(1.0, 0.0, 0.0);
+
+        assert_eq!(
+            Point::new(1.0, 1.0, 0.0),
+            transform.matrix() * Point::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn decomposed_rotation_as_quaternion() {
+        let rotation = transformations::rotation(Vector::new(1.0, -2.0, 0.5), 2.0);
+
+        assert_eq!(
+            rotation,
+            rotation
+                .decompose()
+                .unwrap()
+                .rotation_quaternion()
+                .to_matrix()
+        );
+    }
+
+    #[test]
+    fn interpolating_transformations_keeps_rotations_rigid() {
+        let start = Transform::identity().scale(1.0, 2.0, 1.0);
+        let end = start.rotate_y(PI / 2.0).translate(4.0, 0.0, 0.0);
+
+        let halfway = start.matrix().interpolate_transformation(end.matrix(), 0.5);
+
+        assert_eq!(
+            start.rotate_y(PI / 4.0).translate(2.0, 0.0, 0.0).matrix(),
+            halfway
+        );
+        assert_eq!(
+            start.matrix(),
+            start.matrix().interpolate_transformation(end.matrix(), 0.0)
+        );
+        assert_eq!(
+            end.matrix(),
+            start.matrix().interpolate_transformation(end.matrix(), 1.0)
+        );
+    }
+
+    #[test]
+    fn interpolating_mirroring_transformations_keeps_them_invertible() {
+        let start = Transform::identity().scale(-1.0, 1.0, 1.0);
+        let end = Transform::identity()
+            .scale(-3.0, 1.0, 1.0)
+            .translate(2.0, 0.0, 0.0);
+
+        let halfway = start.matrix().interpolate_transformation(end.matrix(), 0.5);
+
+        assert_eq!(
+            Transform::identity()
+                .scale(-2.0, 1.0, 1.0)
+                .translate(1.0, 0.0, 0.0)
+                .matrix(),
+            halfway
+        );
+        assert!(halfway.try_inverse().is_ok());
+    }
+
+    #[test]
+    fn mirroring_can_not_be_turned_on_or_off_during_a_motion() {
+        let mirror = transformations::scaling(-1.0, 1.0, 1.0);
+
+        // the scale along x would go through 0 halfway through
+        assert_eq!(
+            Err(MotionError::MirroringChange),
+            Matrix::identity().try_interpolate_transformation(mirror, 0.5)
+        );
+        assert_eq!(
+            Err(MotionError::MirroringChange),
+            Matrix::identity().check_motion(mirror)
+        );
+    }
+
+    #[test]
+    fn transformations_which_can_not_be_decomposed_can_not_be_interpolated() {
+        let mut projection = Matrix::identity();
+        projection[(3, 2)] = 1.0;
+
+        assert_eq!(
+            Err(MotionError::NotDecomposable),
+            Matrix::identity().try_interpolate_transformation(projection, 0.5)
+        );
+        assert!(matches!(
+            Matrix::identity().check_motion(transformations::scaling(0.0, 1.0, 1.0)),
+            Err(MotionError::Singular(_))
+        ));
+    }
+
+    #[test]
+    fn half_turn_is_interpolated_through_a_rotation() {
+        // blending the elements of the matrices would flatten objects halfway through
+        let end = transformations::rotation_z(PI);
+
+        let halfway = Matrix::identity().interpolate_transformation(end, 0.5);
+
+        assert!(halfway.determinant().float_eq(1.0));
+        assert_eq!(Ok(()), Matrix::identity().check_motion(end));
+    }
+
+    #[test]
+    fn mirroring_gives_negative_scale() {
+        let decomposition = transformations::scaling(1.0, -1.0, 1.0)
+            .decompose()
+            .unwrap();
+
+        assert_eq!(Vector::new(-1.0, 1.0, 1.0), decomposition.scale);
+        // half a turn around z
+        assert_eq!(0.0, decomposition.rotation.x);
+        assert_eq!(0.0, decomposition.rotation.y);
+        assert_eq!(PI, decomposition.rotation.z.abs());
+    }
+
+    #[test]
+    fn flattening_and_projections_can_not_be_decomposed() {
+        let mut projection = Matrix::identity();
+        projection[(3, 2)] = 1.0;
+
+        assert_eq!(None, transformations::scaling(1.0, 0.0, 1.0).decompose());
+        assert_eq!(None, projection.decompose());
+    }
+}
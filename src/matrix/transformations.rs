@@ -1,4 +1,6 @@
 use crate::matrix::Matrix;
+use crate::quaternion::Quaternion;
+use crate::vector::Vector;
 
 pub fn translation(x: f64, y: f64, z: f64) -> Matrix<4, 4> {
     let mut result: Matrix<4, 4> = Matrix::identity();
@@ -52,6 +54,11 @@ pub fn rotation_z(radians: f64) -> Matrix<4, 4> {
     result
 }
 
+/// Rotation around an arbitrary axis through the origin (see `Quaternion::from_axis_angle`)
+pub fn rotation(axis: Vector, radians: f64) -> Matrix<4, 4> {
+    Quaternion::from_axis_angle(axis, radians).to_matrix()
+}
+
 pub fn shearing(
     x_to_y: f64,
     x_to_z: f64,
@@ -76,7 +83,7 @@ pub fn shearing(
 mod tests {
     use std::f64::consts::PI;
 
-    use crate::{point::Point, vector::Vector};
+    use crate::point::Point;
     use pretty_assertions::assert_eq;
     use test_case::test_case;
 
@@ -1,36 +1,212 @@
 use crate::color::Color;
+use crate::error::ValidationError;
+use crate::float::Float;
 use crate::material::Material;
-use crate::matrix::Matrix;
+use crate::matrix::{Matrix, MotionError, SingularMatrixError};
 use crate::point::Point;
 use crate::shape::Shape;
 use crate::vector::Vector;
 
+/// Shape placed in the world by its transformation, whose precision is `T`
+/// (objects are built in `f64`, see `cast`)
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Object {
+pub struct Object<T: Float = f64> {
     pub shape: Shape,
-    pub transformation: Matrix<4, 4>,
+    /// Transformation of the object at time 0, ie. when the shutter opens
+    transformation: Matrix<4, 4, T>,
+    /// Inverse of `transformation`, computed once as every ray needs it
+    inverse_transformation: Matrix<4, 4, T>,
+    /// Transformation of the object at time 1, ie. when the shutter closes.
+    /// Objects without an end transformation are static.
+    end_transformation: Option<Matrix<4, 4, T>>,
     pub material: Material,
 }
 
 impl Object {
+    /// # Panics
+    ///
+    /// If the transformation can't be inverted (see `try_sphere`).
     pub fn sphere(transformation: Matrix<4, 4>, material: Material) -> Self {
-        Self {
+        Self::try_sphere(transformation, material).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sphere, unless its transformation can't be inverted
+    pub fn try_sphere(
+        transformation: Matrix<4, 4>,
+        material: Material,
+    ) -> Result<Self, SingularMatrixError> {
+        Self::try_new(Shape::Sphere, transformation, material)
+    }
+
+    /// # Panics
+    ///
+    /// If the transformation can't be inverted (see `try_plane`).
+    pub fn plane(transformation: Matrix<4, 4>, material: Material) -> Self {
+        Self::try_plane(transformation, material).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Plane, unless its transformation can't be inverted
+    pub fn try_plane(
+        transformation: Matrix<4, 4>,
+        material: Material,
+    ) -> Result<Self, SingularMatrixError> {
+        Self::try_new(Shape::Plane, transformation, material)
+    }
+
+    fn try_new(
+        shape: Shape,
+        transformation: Matrix<4, 4>,
+        material: Material,
+    ) -> Result<Self, SingularMatrixError> {
+        Ok(Self {
             transformation,
+            inverse_transformation: transformation.try_inverse()?,
+            end_transformation: None,
             material,
-            shape: Shape::Sphere,
+            shape,
+        })
+    }
+
+    /// # Panics
+    ///
+    /// If the transformation can't be inverted (see `try_with_transformation`).
+    pub fn with_transformation(self, transformation: Matrix<4, 4>) -> Self {
+        self.try_with_transformation(transformation)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `with_transformation`, unless the transformation can't be inverted
+    /// or, for moving objects, can't be interpolated with the end transformation
+    pub fn try_with_transformation(
+        self,
+        transformation: Matrix<4, 4>,
+    ) -> Result<Self, MotionError> {
+        if let Some(end_transformation) = self.end_transformation {
+            transformation.check_motion(end_transformation)?;
         }
+        Ok(Self {
+            transformation,
+            inverse_transformation: transformation.try_inverse()?,
+            ..self
+        })
     }
 
-    pub fn plane(transformation: Matrix<4, 4>, material: Material) -> Self {
+    /// Make the object move during the exposure, from its current transformation
+    /// (at time 0) to `end_transformation` (at time 1).
+    ///
+    /// # Panics
+    ///
+    /// If the transformations in between can't all be inverted (see `try_with_motion`).
+    pub fn with_motion(self, end_transformation: Matrix<4, 4>) -> Self {
+        self.try_with_motion(end_transformation)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `with_motion`, unless some transformation in between can't be
+    /// inverted, eg. when only the end transformation mirrors the object
+    /// (see `Matrix::check_motion`)
+    pub fn try_with_motion(self, end_transformation: Matrix<4, 4>) -> Result<Self, MotionError> {
+        self.transformation.check_motion(end_transformation)?;
+        Ok(Self {
+            end_transformation: Some(end_transformation),
+            ..self
+        })
+    }
+}
+
+impl<T: Float> Object<T> {
+    /// Transformation of the object at time 0, ie. when the shutter opens
+    pub fn transformation(&self) -> Matrix<4, 4, T> {
+        self.transformation
+    }
+
+    /// Inverse of the transformation of the object at time 0
+    pub fn inverse_transformation(&self) -> Matrix<4, 4, T> {
+        self.inverse_transformation
+    }
+
+    /// Transformation of the object at time 1, or `None` if the object is static
+    pub fn end_transformation(&self) -> Option<Matrix<4, 4, T>> {
+        self.end_transformation
+    }
+
+    /// Same object, with its transformations in precision `U`
+    pub fn cast<U: Float>(&self) -> Object<U> {
+        Object {
+            shape: self.shape,
+            transformation: self.transformation.cast(),
+            inverse_transformation: self.inverse_transformation.cast(),
+            end_transformation: self.end_transformation.map(Matrix::cast),
+            material: self.material,
+        }
+    }
+
+    /// Check that the transformations at all times can be inverted and that
+    /// the material is valid
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let transformation = self.transformation.cast();
+        ValidationError::check_transformation("transformation", transformation)?;
+        if let Some(end_transformation) = self.end_transformation {
+            let end_transformation = end_transformation.cast();
+            ValidationError::check_transformation("end transformation", end_transformation)?;
+            ValidationError::check_motion(transformation, end_transformation)?;
+        }
+        self.material.validate()
+    }
+
+    /// Transformation of the object at the given time.
+    /// Intermediate transformations turn objects along the shortest arc between
+    /// their start and end rotations (see `Matrix::interpolate_transformation`),
+    /// interpolated in `f64`.
+    pub fn transformation_at(&self, time: f64) -> Matrix<4, 4, T> {
+        match self.end_transformation {
+            None => self.transformation,
+            Some(end_transformation) => self
+                .transformation
+                .cast::<f64>()
+                .interpolate_transformation(end_transformation.cast(), time.clamp(0.0, 1.0))
+                .cast(),
+        }
+    }
+
+    /// Inverse of the transformation of the object at the given time, unless it
+    /// can't be inverted or the motion of the object can't be interpolated
+    pub fn inverse_transformation_at(&self, time: f64) -> Option<Matrix<4, 4, T>> {
+        match self.end_transformation {
+            None => Some(self.inverse_transformation),
+            Some(end_transformation) => self
+                .transformation
+                .cast::<f64>()
+                .try_interpolate_transformation(end_transformation.cast(), time.clamp(0.0, 1.0))
+                .ok()?
+                .cast()
+                .try_inverse()
+                .ok(),
+        }
+    }
+
+    /// Static copy of the object, frozen in the position it has at the given time.
+    pub fn at_time(self, time: f64) -> Self {
+        let transformation = self.transformation_at(time);
         Self {
             transformation,
-            material,
-            shape: Shape::Plane,
+            inverse_transformation: transformation.inverse(),
+            end_transformation: None,
+            ..self
         }
     }
 
-    pub fn normal_at(self, world_point: Point) -> Vector {
-        let inverse_transformation = self.transformation.inverse();
+    pub fn normal_at(&self, world_point: Point<T>) -> Vector<T> {
+        self.normal_with_inverse(self.inverse_transformation, world_point)
+    }
+
+    /// Normal at the given point, with the object in the position given by the
+    /// inverse of its transformation (see `inverse_transformation_at`)
+    pub fn normal_with_inverse(
+        &self,
+        inverse_transformation: Matrix<4, 4, T>,
+        world_point: Point<T>,
+    ) -> Vector<T> {
         let object_point = inverse_transformation * world_point;
 
         let object_normal = self.shape.object_normal_at(object_point);
@@ -39,8 +215,21 @@ impl Object {
         world_normal.normalize()
     }
 
-    pub fn object_color_at(self, point: Point) -> Color {
-        self.material.pattern.object_color_at(self, point)
+    pub fn object_color_at(&self, point: Point<T>) -> Color {
+        self.color_with_inverse(self.inverse_transformation, point)
+    }
+
+    /// Color at the given point, with the object in the position given by the
+    /// inverse of its transformation (see `inverse_transformation_at`).
+    /// Patterns are computed in `f64`.
+    pub fn color_with_inverse(
+        &self,
+        inverse_transformation: Matrix<4, 4, T>,
+        point: Point<T>,
+    ) -> Color {
+        self.material
+            .pattern
+            .object_color_at(inverse_transformation.cast(), point.cast())
     }
 }
 
@@ -57,6 +246,9 @@ mod tests {
     use crate::color::Color;
     use crate::matrix::transformations;
     use crate::pattern::Pattern;
+    use crate::ray::Ray;
+    use crate::ray_packet::{RayPacket, PACKET_SIZE};
+    use crate::world::World;
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -70,11 +262,105 @@ mod tests {
 
     #[test]
     fn an_object_transformation_can_be_changed() {
-        let mut object = Object::default();
         let transformation = transformations::translation(1.0, 2.0, 3.0);
-        object.transformation = transformation;
+        let object = Object::default().with_transformation(transformation);
+
+        assert_eq!(transformation, object.transformation());
+        assert_eq!(
+            transformations::translation(-1.0, -2.0, -3.0),
+            object.inverse_transformation()
+        );
+    }
+
+    #[test]
+    fn an_object_is_static_by_default() {
+        let object = Object::default();
+
+        assert_eq!(None, object.end_transformation);
+        assert_eq!(object.transformation, object.transformation_at(0.7));
+    }
+
+    #[test]
+    fn transformation_of_moving_object_is_interpolated_over_time() {
+        let object = Object::default().with_motion(transformations::translation(2.0, 0.0, 0.0));
+
+        assert_eq!(Matrix::identity(), object.transformation_at(0.0));
+        assert_eq!(
+            transformations::translation(0.5, 0.0, 0.0),
+            object.transformation_at(0.25)
+        );
+        assert_eq!(
+            transformations::translation(2.0, 0.0, 0.0),
+            object.transformation_at(1.0)
+        );
+    }
+
+    #[test]
+    fn motions_which_flatten_the_object_are_rejected() {
+        let mirrored = transformations::scaling(-1.0, 1.0, 1.0);
 
-        assert_eq!(transformation, object.transformation);
+        assert_eq!(
+            Err(MotionError::MirroringChange),
+            Object::default().try_with_motion(mirrored)
+        );
+        assert!(Object::sphere(mirrored, Material::default())
+            .try_with_motion(mirrored >> transformations::translation(1.0, 0.0, 0.0))
+            .is_ok());
+
+        // objects built without checking their motion are reported by the validation
+        let object = Object {
+            end_transformation: Some(mirrored),
+            ..Object::default()
+        };
+        assert_eq!(None, object.inverse_transformation_at(0.5));
+        assert_eq!(
+            Err(ValidationError::InvalidMotion {
+                error: MotionError::MirroringChange
+            }),
+            object.validate()
+        );
+    }
+
+    #[test]
+    fn objects_whose_transformation_can_not_be_inverted_are_missed() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)).with_time(0.5);
+        let mirrored = Object {
+            end_transformation: Some(transformations::scaling(-1.0, 1.0, 1.0)),
+            ..Object::default()
+        };
+
+        assert_eq!(0, ray.intersect(&mirrored).count());
+        let world = World::new(vec![], vec![mirrored]);
+        assert_eq!(
+            [None; PACKET_SIZE],
+            RayPacket::new([ray; PACKET_SIZE]).hits(&world)
+        );
+    }
+
+    #[test]
+    fn objects_whose_transformation_can_not_be_inverted_are_rejected() {
+        let flat = transformations::scaling(1.0, 1.0, 0.0);
+        let singular = SingularMatrixError { determinant: 0.0 };
+
+        assert_eq!(Err(singular), Object::try_sphere(flat, Material::default()));
+        assert_eq!(Err(singular), Object::try_plane(flat, Material::default()));
+        assert_eq!(
+            Err(MotionError::Singular(singular)),
+            Object::default().try_with_transformation(flat)
+        );
+    }
+
+    #[test]
+    fn moving_object_can_be_frozen_at_a_given_time() {
+        let object = Object::default().with_motion(transformations::translation(2.0, 0.0, 0.0));
+
+        let frozen = object.at_time(0.5);
+
+        assert_eq!(None, frozen.end_transformation);
+        assert_eq!(
+            transformations::translation(1.0, 0.0, 0.0),
+            frozen.transformation
+        );
     }
 
     #[test]
@@ -84,10 +370,12 @@ mod tests {
         assert_eq!(normal, normal.normalize());
     }
 
+    // the values of the book, rounded to 5 decimals
     #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_normal_on_translated_object() {
-        let mut object = Object::default();
-        object.transformation = transformations::translation(0.0, 1.0, 0.0);
+        let object =
+            Object::default().with_transformation(transformations::translation(0.0, 1.0, 0.0));
 
         let normal = object.normal_at(Point::new(0.0, 1.70711, -0.70711));
 
@@ -96,10 +384,9 @@ mod tests {
 
     #[test]
     fn computing_normal_on_transformed_object() {
-        let mut object = Object::default();
         let transformation =
             transformations::scaling(1.0, 0.5, 1.0) * transformations::rotation_z(PI / 5.0);
-        object.transformation = transformation;
+        let object = Object::default().with_transformation(transformation);
 
         let normal = object.normal_at(Point::new(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0));
 
@@ -137,5 +424,7 @@ mod tests {
         let object = Object::sphere(transformation, material);
 
         let color = object.object_color_at(Point::new(1.5, 0.0, 0.0));
+
+        assert_eq!(Color::white(), color);
     }
 }
@@ -0,0 +1,226 @@
+//! Conversion of the linear radiance of a canvas to colors for display, when
+//! writing 8-bit images: exposure adjustment, tone mapping of the colors
+//! brighter than white, and encoding for the display.
+
+use crate::color::Color;
+
+/// Applied to every pixel when writing an 8-bit image.
+/// The default keeps the linear colors, only clamping them between 0 and 1.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OutputTransform {
+    /// Adjustment of the brightness, in stops (each one doubling the brightness)
+    pub exposure: f64,
+    pub tone_mapping: ToneMapping,
+    pub encoding: Encoding,
+}
+
+/// Compression of the unbounded range of colors into the displayable 0..1
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ToneMapping {
+    /// Colors are clamped, so highlights are blown out
+    #[default]
+    Clamp,
+    /// `x / (1 + x)` for each component, never reaching white
+    Reinhard,
+    /// Stephen Hill's fit of the ACES reference rendering and sRGB output transforms
+    AcesFitted,
+    /// John Hable's filmic curve from Uncharted 2, with a white point of 11.2
+    Filmic,
+}
+
+/// Encoding of the colors for the display
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// Colors written as they are, which looks too dark on most displays
+    #[default]
+    Linear,
+    /// Standard transfer function of the sRGB color space ("gamma" of about 2.2)
+    Srgb,
+}
+
+impl OutputTransform {
+    /// Tone mapping and sRGB encoding giving a natural look to most renders
+    pub fn display() -> Self {
+        Self {
+            exposure: 0.0,
+            tone_mapping: ToneMapping::AcesFitted,
+            encoding: Encoding::Srgb,
+        }
+    }
+
+    pub fn with_exposure(mut self, exposure: f64) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = tone_mapping;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Color to display, with components between 0 and 1
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = color * 2f64.powf(self.exposure);
+        let mapped = self.tone_mapping.apply(exposed);
+        let clamped = Color::new(
+            mapped.red.clamp(0.0, 1.0),
+            mapped.green.clamp(0.0, 1.0),
+            mapped.blue.clamp(0.0, 1.0),
+        );
+        self.encoding.apply(clamped)
+    }
+}
+
+impl ToneMapping {
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            ToneMapping::Clamp => color,
+            ToneMapping::Reinhard => map_components(color, |x| x / (1.0 + x)),
+            ToneMapping::AcesFitted => aces_fitted(color),
+            ToneMapping::Filmic => {
+                const WHITE_POINT: f64 = 11.2;
+                const EXPOSURE_BIAS: f64 = 2.0;
+                let white_scale = 1.0 / hable(WHITE_POINT);
+                map_components(color, |x| hable(x * EXPOSURE_BIAS) * white_scale)
+            }
+        }
+    }
+}
+
+impl Encoding {
+    /// Encode a color with components between 0 and 1
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            Encoding::Linear => color,
+            Encoding::Srgb => map_components(color, |x| {
+                if x <= 0.0031308 {
+                    12.92 * x
+                } else {
+                    1.055 * x.powf(1.0 / 2.4) - 0.055
+                }
+            }),
+        }
+    }
+}
+
+fn map_components(color: Color, f: impl Fn(f64) -> f64) -> Color {
+    Color::new(
+        f(color.red.max(0.0)),
+        f(color.green.max(0.0)),
+        f(color.blue.max(0.0)),
+    )
+}
+
+/// Tone mapping curve of Uncharted 2
+fn hable(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+/// See https://github.com/TheRealMJP/BakingLab/blob/master/BakingLab/ACES.hlsl
+fn aces_fitted(color: Color) -> Color {
+    // sRGB to the ACES working space, with the RRT saturation applied
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // ODT saturation, and back to sRGB
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let multiply = |matrix: [[f64; 3]; 3], color: Color| {
+        let [red, green, blue] =
+            matrix.map(|row| row[0] * color.red + row[1] * color.green + row[2] * color.blue);
+        Color::new(red, green, blue)
+    };
+
+    let fitted = map_components(multiply(INPUT, color), |x| {
+        let numerator = x * (x + 0.0245786) - 0.000090537;
+        let denominator = x * (0.983729 * x + 0.4329510) + 0.238081;
+        numerator / denominator
+    });
+    multiply(OUTPUT, fitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test]
+    fn default_transform_only_clamps() {
+        let transform = OutputTransform::default();
+
+        assert_eq!(
+            Color::new(1.0, 0.5, 0.0),
+            transform.apply(Color::new(1.5, 0.5, -0.5))
+        );
+    }
+
+    #[test]
+    fn exposure_is_in_stops() {
+        let transform = OutputTransform::default().with_exposure(-2.0);
+
+        assert_eq!(
+            Color::new(0.5, 0.125, 0.0),
+            transform.apply(Color::new(2.0, 0.5, 0.0))
+        );
+    }
+
+    #[test_case(0.0, 0.0 ; "black")]
+    #[test_case(0.002, 0.02584 ; "linear segment")]
+    #[test_case(0.18, 0.46135 ; "middle gray")]
+    #[test_case(1.0, 1.0 ; "white")]
+    fn srgb_encoding(linear: f64, encoded: f64) {
+        let transform = OutputTransform::default().with_encoding(Encoding::Srgb);
+
+        assert_eq!(
+            Color::new(encoded, encoded, encoded),
+            transform.apply(Color::new(linear, linear, linear))
+        );
+    }
+
+    #[test_case(ToneMapping::Reinhard)]
+    #[test_case(ToneMapping::AcesFitted)]
+    #[test_case(ToneMapping::Filmic)]
+    fn tone_mapping_keeps_highlights_distinct(tone_mapping: ToneMapping) {
+        let transform = OutputTransform::default().with_tone_mapping(tone_mapping);
+        let gray = |value| transform.apply(Color::new(value, value, value)).red;
+
+        assert_eq!(0.0, gray(0.0));
+        // increasing, and compressing bright colors instead of clamping them
+        let values: Vec<f64> = [0.1, 0.5, 1.0, 2.0, 4.0].into_iter().map(gray).collect();
+        assert!(
+            values.windows(2).all(|pair| pair[0] < pair[1]),
+            "{values:?}"
+        );
+        assert!(values.iter().all(|value| *value < 1.0), "{values:?}");
+    }
+
+    #[test]
+    fn filmic_maps_white_point_to_white() {
+        let transform = OutputTransform::default().with_tone_mapping(ToneMapping::Filmic);
+
+        assert_eq!(
+            Color::white(),
+            transform.apply(Color::new(11.2, 11.2, 11.2) * 0.5)
+        );
+    }
+
+    #[test]
+    fn aces_fitted_keeps_gray_neutral() {
+        let gray = ToneMapping::AcesFitted.apply(Color::new(0.18, 0.18, 0.18));
+
+        assert!((gray.red - gray.green).abs() < 0.001 && (gray.green - gray.blue).abs() < 0.001);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::output_transform::OutputTransform;
+use std::io;
+use std::io::Write;
+
+const MAX_VALUE: f64 = 255.0;
+
+/// Writes canvases in the Portable Arbitrary Map format (`.pam`), with 8 bits
+/// per component and the alpha of the pixels, for compositing renders over
+/// other images.
+///
+/// The alpha is straight (not premultiplied): the colors written are the
+/// foreground of the pixels (see `Canvas::foreground_at`), without the
+/// background of the render, divided by their alpha.
+pub struct PamWriter<W: io::Write> {
+    writer: io::BufWriter<W>,
+    output_transform: OutputTransform,
+}
+
+impl<W: io::Write> PamWriter<W> {
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            writer: io::BufWriter::new(writer),
+            output_transform: OutputTransform::default(),
+        }
+    }
+
+    /// Convert colors for display before writing them (linear and clamped by default)
+    pub fn with_output_transform(mut self, output_transform: OutputTransform) -> Self {
+        self.output_transform = output_transform;
+        self
+    }
+
+    pub fn write_canvas(&mut self, canvas: &Canvas) -> io::Result<()> {
+        write!(
+            self.writer,
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+            canvas.width(),
+            canvas.height()
+        )?;
+
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                let alpha = canvas.alpha_at(x, y).clamp(0.0, 1.0);
+                let straight = if alpha > 0.0 {
+                    canvas.foreground_at(x, y) * (1.0 / alpha)
+                } else {
+                    Color::black()
+                };
+                let color = self.output_transform.apply(straight).scale(0.0, MAX_VALUE);
+                for component in [color.red, color.green, color.blue, alpha * MAX_VALUE] {
+                    self.writer.write_all(&[component.round() as u8])?;
+                }
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::background::Background;
+    use crate::camera::Camera;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::matrix::Matrix;
+    use crate::object::Object;
+    use crate::point::Point;
+    use crate::vector::Vector;
+    use crate::view_transform::view_transform;
+    use crate::world::World;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn writes_colors_with_straight_alpha() {
+        let mut canvas = Canvas::new(3, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.5, 0.0));
+        canvas.write_pixel(1, 0, Color::new(0.25, 0.0, 0.5));
+        canvas.write_alpha(1, 0, 0.5);
+        canvas.write_alpha(2, 0, 0.0);
+        let mut pam_buffer: Vec<u8> = vec![];
+
+        PamWriter::from_writer(&mut pam_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let mut expected =
+            b"P7\nWIDTH 3\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+        expected.extend([255, 128, 0, 255, 128, 0, 255, 128, 0, 0, 0, 0]);
+        assert_eq!(expected, pam_buffer);
+    }
+
+    #[test]
+    fn output_transform_is_applied_before_alpha() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(0.5, 0.5, 0.5));
+        canvas.write_alpha(0, 0, 0.5);
+        let mut pam_buffer: Vec<u8> = vec![];
+
+        PamWriter::from_writer(&mut pam_buffer)
+            .with_output_transform(OutputTransform::default().with_exposure(-1.0))
+            .write_canvas(&canvas)
+            .unwrap();
+
+        assert_eq!(&[128, 128, 128, 128], &pam_buffer[pam_buffer.len() - 4..]);
+    }
+
+    #[test]
+    fn background_is_left_out_of_partially_covered_pixels() {
+        let red = Material::builder()
+            .with_color(Color::new(1.0, 0.0, 0.0))
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0)
+            .build()
+            .unwrap();
+        let world = World::new(
+            vec![Light::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            vec![Object::sphere(Matrix::identity(), red)],
+        )
+        .with_background(Background::Constant(Color::new(1.0, 1.0, 1.0)));
+        let canvas = Camera::new(11, 7, PI / 2.0)
+            .with_transform(view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .with_samples_per_pixel(16)
+            .with_progress(false)
+            .render(world);
+        let alpha = canvas.alpha_at(4, 2);
+        assert!(alpha > 0.0 && alpha < 1.0, "{alpha}");
+        let mut pam_buffer: Vec<u8> = vec![];
+
+        PamWriter::from_writer(&mut pam_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let header_length = pam_buffer.len() - 11 * 7 * 4;
+        let edge = header_length + (2 * 11 + 4) * 4;
+        assert_eq!(&[255, 0, 0], &pam_buffer[edge..edge + 3]);
+    }
+}
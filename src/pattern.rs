@@ -1,6 +1,6 @@
 use crate::color::Color;
-use crate::matrix::Matrix;
-use crate::object::Object;
+use crate::error::ValidationError;
+use crate::matrix::{Matrix, SingularMatrixError};
 use crate::pattern::stripe::StripeProperties;
 use crate::point::Point;
 
@@ -17,19 +17,55 @@ impl Pattern {
         Pattern::Solid(color)
     }
 
+    /// # Panics
+    ///
+    /// If the transformation can't be inverted (see `try_stripe`).
     pub fn stripe(first_color: Color, second_color: Color, transformation: Matrix<4, 4>) -> Self {
-        Pattern::Stripe(StripeProperties::new(
+        Self::try_stripe(first_color, second_color, transformation)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Stripe pattern, unless its transformation can't be inverted
+    pub fn try_stripe(
+        first_color: Color,
+        second_color: Color,
+        transformation: Matrix<4, 4>,
+    ) -> Result<Self, SingularMatrixError> {
+        transformation.try_inverse()?;
+        Ok(Pattern::Stripe(StripeProperties::new(
             first_color,
             second_color,
             transformation,
-        ))
+        )))
+    }
+
+    /// Check that colors are neither negative nor NaN, and that the
+    /// transformation can be inverted
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            Pattern::Solid(color) => ValidationError::check_color("color", *color),
+            Pattern::Stripe(stripe_properties) => {
+                ValidationError::check_color("stripe color", stripe_properties.first_stripe())?;
+                ValidationError::check_color("stripe color", stripe_properties.second_stripe())?;
+                ValidationError::check_transformation(
+                    "pattern transformation",
+                    stripe_properties.transformation(),
+                )
+            }
+        }
     }
 
-    /// Color of pattern at point in object space
-    pub fn object_color_at(&self, object: Object, point: Point) -> Color {
+    /// Color of pattern at point on the object whose transformation has the given inverse
+    pub fn object_color_at(
+        &self,
+        object_inverse_transformation: Matrix<4, 4>,
+        point: Point,
+    ) -> Color {
         match self {
             Pattern::Solid(color) => *color,
-            Pattern::Stripe(stripe_properties) => stripe_properties.object_color_at(object, point),
+            Pattern::Stripe(stripe_properties) => {
+                stripe_properties.object_color_at(object_inverse_transformation, point)
+            }
         }
     }
 }
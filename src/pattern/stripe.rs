@@ -1,6 +1,5 @@
 use crate::color::Color;
 use crate::matrix::Matrix;
-use crate::object::Object;
 use crate::point::Point;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -19,9 +18,25 @@ impl StripeProperties {
         }
     }
 
-    pub fn object_color_at(&self, object: Object, point: Point) -> Color {
+    pub fn first_stripe(&self) -> Color {
+        self.first_stripe
+    }
+
+    pub fn second_stripe(&self) -> Color {
+        self.second_stripe
+    }
+
+    pub fn transformation(&self) -> Matrix<4, 4> {
+        self.transformation
+    }
+
+    pub fn object_color_at(
+        &self,
+        object_inverse_transformation: Matrix<4, 4>,
+        point: Point,
+    ) -> Color {
         let combined_transformations =
-            object.transformation.inverse() >> self.transformation.inverse();
+            object_inverse_transformation >> self.transformation.inverse();
 
         let object_space_point = combined_transformations * point;
         let x_is_even = object_space_point.x.floor() as i64 % 2 == 0;
@@ -37,7 +52,6 @@ impl StripeProperties {
 #[cfg(test)]
 mod tests {
     use crate::matrix::transformations;
-    use crate::object::Object;
 
     use super::*;
 
@@ -47,15 +61,15 @@ mod tests {
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 0.0, 0.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 1.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 1.0, 0.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 2.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 2.0, 0.0))
         )
     }
 
@@ -65,15 +79,15 @@ mod tests {
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 0.0, 0.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 0.0, 1.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 0.0, 1.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 0.0, 2.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 0.0, 2.0))
         )
     }
 
@@ -83,41 +97,38 @@ mod tests {
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.0, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.0, 0.0, 0.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(0.9, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(0.9, 0.0, 0.0))
         );
         assert_eq!(
             Color::black(),
-            pattern.object_color_at(Object::default(), Point::new(1.0, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(1.0, 0.0, 0.0))
         );
         assert_eq!(
             Color::black(),
-            pattern.object_color_at(Object::default(), Point::new(-0.1, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(-0.1, 0.0, 0.0))
         );
         assert_eq!(
             Color::black(),
-            pattern.object_color_at(Object::default(), Point::new(-1.0, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(-1.0, 0.0, 0.0))
         );
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(-1.1, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(-1.1, 0.0, 0.0))
         )
     }
 
     #[test]
     fn stripe_pattern_adapts_to_object_transformation() {
         let pattern = StripeProperties::new(Color::white(), Color::black(), Matrix::identity());
-        let object = Object {
-            transformation: transformations::scaling(2.0, 2.0, 2.0),
-            ..Object::default()
-        };
+        let object_inverse_transformation = transformations::scaling(2.0, 2.0, 2.0).inverse();
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(object, Point::new(1.5, 0.0, 0.0))
+            pattern.object_color_at(object_inverse_transformation, Point::new(1.5, 0.0, 0.0))
         );
     }
 
@@ -131,7 +142,7 @@ mod tests {
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(Object::default(), Point::new(1.5, 0.0, 0.0))
+            pattern.object_color_at(Matrix::identity(), Point::new(1.5, 0.0, 0.0))
         );
     }
 
@@ -142,14 +153,11 @@ mod tests {
             Color::black(),
             transformations::translation(0.5, 0.0, 0.0),
         );
-        let object = Object {
-            transformation: transformations::scaling(2.0, 2.0, 2.0),
-            ..Object::default()
-        };
+        let object_inverse_transformation = transformations::scaling(2.0, 2.0, 2.0).inverse();
 
         assert_eq!(
             Color::white(),
-            pattern.object_color_at(object, Point::new(2.5, 0.0, 0.0))
+            pattern.object_color_at(object_inverse_transformation, Point::new(2.5, 0.0, 0.0))
         );
     }
 }
@@ -0,0 +1,131 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io;
+use std::io::Read;
+
+/// Reads canvases in the Portable Float Map format (`.pfm`), in color (`PF`)
+/// or grayscale (`Pf`), with either byte order.
+pub struct PfmReader<R: io::Read> {
+    reader: io::BufReader<R>,
+}
+
+impl<R: io::Read> PfmReader<R> {
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader: io::BufReader::new(reader),
+        }
+    }
+
+    pub fn read_canvas(&mut self) -> io::Result<Canvas> {
+        let channels = match self.read_token()?.as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(invalid_data("Not a PFM file")),
+        };
+        let width = self.read_number::<usize>()?;
+        let height = self.read_number::<usize>()?;
+        let scale = self.read_number::<f32>()?;
+        if scale == 0.0 {
+            return Err(invalid_data("Invalid scale 0"));
+        }
+        let little_endian = scale < 0.0;
+
+        let mut canvas = Canvas::new(width, height);
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let mut components = [0.0; 3];
+                for component in components.iter_mut().take(channels) {
+                    let mut bytes = [0; 4];
+                    self.reader.read_exact(&mut bytes)?;
+                    *component = f64::from(if little_endian {
+                        f32::from_le_bytes(bytes)
+                    } else {
+                        f32::from_be_bytes(bytes)
+                    });
+                }
+                let [red, green, blue] = if channels == 1 {
+                    [components[0]; 3]
+                } else {
+                    components
+                };
+                canvas.write_pixel(x, y, Color::new(red, green, blue));
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Header field, followed by a single whitespace character
+    fn read_token(&mut self) -> io::Result<String> {
+        let mut token = String::new();
+        for byte in self.reader.by_ref().bytes() {
+            let byte = byte?;
+            if byte.is_ascii_whitespace() {
+                if token.is_empty() {
+                    continue;
+                }
+                return Ok(token);
+            }
+            token.push(char::from(byte));
+        }
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+
+    fn read_number<T: std::str::FromStr>(&mut self) -> io::Result<T> {
+        let token = self.read_token()?;
+        token
+            .parse()
+            .map_err(|_| invalid_data(format!("Invalid number \"{token}\" in header")))
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pfm_writer::PfmWriter;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn reads_back_written_canvas() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1000.0, 0.5, -0.25));
+        canvas.write_pixel(2, 1, Color::new(0.125, 2.0, 3.0));
+        let mut pfm_buffer: Vec<u8> = vec![];
+        PfmWriter::from_writer(&mut pfm_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let read = PfmReader::from_reader(pfm_buffer.as_slice())
+            .read_canvas()
+            .unwrap();
+
+        assert_eq!(format!("{canvas:?}"), format!("{read:?}"));
+    }
+
+    #[test]
+    fn reads_big_endian_grayscale() {
+        let mut pfm_buffer = b"Pf\n2 1\n1.0\n".to_vec();
+        pfm_buffer.extend(0.5f32.to_be_bytes());
+        pfm_buffer.extend(4.0f32.to_be_bytes());
+
+        let read = PfmReader::from_reader(pfm_buffer.as_slice())
+            .read_canvas()
+            .unwrap();
+
+        assert_eq!(Color::new(0.5, 0.5, 0.5), read.pixel_at(0, 0));
+        assert_eq!(Color::new(4.0, 4.0, 4.0), read.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        let error = PfmReader::from_reader(b"P3\n1 1\n255\n0 0 0\n".as_slice())
+            .read_canvas()
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::canvas::Canvas;
+use std::io;
+use std::io::Write;
+
+const PFM_COLOR_IDENTIFIER: &[u8] = b"PF";
+/// Negative scale, meaning that the values are little-endian
+const LITTLE_ENDIAN_SCALE: &[u8] = b"-1.0";
+
+/// Writes canvases in the Portable Float Map format (`.pfm`), which stores
+/// every component as a 32-bit float.
+///
+/// Rows are written from the bottom of the image to the top, as the format requires.
+pub struct PfmWriter<W: io::Write> {
+    writer: io::BufWriter<W>,
+}
+
+impl<W: io::Write> PfmWriter<W> {
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            writer: io::BufWriter::new(writer),
+        }
+    }
+
+    pub fn write_canvas(&mut self, canvas: &Canvas) -> io::Result<()> {
+        self.writer.write_all(PFM_COLOR_IDENTIFIER)?;
+        write!(self.writer, "\n{} {}\n", canvas.width(), canvas.height())?;
+        self.writer.write_all(LITTLE_ENDIAN_SCALE)?;
+        self.writer.write_all(b"\n")?;
+
+        for row in canvas.iter_rows().rev() {
+            for pixel in row {
+                for component in [pixel.red, pixel.green, pixel.blue] {
+                    self.writer.write_all(&(component as f32).to_le_bytes())?;
+                }
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn writes_bottom_row_first() {
+        let mut canvas = Canvas::new(1, 2);
+        canvas.write_pixel(0, 1, Color::new(2.5, -1.0, 0.0));
+        let mut pfm_buffer: Vec<u8> = vec![];
+
+        PfmWriter::from_writer(&mut pfm_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let mut expected = b"PF\n1 2\n-1.0\n".to_vec();
+        for component in [2.5f32, -1.0, 0.0, 0.0, 0.0, 0.0] {
+            expected.extend(component.to_le_bytes());
+        }
+        assert_eq!(expected, pfm_buffer);
+    }
+}
@@ -1,51 +1,60 @@
-use crate::{float_eq::FloatEq, vector::Vector};
+use crate::{float::Float, float_eq::FloatEq, vector::Vector};
 use std::ops;
 
 #[derive(Copy, Clone, Debug)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+pub struct Point<T: Float = f64> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
-impl Point {
-    pub const W: f64 = 1.0;
+impl<T: Float> Point<T> {
+    pub const W: T = T::ONE;
 
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: T, y: T, z: T) -> Self {
         Self { x, y, z }
     }
 
     pub fn origin() -> Self {
-        Self::new(0.0, 0.0, 0.0)
+        Self::new(T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    /// Same point in precision `U`
+    pub fn cast<U: Float>(self) -> Point<U> {
+        Point::new(
+            U::from_f64(self.x.to_f64()),
+            U::from_f64(self.y.to_f64()),
+            U::from_f64(self.z.to_f64()),
+        )
     }
 }
 
-impl PartialEq for Point {
+impl<T: Float> PartialEq for Point<T> {
     fn eq(&self, other: &Self) -> bool {
         self.x.float_eq(other.x) && self.y.float_eq(other.y) && self.z.float_eq(other.z)
     }
 }
 
-impl ops::Add<Vector> for Point {
-    type Output = Point;
+impl<T: Float> ops::Add<Vector<T>> for Point<T> {
+    type Output = Point<T>;
 
-    fn add(self, vector: Vector) -> Self::Output {
+    fn add(self, vector: Vector<T>) -> Self::Output {
         Point::new(self.x + vector.x, self.y + vector.y, self.z + vector.z)
     }
 }
 
-impl ops::Sub<Vector> for Point {
-    type Output = Point;
+impl<T: Float> ops::Sub<Vector<T>> for Point<T> {
+    type Output = Point<T>;
 
-    fn sub(self, vector: Vector) -> Self::Output {
+    fn sub(self, vector: Vector<T>) -> Self::Output {
         Point::new(self.x - vector.x, self.y - vector.y, self.z - vector.z)
     }
 }
 
-impl ops::Sub<Point> for Point {
-    type Output = Vector;
+impl<T: Float> ops::Sub<Point<T>> for Point<T> {
+    type Output = Vector<T>;
 
-    fn sub(self, other: Point) -> Self::Output {
+    fn sub(self, other: Point<T>) -> Self::Output {
         Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
     }
 }
@@ -0,0 +1,178 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io;
+use std::io::Read;
+
+/// Reads canvases in the PPM format, either plain (`P3`, as written by
+/// `PpmWriter`) or raw (`P6`), with up to 16 bits per component.
+///
+/// Components are scaled between 0 and 1, without decoding them.
+pub struct PpmReader<R: io::Read> {
+    reader: io::BufReader<R>,
+}
+
+impl<R: io::Read> PpmReader<R> {
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader: io::BufReader::new(reader),
+        }
+    }
+
+    pub fn read_canvas(&mut self) -> io::Result<Canvas> {
+        let raw = match self.read_token()?.as_str() {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(invalid_data("Not a PPM file")),
+        };
+        let width = self.read_number()?;
+        let height = self.read_number()?;
+        let max_value = self.read_number()?;
+        if !(1..=u16::MAX as usize).contains(&max_value) {
+            return Err(invalid_data(format!("Invalid maximum value {max_value}")));
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut components = [0.0; 3];
+                for component in components.iter_mut() {
+                    let value = if raw {
+                        self.read_raw_value(max_value)?
+                    } else {
+                        self.read_number()?
+                    };
+                    if value > max_value {
+                        return Err(invalid_data(format!(
+                            "Value {value} is greater than the maximum {max_value}"
+                        )));
+                    }
+                    *component = value as f64 / max_value as f64;
+                }
+                let [red, green, blue] = components;
+                canvas.write_pixel(x, y, Color::new(red, green, blue));
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Header field or plain value, skipping whitespace and comments before it
+    /// and consuming the single whitespace character after it
+    fn read_token(&mut self) -> io::Result<String> {
+        let mut token = String::new();
+        let mut in_comment = false;
+        for byte in self.reader.by_ref().bytes() {
+            let byte = byte?;
+            if in_comment {
+                in_comment = byte != b'\n';
+            } else if byte == b'#' && token.is_empty() {
+                in_comment = true;
+            } else if byte.is_ascii_whitespace() {
+                if !token.is_empty() {
+                    return Ok(token);
+                }
+            } else {
+                token.push(char::from(byte));
+            }
+        }
+        if token.is_empty() {
+            Err(io::ErrorKind::UnexpectedEof.into())
+        } else {
+            Ok(token)
+        }
+    }
+
+    fn read_number(&mut self) -> io::Result<usize> {
+        let token = self.read_token()?;
+        token
+            .parse()
+            .map_err(|_| invalid_data(format!("Invalid number \"{token}\"")))
+    }
+
+    /// Binary value, on two big-endian bytes if the maximum value needs them
+    fn read_raw_value(&mut self, max_value: usize) -> io::Result<usize> {
+        if max_value < 256 {
+            let mut byte = [0; 1];
+            self.reader.read_exact(&mut byte)?;
+            Ok(usize::from(byte[0]))
+        } else {
+            let mut bytes = [0; 2];
+            self.reader.read_exact(&mut bytes)?;
+            Ok(usize::from(u16::from_be_bytes(bytes)))
+        }
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ppm_writer::PpmWriter;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn reads_back_written_canvas() {
+        let mut canvas = Canvas::new(30, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.2, 0.0));
+        canvas.write_pixel(29, 1, Color::new(0.6, 0.0, 0.4));
+        let mut ppm_buffer: Vec<u8> = vec![];
+        PpmWriter::from_writer(&mut ppm_buffer)
+            .write_canvas(&canvas)
+            .unwrap();
+
+        let read = PpmReader::from_reader(ppm_buffer.as_slice())
+            .read_canvas()
+            .unwrap();
+
+        assert_eq!(format!("{canvas:?}"), format!("{read:?}"));
+    }
+
+    #[test]
+    fn reads_comments_and_raw_values() {
+        let mut ppm_buffer = b"P6 # raw\n2 1\n# two pixels\n255\n".to_vec();
+        ppm_buffer.extend([255, 0, 51, 0, 0, 0]);
+
+        let read = PpmReader::from_reader(ppm_buffer.as_slice())
+            .read_canvas()
+            .unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.2), read.pixel_at(0, 0));
+        assert_eq!(Color::black(), read.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn reads_16_bit_values() {
+        let ppm = "P3\n1 1\n65535\n65535 0 32768\n";
+
+        let read = PpmReader::from_reader(ppm.as_bytes())
+            .read_canvas()
+            .unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.5), read.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        for ppm in [
+            "PF\n1 1\n-1.0\n",
+            "P3\n1 1\n255\n0 0\n",
+            "P3\n1 1\n255\n0 0 256\n",
+            "P3\n1 1\n0\n0 0 0\n",
+        ] {
+            let error = PpmReader::from_reader(ppm.as_bytes())
+                .read_canvas()
+                .unwrap_err();
+
+            assert!(
+                matches!(
+                    error.kind(),
+                    io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                ),
+                "{ppm:?}: {error}"
+            );
+        }
+    }
+}
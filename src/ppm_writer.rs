@@ -1,4 +1,5 @@
 use crate::canvas::Canvas;
+use crate::output_transform::OutputTransform;
 use std::io;
 use std::io::Write;
 
@@ -9,15 +10,23 @@ const MAX_PPM_BODY_ROW_LENGTH: usize = 70;
 
 pub struct PpmWriter<W: io::Write> {
     writer: io::BufWriter<W>,
+    output_transform: OutputTransform,
 }
 
 impl<W: io::Write> PpmWriter<W> {
     pub fn from_writer(writer: W) -> Self {
         Self {
             writer: io::BufWriter::new(writer),
+            output_transform: OutputTransform::default(),
         }
     }
 
+    /// Convert colors for display before writing them (linear and clamped by default)
+    pub fn with_output_transform(mut self, output_transform: OutputTransform) -> Self {
+        self.output_transform = output_transform;
+        self
+    }
+
     pub fn write_canvas(&mut self, canvas: &Canvas) -> io::Result<()> {
         self.write_header(canvas)?;
         self.write_body(canvas)?;
@@ -42,7 +51,10 @@ impl<W: io::Write> PpmWriter<W> {
             let mut row_length = 0;
 
             for pixel in row.iter() {
-                let pixel_scaled = pixel.scale(MIN_PIXEL_VALUE, MAX_PIXEL_VALUE);
+                let pixel_scaled = self
+                    .output_transform
+                    .apply(*pixel)
+                    .scale(MIN_PIXEL_VALUE, MAX_PIXEL_VALUE);
 
                 for component in [pixel_scaled.red, pixel_scaled.green, pixel_scaled.blue] {
                     let component_formatted = (component.round() as i32).to_string();
@@ -78,6 +90,7 @@ impl<W: io::Write> PpmWriter<W> {
 #[cfg(test)]
 mod tests {
     use crate::color::Color;
+    use crate::output_transform::{Encoding, ToneMapping};
     use pretty_assertions::assert_eq;
 
     use super::*;
@@ -145,6 +158,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn output_transform_is_applied_to_pixels() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(0.18, 0.18, 0.18));
+        canvas.write_pixel(1, 0, Color::new(8.0, 1.0, 0.0));
+        let mut ppm_buffer: Vec<u8> = vec![];
+
+        {
+            let output_transform = OutputTransform::default()
+                .with_tone_mapping(ToneMapping::Reinhard)
+                .with_encoding(Encoding::Srgb);
+            let mut ppm_writer =
+                PpmWriter::from_writer(&mut ppm_buffer).with_output_transform(output_transform);
+            ppm_writer.write_canvas(&canvas).unwrap();
+        }
+
+        assert_eq!(&["109 109 109 242 188 0"], &str_lines(&ppm_buffer)[3..4])
+    }
+
     #[test]
     fn ppm_files_are_terminated_by_newline() {
         let canvas = Canvas::new(5, 3);
@@ -0,0 +1,353 @@
+use std::ops;
+
+use crate::float_eq::FloatEq;
+use crate::matrix::Matrix;
+use crate::vector::Vector;
+
+/// Rotation around an arbitrary axis, as a unit quaternion `w + xi + yj + zk`.
+///
+/// Unlike successive rotations around the x, y and z axes, quaternions can
+/// be interpolated smoothly (see `slerp`), without gimbal lock.
+#[derive(Copy, Clone, Debug)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// No rotation
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Rotation by `radians` around `axis` (which doesn't need to be normalized),
+    /// counterclockwise when looking from the tip of the axis towards the origin
+    pub fn from_axis_angle(axis: Vector, radians: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (radians / 2.0).sin_cos();
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    /// Rotation turning an object looking towards -z (like the camera) to look
+    /// towards `forward`, with its y axis turned as close as possible to `up`.
+    /// Neither vector needs to be normalized, but they must not be parallel.
+    pub fn look_rotation(forward: Vector, up: Vector) -> Self {
+        let forward = forward.normalize();
+        let left = forward.cross(up).normalize();
+        let true_up = left.cross(forward);
+
+        let columns = [left, true_up, -forward];
+        let mut rotation = Matrix::identity();
+        for (column, axis) in columns.into_iter().enumerate() {
+            rotation[(0, column)] = axis.x;
+            rotation[(1, column)] = axis.y;
+            rotation[(2, column)] = axis.z;
+        }
+        Self::from_matrix(rotation)
+    }
+
+    /// Rotation of a transformation matrix, which must be a rotation without
+    /// scale or shear (see `Matrix::decompose` to remove them)
+    pub fn from_matrix(matrix: Matrix<4, 4>) -> Self {
+        let m = |row, column| matrix[(row, column)];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+
+        // divide by the largest component, for numerical stability
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                s / 4.0,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+            Self::new(
+                (m(2, 1) - m(1, 2)) / s,
+                s / 4.0,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) > m(2, 2) {
+            let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+            Self::new(
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                s / 4.0,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+            Self::new(
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        quaternion.normalize()
+    }
+
+    /// Rotation matrix of the quaternion
+    pub fn to_matrix(self) -> Matrix<4, 4> {
+        let Self { w, x, y, z } = self.normalize();
+        Matrix::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        self * (1.0 / self.magnitude())
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Opposite rotation, for unit quaternions
+    pub fn conjugate(self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Spherical linear interpolation, rotating at constant speed along the
+    /// shortest arc from `self` (`t` = 0) to `other` (`t` = 1)
+    pub fn slerp(self, other: Self, t: f64) -> Self {
+        let (from, mut to) = (self.normalize(), other.normalize());
+        // q and -q are the same rotation, but only one of them is on the shortest arc
+        let mut cos_angle = from.dot(to);
+        if cos_angle < 0.0 {
+            to = to * -1.0;
+            cos_angle = -cos_angle;
+        }
+
+        if cos_angle > 0.9995 {
+            // nearly identical rotations: linear interpolation avoids dividing by ~0
+            return (from * (1.0 - t) + to * t).normalize();
+        }
+        let angle = cos_angle.acos();
+        let sin_angle = angle.sin();
+        from * (((1.0 - t) * angle).sin() / sin_angle) + to * ((t * angle).sin() / sin_angle)
+    }
+}
+
+/// Compares components, so `q` and `-q` are different even though they give
+/// the same rotation
+impl PartialEq for Quaternion {
+    fn eq(&self, other: &Self) -> bool {
+        self.w.float_eq(other.w)
+            && self.x.float_eq(other.x)
+            && self.y.float_eq(other.y)
+            && self.z.float_eq(other.z)
+    }
+}
+
+impl ops::Add for Quaternion {
+    type Output = Quaternion;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self::new(
+            self.w + other.w,
+            self.x + other.x,
+            self.y + other.y,
+            self.z + other.z,
+        )
+    }
+}
+
+impl ops::Mul<f64> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, factor: f64) -> Self::Output {
+        Self::new(
+            self.w * factor,
+            self.x * factor,
+            self.y * factor,
+            self.z * factor,
+        )
+    }
+}
+
+/// Composition of rotations: `a * b` rotates by `b`, then by `a`
+impl ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, other: Self) -> Self::Output {
+        Self::new(
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        )
+    }
+}
+
+/// Rotate a vector
+impl ops::Mul<Vector> for Quaternion {
+    type Output = Vector;
+
+    fn mul(self, vector: Vector) -> Self::Output {
+        let axis = Vector::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+}
+
+impl From<Quaternion> for Matrix<4, 4> {
+    fn from(quaternion: Quaternion) -> Self {
+        quaternion.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::matrix::transformations;
+    use crate::point::Point;
+    use crate::view_transform::view_transform;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test]
+    fn rotations_around_principal_axes() {
+        let rotation = |x, y, z, radians| {
+            Quaternion::from_axis_angle(Vector::new(x, y, z), radians).to_matrix()
+        };
+
+        assert_eq!(
+            transformations::rotation_x(0.8),
+            rotation(1.0, 0.0, 0.0, 0.8)
+        );
+        assert_eq!(
+            transformations::rotation_y(-1.3),
+            rotation(0.0, 2.0, 0.0, -1.3)
+        );
+        assert_eq!(
+            transformations::rotation_z(2.9),
+            rotation(0.0, 0.0, 1.0, 2.9)
+        );
+    }
+
+    #[test]
+    fn rotation_around_arbitrary_axis() {
+        // a third of a turn around the diagonal cycles the axes
+        let rotation = Quaternion::from_axis_angle(Vector::new(1.0, 1.0, 1.0), 2.0 * PI / 3.0);
+
+        assert_eq!(
+            Vector::new(0.0, 1.0, 0.0),
+            rotation * Vector::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Point::new(0.0, 0.0, 1.0),
+            rotation.to_matrix() * Point::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test_case(Quaternion::from_axis_angle(Vector::new(1.0, 2.0, 3.0), 0.4) ; "small angle")]
+    #[test_case(Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), PI) ; "half turn around y")]
+    #[test_case(Quaternion::from_axis_angle(Vector::new(-1.0, 0.2, 0.0), 3.0) ; "large angle around x")]
+    #[test_case(Quaternion::from_axis_angle(Vector::new(0.1, 0.0, -1.0), 2.5) ; "large angle around z")]
+    fn matrix_conversion_round_trips(quaternion: Quaternion) {
+        let converted = Quaternion::from_matrix(quaternion.to_matrix());
+
+        // either representation of the same rotation
+        assert!(
+            converted == quaternion || converted == quaternion * -1.0,
+            "{converted:?}"
+        );
+    }
+
+    #[test]
+    fn composing_rotations() {
+        let quarter_x = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), PI / 2.0);
+        let quarter_y = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), PI / 2.0);
+
+        assert_eq!(
+            transformations::rotation_x(PI / 2.0) >> transformations::rotation_y(PI / 2.0),
+            (quarter_y * quarter_x).to_matrix()
+        );
+        assert_eq!(Quaternion::identity(), quarter_x * quarter_x.conjugate());
+    }
+
+    #[test]
+    fn slerp_rotates_at_constant_speed() {
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), PI / 2.0);
+
+        assert_eq!(start, start.slerp(end, 0.0));
+        assert_eq!(end, start.slerp(end, 1.0));
+        assert_eq!(
+            Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), PI / 6.0),
+            start.slerp(end, 1.0 / 3.0)
+        );
+    }
+
+    #[test]
+    fn slerp_takes_shortest_arc() {
+        let start = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), -0.2);
+        // same rotation as 1.5 - 2π
+        let end = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), 1.5) * -1.0;
+
+        let halfway = start.slerp(end, 0.5);
+
+        assert_eq!(transformations::rotation_y(0.65), halfway.to_matrix());
+    }
+
+    #[test]
+    fn look_rotation_turns_camera_direction_to_forward() {
+        let forward = Vector::new(1.0, -2.0, 0.5);
+        let up = Vector::new(0.0, 1.0, 0.0);
+
+        let rotation = Quaternion::look_rotation(forward, up);
+
+        assert_eq!(forward.normalize(), rotation * Vector::new(0.0, 0.0, -1.0));
+        let rotated_up = rotation * Vector::new(0.0, 1.0, 0.0);
+        assert!(rotated_up.dot(forward).abs() < 1e-9);
+        assert!(rotated_up.dot(up) > 0.0);
+    }
+
+    #[test]
+    fn look_rotation_is_inverse_of_view_orientation() {
+        let forward = Vector::new(2.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+
+        let rotation = Quaternion::look_rotation(forward, up);
+
+        assert_eq!(
+            view_transform(Point::origin(), Point::origin() + forward, up),
+            rotation.to_matrix().inverse()
+        );
+    }
+}
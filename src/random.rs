@@ -0,0 +1,76 @@
+/// Small, seedable pseudo-random number generator.
+///
+/// Based on the SplitMix64 generator, which is fast, has a 64 bit state and
+/// produces well distributed values even for consecutive seeds.
+/// Being deterministic, renders relying on it are reproducible.
+/// See also https://prng.di.unimi.it/splitmix64.c
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generator for a single pixel of a render.
+    /// Every pixel gets an independent sequence, so that the result does not
+    /// depend on the order (or the thread) in which pixels are rendered.
+    pub fn for_pixel(seed: u64, x: usize, y: usize) -> Self {
+        let mut rng = Self::seeded(seed ^ ((x as u64) << 32) ^ (y as u64));
+        // mix the state, so that neighbouring pixels don't start from similar states
+        let state = rng.next_u64();
+        Self::seeded(state)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed value in the range [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // keep the 53 most significant bits, ie. the precision of a f64 mantissa
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn same_seed_generates_same_sequence() {
+        let mut rng_1 = Rng::seeded(42);
+        let mut rng_2 = Rng::seeded(42);
+
+        for _ in 0..100 {
+            assert_eq!(rng_1.next_u64(), rng_2.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_pixels_generate_different_sequences() {
+        let mut rng_1 = Rng::for_pixel(0, 1, 0);
+        let mut rng_2 = Rng::for_pixel(0, 0, 1);
+
+        assert_ne!(rng_1.next_u64(), rng_2.next_u64());
+    }
+
+    #[test]
+    fn floats_are_between_0_and_1() {
+        let mut rng = Rng::seeded(7);
+
+        for _ in 0..1000 {
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
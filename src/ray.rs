@@ -1,3 +1,5 @@
+use crate::float::Float;
+use crate::intersection::Intersection;
 use crate::intersections::Intersections;
 use crate::matrix::Matrix;
 use crate::object::Object;
@@ -5,51 +7,138 @@ use crate::point::Point;
 use crate::vector::Vector;
 use crate::world::World;
 
+/// Start of the interval of rays cast from a surface, relative to the
+/// magnitude of the coordinates of their origin (see `epsilon_at`).
+/// Rays in single precision start further (see `Float::RAY_EPSILON`).
+pub const EPSILON: f64 = 0.000000001;
+
+/// Start of the interval of rays in precision `T` cast from a surface at the
+/// given point: closer intersections are the surface itself, found again
+/// because of rounding errors, which grow with the coordinates of the point
+pub fn epsilon_at<T: Float>(point: Point) -> T {
+    let magnitude = point.x.abs().max(point.y.abs()).max(point.z.abs()).max(1.0);
+    T::from_f64(T::RAY_EPSILON.to_f64() * magnitude)
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Ray {
-    pub origin: Point,
-    pub direction: Vector,
+pub struct Ray<T: Float = f64> {
+    pub origin: Point<T>,
+    pub direction: Vector<T>,
+    /// Instant at which the ray is cast, between 0 (shutter open) and 1 (shutter close).
+    /// Moving objects are intersected in the position they have at this time.
+    pub time: f64,
+    /// Intersections are only considered with `t_min <= t < t_max`,
+    /// by default all those ahead of the ray
+    pub t_min: T,
+    pub t_max: T,
 }
 
-impl Ray {
-    pub fn new(origin: Point, direction: Vector) -> Self {
-        Self { origin, direction }
+impl<T: Float> Ray<T> {
+    pub fn new(origin: Point<T>, direction: Vector<T>) -> Self {
+        Self {
+            origin,
+            direction,
+            time: 0.0,
+            t_min: T::ZERO,
+            t_max: T::INFINITY,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
+    pub fn with_interval(self, t_min: T, t_max: T) -> Self {
+        Self {
+            t_min,
+            t_max,
+            ..self
+        }
+    }
+
+    /// Check if t is in the interval of the ray (see `with_interval`)
+    pub fn in_interval(self, t: T) -> bool {
+        (self.t_min..self.t_max).contains(&t)
     }
 
-    pub fn position(self, t: f64) -> Point {
+    pub fn position(self, t: T) -> Point<T> {
         self.origin + self.direction * t
     }
 
-    pub fn intersect(self, object: Object) -> Intersections {
-        let transformed_ray = self.transform(object.transformation.inverse());
+    /// Same ray, with its coordinates and interval in precision `U`
+    pub fn cast<U: Float>(self) -> Ray<U> {
+        Ray {
+            origin: self.origin.cast(),
+            direction: self.direction.cast(),
+            time: self.time,
+            t_min: U::from_f64(self.t_min.to_f64()),
+            t_max: U::from_f64(self.t_max.to_f64()),
+        }
+    }
+
+    /// Intersections with the object in the interval of the ray, in the position
+    /// the object has at the time of the ray
+    /// Objects whose transformation can't be inverted are missed.
+    pub fn intersect(self, object: &Object<T>) -> Intersections<'_, T> {
+        let Some(inverse_transformation) = object.inverse_transformation_at(self.time) else {
+            return Intersections::empty();
+        };
+        let transformed_ray = self.transform(inverse_transformation);
 
         object.shape.object_intersect_at(object, transformed_ray)
     }
 
-    pub fn intersect_world(&self, world: &World) -> Intersections {
-        let all_intersections = world
+    /// Closest intersections in the interval of the ray with the objects of the
+    /// world, sorted by t.
+    /// Only the `Intersections::CAPACITY` closest ones are kept, which is enough
+    /// to find the hit without allocating: farther intersections are silently
+    /// dropped (see `intersect_world_all`).
+    pub fn intersect_world<'a>(&self, world: &'a World<T>) -> Intersections<'a, T> {
+        let mut intersections = Intersections::empty();
+        for object in &world.objects {
+            for intersection in self.intersect(object).iter() {
+                intersections.push(intersection);
+            }
+        }
+        intersections
+    }
+
+    /// All the intersections in the interval of the ray with the objects of the
+    /// world, sorted by t (see `intersect_world`)
+    pub fn intersect_world_all<'a>(&self, world: &'a World<T>) -> Vec<Intersection<'a, T>> {
+        let mut intersections = vec![];
+        for object in &world.objects {
+            intersections.extend(self.intersect(object).iter());
+        }
+        // the sort is stable: on ties, the first object comes first as with `intersect_world`
+        intersections.sort_by(|first, second| first.t.total_cmp(&second.t));
+        intersections
+    }
+
+    /// Check if the ray intersects any object of the world in its interval,
+    /// stopping at the first one found
+    pub fn intersects_any(&self, world: &World<T>) -> bool {
+        world
             .objects
             .iter()
-            .map(|object| self.intersect(*object))
-            .collect();
-
-        Intersections::merge(all_intersections)
+            .any(|object| self.intersect(object).count() > 0)
     }
 
-    fn transform(self, transformation_matrix: Matrix<4, 4>) -> Self {
+    fn transform(self, transformation_matrix: Matrix<4, 4, T>) -> Self {
         Self {
             origin: transformation_matrix * self.origin,
             direction: transformation_matrix * self.direction,
+            ..self
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::intersection_state::IntersectionState;
     use crate::material::Material;
     use crate::matrix::transformations;
     use crate::object::Object;
-    use crate::world::World;
 
     use super::*;
 
@@ -84,7 +173,7 @@ mod tests {
             Material::default(),
         );
 
-        let intersections = ray.intersect(sphere);
+        let intersections = ray.intersect(&sphere);
 
         assert_eq!(2, intersections.count());
         assert_eq!(9.0, intersections[0].t);
@@ -116,10 +205,9 @@ mod tests {
     #[test]
     fn intersect_can_scale_ray_before_calculation() {
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let mut sphere = Object::default();
-        sphere.transformation = transformations::scaling(2.0, 2.0, 2.0);
+        let sphere = Object::sphere(transformations::scaling(2.0, 2.0, 2.0), Material::default());
 
-        let intersections = ray.intersect(sphere);
+        let intersections = ray.intersect(&sphere);
 
         assert_eq!(2, intersections.count());
         assert_eq!(3.0, intersections[0].t);
@@ -129,14 +217,66 @@ mod tests {
     #[test]
     fn intersect_can_translate_ray_before_calculation() {
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let mut sphere = Object::default();
-        sphere.transformation = transformations::translation(5.0, 0.0, 0.0);
+        let sphere = Object::sphere(
+            transformations::translation(5.0, 0.0, 0.0),
+            Material::default(),
+        );
 
-        let intersections = ray.intersect(sphere);
+        let intersections = ray.intersect(&sphere);
 
         assert_eq!(0, intersections.count());
     }
 
+    #[test]
+    fn moving_object_is_intersected_in_its_position_at_the_time_of_the_ray() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let sphere = Object::default().with_motion(transformations::translation(0.0, 0.0, 4.0));
+
+        let intersections_at_start = ray.intersect(&sphere);
+        let intersections_at_half = ray.with_time(0.5).intersect(&sphere);
+
+        assert_eq!(4.0, intersections_at_start[0].t);
+        assert_eq!(6.0, intersections_at_half[0].t);
+        // shaded from outside the sphere, which is now centered on z = 2
+        let state = IntersectionState::prepare(intersections_at_half[0], ray.with_time(0.5));
+        assert!(!state.inside);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), state.normal_v);
+    }
+
+    #[test]
+    fn moving_object_can_be_missed_depending_on_time() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let sphere = Object::default().with_motion(transformations::translation(4.0, 0.0, 0.0));
+
+        assert_eq!(2, ray.with_time(0.0).intersect(&sphere).count());
+        assert_eq!(0, ray.with_time(1.0).intersect(&sphere).count());
+    }
+
+    #[test]
+    fn intersect_world_leaves_out_intersections_behind_the_ray() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, 1.0));
+
+        let intersections = ray.intersect_world(&world);
+
+        assert_eq!(1, intersections.count());
+        assert_eq!(0.25, intersections[0].t);
+    }
+
+    #[test]
+    fn intersect_world_only_returns_intersections_in_the_interval_of_the_ray() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0))
+            .with_interval(4.25, 6.0);
+
+        let intersections = ray.intersect_world(&world);
+
+        assert_eq!(
+            vec![4.5, 5.5],
+            intersections.iter().map(|i| i.t).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn intersect_world_returns_all_intersections_with_objects_in_the_world() {
         let world = World::default();
@@ -150,4 +290,43 @@ mod tests {
         assert_eq!(5.5, intersections[2].t);
         assert_eq!(6.0, intersections[3].t);
     }
+
+    #[test]
+    fn intersect_world_keeps_the_closest_intersections_up_to_its_capacity() {
+        let mut world = World::default();
+        world.objects.push(Object::sphere(
+            transformations::translation(0.0, 0.0, 3.0),
+            Material::default(),
+        ));
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let closest = ray.intersect_world(&world);
+        let all = ray.intersect_world_all(&world);
+
+        assert_eq!(
+            vec![4.0, 4.5, 5.5, 6.0],
+            closest.iter().map(|i| i.t).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![4.0, 4.5, 5.5, 6.0, 7.0, 9.0],
+            all.iter().map(|i| i.t).collect::<Vec<_>>()
+        );
+        assert!(closest
+            .iter()
+            .eq(all[..Intersections::CAPACITY].iter().copied()));
+    }
+
+    #[test]
+    fn intersects_any_only_counts_intersections_in_the_interval() {
+        // the default world has intersections at t = 4, 4.5, 5.5 and 6
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(ray.intersects_any(&world));
+        assert!(ray.with_interval(0.0, 10.0).intersects_any(&world));
+        assert!(ray.with_interval(5.0, 5.6).intersects_any(&world));
+        assert!(!ray.with_interval(0.0, 4.0).intersects_any(&world));
+        assert!(!ray.with_interval(4.6, 5.5).intersects_any(&world));
+        assert!(!ray.with_interval(6.5, f64::INFINITY).intersects_any(&world));
+    }
 }
@@ -0,0 +1,356 @@
+//! Rays traced together with SIMD instructions.
+//!
+//! Coherent rays, eg. through neighbouring pixels, mostly hit the same objects:
+//! a packet intersects each object with all of its rays at once, the coordinates
+//! of the rays being stored one component per SIMD vector (structure of arrays).
+
+use std::fmt::Debug;
+use std::ops::{Add, BitAnd, Div, Mul, Neg, Not, Sub};
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::num::SimdFloat;
+use std::simd::{Mask, Select, Simd, StdFloat};
+
+use crate::float::Float;
+use crate::intersection::Intersection;
+use crate::matrix::Matrix;
+use crate::object::Object;
+use crate::ray::Ray;
+use crate::world::World;
+
+/// Number of rays in a packet
+pub const PACKET_SIZE: usize = 4;
+
+/// One value for each ray of a packet
+pub type Lanes<T = f64> = <T as Float>::Lanes;
+
+/// Index of the object hit by each ray of a packet
+type Indices = Simd<u64, PACKET_SIZE>;
+
+/// SIMD vector of one value of precision `T` for each ray of a packet
+pub trait SimdLanes<T>:
+    Copy
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Result of comparisons, one boolean for each lane
+    type Mask: Copy + BitAnd<Output = Self::Mask> + Not<Output = Self::Mask>;
+
+    fn splat(value: T) -> Self;
+    fn from_array(values: [T; PACKET_SIZE]) -> Self;
+    fn to_array(self) -> [T; PACKET_SIZE];
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn simd_lt(self, other: Self) -> Self::Mask;
+    fn simd_ge(self, other: Self) -> Self::Mask;
+    /// Values of `true_values` in the lanes of the mask which are set, and of
+    /// `false_values` in the others
+    fn select(mask: Self::Mask, true_values: Self, false_values: Self) -> Self;
+    /// Same as `select`, for indices
+    fn select_indices(mask: Self::Mask, true_values: Indices, false_values: Indices) -> Indices;
+}
+
+macro_rules! impl_simd_lanes {
+    ($type:ty, $mask_element:ty) => {
+        impl SimdLanes<$type> for Simd<$type, PACKET_SIZE> {
+            type Mask = Mask<$mask_element, PACKET_SIZE>;
+
+            fn splat(value: $type) -> Self {
+                Simd::splat(value)
+            }
+
+            fn from_array(values: [$type; PACKET_SIZE]) -> Self {
+                Simd::from_array(values)
+            }
+
+            fn to_array(self) -> [$type; PACKET_SIZE] {
+                Simd::to_array(self)
+            }
+
+            fn sqrt(self) -> Self {
+                StdFloat::sqrt(self)
+            }
+
+            fn abs(self) -> Self {
+                SimdFloat::abs(self)
+            }
+
+            fn simd_lt(self, other: Self) -> Self::Mask {
+                SimdPartialOrd::simd_lt(self, other)
+            }
+
+            fn simd_ge(self, other: Self) -> Self::Mask {
+                SimdPartialOrd::simd_ge(self, other)
+            }
+
+            fn select(mask: Self::Mask, true_values: Self, false_values: Self) -> Self {
+                mask.select(true_values, false_values)
+            }
+
+            fn select_indices(
+                mask: Self::Mask,
+                true_values: Indices,
+                false_values: Indices,
+            ) -> Indices {
+                mask.cast::<i64>().select(true_values, false_values)
+            }
+        }
+    };
+}
+
+impl_simd_lanes!(f32, i32);
+impl_simd_lanes!(f64, i64);
+
+/// Points or vectors, one for each ray of a packet
+#[derive(Copy, Clone, Debug)]
+pub struct Coordinates<T: Float = f64> {
+    pub x: Lanes<T>,
+    pub y: Lanes<T>,
+    pub z: Lanes<T>,
+}
+
+impl<T: Float> Coordinates<T> {
+    pub fn dot(self, other: Self) -> Lanes<T> {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Transformed points (if `w` is 1) or vectors (if `w` is 0),
+    /// computed in the same order as `Matrix * Point` and `Matrix * Vector`
+    fn transform(self, matrix: Matrix<4, 4, T>, w: T) -> Self {
+        let row = |r: usize| {
+            Lanes::<T>::splat(T::ZERO)
+                + Lanes::<T>::splat(matrix[(r, 0)]) * self.x
+                + Lanes::<T>::splat(matrix[(r, 1)]) * self.y
+                + Lanes::<T>::splat(matrix[(r, 2)]) * self.z
+                + Lanes::<T>::splat(matrix[(r, 3)] * w)
+        };
+        Self {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RayPacket<T: Float = f64> {
+    rays: [Ray<T>; PACKET_SIZE],
+    pub origin: Coordinates<T>,
+    pub direction: Coordinates<T>,
+    pub t_min: Lanes<T>,
+    pub t_max: Lanes<T>,
+}
+
+impl<T: Float> RayPacket<T> {
+    pub fn new(rays: [Ray<T>; PACKET_SIZE]) -> Self {
+        Self {
+            rays,
+            origin: Coordinates {
+                x: Lanes::<T>::from_array(rays.map(|ray| ray.origin.x)),
+                y: Lanes::<T>::from_array(rays.map(|ray| ray.origin.y)),
+                z: Lanes::<T>::from_array(rays.map(|ray| ray.origin.z)),
+            },
+            direction: Coordinates {
+                x: Lanes::<T>::from_array(rays.map(|ray| ray.direction.x)),
+                y: Lanes::<T>::from_array(rays.map(|ray| ray.direction.y)),
+                z: Lanes::<T>::from_array(rays.map(|ray| ray.direction.z)),
+            },
+            t_min: Lanes::<T>::from_array(rays.map(|ray| ray.t_min)),
+            t_max: Lanes::<T>::from_array(rays.map(|ray| ray.t_max)),
+        }
+    }
+
+    /// Packet of the given rays, repeating the last one to fill the packet
+    ///
+    /// # Panics
+    ///
+    /// If there are no rays, or more than `PACKET_SIZE`.
+    pub fn padded(rays: &[Ray<T>]) -> Self {
+        assert!(
+            (1..=PACKET_SIZE).contains(&rays.len()),
+            "Expected between 1 and {PACKET_SIZE} rays, found {}",
+            rays.len()
+        );
+        Self::new(std::array::from_fn(|lane| rays[lane.min(rays.len() - 1)]))
+    }
+
+    pub fn rays(&self) -> [Ray<T>; PACKET_SIZE] {
+        self.rays
+    }
+
+    /// Closest hit of each ray, the same as `ray.intersect_world(world).hit()`
+    pub fn hits<'a>(&self, world: &'a World<T>) -> [Option<Intersection<'a, T>>; PACKET_SIZE] {
+        let mut closest_t = Lanes::<T>::splat(T::INFINITY);
+        let mut closest_index = Indices::splat(u64::MAX);
+        for (index, object) in world.objects.iter().enumerate() {
+            let t = self.intersect(object);
+            // on ties, the first object wins as with `Intersections::hit`
+            let closer = t.simd_lt(closest_t);
+            closest_t = Lanes::<T>::select(closer, t, closest_t);
+            closest_index =
+                Lanes::<T>::select_indices(closer, Indices::splat(index as u64), closest_index);
+        }
+
+        let closest_t = closest_t.to_array();
+        let closest_index = closest_index.to_array();
+        std::array::from_fn(|lane| {
+            world
+                .objects
+                .get(closest_index[lane] as usize)
+                .map(|object| Intersection::new(closest_t[lane], object))
+        })
+    }
+
+    /// Smallest t in the interval of each ray at which it intersects the object,
+    /// or infinity if it misses it
+    fn intersect(&self, object: &Object<T>) -> Lanes<T> {
+        if object.end_transformation().is_some() {
+            // each ray sees moving objects in a different position
+            return Lanes::<T>::from_array(self.rays.map(|ray| {
+                ray.intersect(object)
+                    .iter()
+                    .next()
+                    .map_or(T::INFINITY, |hit| hit.t)
+            }));
+        }
+
+        let inverse_transformation = object.inverse_transformation();
+        let object_origin = self.origin.transform(inverse_transformation, T::ONE);
+        let object_direction = self.direction.transform(inverse_transformation, T::ZERO);
+        object
+            .shape
+            .packet_intersect_at(object_origin, object_direction, self.t_min, self.t_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Material;
+    use crate::matrix::transformations;
+    use crate::point::Point;
+    use crate::vector::Vector;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn scalar_hits<T: Float>(
+        rays: [Ray<T>; PACKET_SIZE],
+        world: &World<T>,
+    ) -> [Option<Intersection<'_, T>>; PACKET_SIZE] {
+        rays.map(|ray| ray.intersect_world(world).hit())
+    }
+
+    #[test]
+    fn hits_are_the_same_as_for_single_rays() {
+        let mut world = World::default();
+        world.objects.push(Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material::default(),
+        ));
+        world.objects.push(
+            Object::sphere(
+                transformations::translation(3.0, 0.0, 0.0),
+                Material::default(),
+            )
+            .with_motion(transformations::translation(3.0, 2.0, 0.0)),
+        );
+        let origin = Point::new(0.0, 0.0, -5.0);
+        let rays = [
+            // through both default spheres
+            Ray::new(origin, Vector::new(0.0, 0.0, 1.0)),
+            // towards the floor
+            Ray::new(origin, Vector::new(0.0, -0.5, 1.0).normalize()),
+            // missing everything
+            Ray::new(origin, Vector::new(0.0, 1.0, 0.0)),
+            // towards the moving sphere, at different times
+            Ray::new(Point::new(3.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0)).with_time(0.5),
+        ];
+
+        let hits = RayPacket::new(rays).hits(&world);
+
+        assert_eq!(scalar_hits(rays, &world), hits);
+        assert_eq!(4.0, hits[0].unwrap().t);
+        assert_eq!(None, hits[2]);
+        assert_eq!(&world.objects[3], hits[3].unwrap().object);
+
+        let rays = rays.map(|ray| ray.with_time(0.0));
+        assert_eq!(scalar_hits(rays, &world), RayPacket::new(rays).hits(&world));
+    }
+
+    #[test]
+    fn rays_starting_inside_objects_hit_them_ahead() {
+        let world = World::new(vec![], vec![Object::default()]);
+        let rays = [
+            Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+
+        let hits = RayPacket::new(rays).hits(&world);
+
+        assert_eq!(
+            [Some(1.0), None, Some(4.0), Some(5.0)],
+            hits.map(|hit| hit.map(|hit| hit.t))
+        );
+    }
+
+    #[test]
+    fn hits_are_limited_to_the_interval_of_each_ray() {
+        let world = World::new(vec![], vec![Object::default()]);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let rays = [
+            ray,
+            ray.with_interval(5.0, f64::INFINITY),
+            ray.with_interval(0.0, 4.0),
+            ray.with_interval(4.5, 5.5),
+        ];
+
+        let hits = RayPacket::new(rays).hits(&world);
+
+        assert_eq!(scalar_hits(rays, &world), hits);
+        assert_eq!(
+            [Some(4.0), Some(6.0), None, None],
+            hits.map(|hit| hit.map(|hit| hit.t))
+        );
+    }
+
+    #[test]
+    fn hits_are_the_same_as_for_single_rays_in_single_precision() {
+        let mut world = World::default();
+        world.objects.push(Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material::default(),
+        ));
+        let world = world.cast::<f32>();
+        let origin = Point::new(0.0, 0.0, -5.0);
+        let rays = [
+            Ray::new(origin, Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(origin, Vector::new(0.0, -0.5, 1.0).normalize()),
+            Ray::new(origin, Vector::new(0.0, 1.0, 0.0)),
+            Ray::new(origin, Vector::new(0.0, 0.0, 1.0)).with_interval(5.0, f32::INFINITY),
+        ];
+
+        let hits = RayPacket::new(rays).hits(&world);
+
+        assert_eq!(scalar_hits(rays, &world), hits);
+        assert_eq!(
+            [Some(4.0), Some(5.0_f32.sqrt()), None, Some(5.5)],
+            hits.map(|hit| hit.map(|hit| hit.t))
+        );
+    }
+
+    #[test]
+    fn padding_repeats_the_last_ray() {
+        let first = Ray::new(Point::origin(), Vector::new(1.0, 0.0, 0.0));
+        let last = Ray::new(Point::origin(), Vector::new(0.0, 1.0, 0.0));
+
+        let packet = RayPacket::padded(&[first, last]);
+
+        assert_eq!([first, last, last, last], packet.rays());
+    }
+}
@@ -0,0 +1,479 @@
+//! Regression testing of renders against stored reference images.
+//!
+//! A render is compared to its reference (an 8-bit PPM image) pixel by pixel,
+//! and with the PSNR and SSIM metrics. When they differ, a heat map of the
+//! differences is written next to the reference, together with the render.
+//!
+//! References are (re)generated instead of being compared to when the
+//! `UPDATE_REFERENCES` environment variable is set, eg. with
+//! `UPDATE_REFERENCES=1 cargo test`.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::ppm_reader::PpmReader;
+use crate::ppm_writer::PpmWriter;
+
+/// Environment variable requesting references to be written instead of checked
+pub const UPDATE_REFERENCES_VARIABLE: &str = "UPDATE_REFERENCES";
+
+/// Stored image that renders should match, within tolerances
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceImage {
+    path: PathBuf,
+    /// Maximum difference between the components of matching pixels
+    pixel_tolerance: f64,
+    /// Number of pixels allowed to differ by more than the tolerance
+    max_differing_pixels: usize,
+    min_psnr: f64,
+    min_ssim: f64,
+}
+
+/// Differences between a render and its reference. Colors are clamped
+/// between 0 and 1, as they are in the reference.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// Largest difference between components of the same pixel
+    pub max_difference: f64,
+    /// Pixels differing by more than the tolerance of the reference
+    pub differing_pixels: usize,
+    /// Peak signal-to-noise ratio, in decibels (infinite for identical images)
+    pub psnr: f64,
+    /// Structural similarity of the luminance, 1 for identical images
+    pub ssim: f64,
+}
+
+#[derive(Debug)]
+pub enum RegressionError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    SizeMismatch {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    /// The render differs from the reference, the differences being drawn in `heat_map`
+    Mismatch {
+        comparison: Comparison,
+        heat_map: PathBuf,
+    },
+}
+
+impl ReferenceImage {
+    /// Reference stored at `path`, to be matched within 1/255 for every
+    /// component (a difference of one in 8 bits)
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            pixel_tolerance: 1.0 / 255.0,
+            max_differing_pixels: 0,
+            min_psnr: 40.0,
+            min_ssim: 0.99,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn with_pixel_tolerance(mut self, pixel_tolerance: f64) -> Self {
+        self.pixel_tolerance = pixel_tolerance;
+        self
+    }
+
+    pub fn with_max_differing_pixels(mut self, max_differing_pixels: usize) -> Self {
+        self.max_differing_pixels = max_differing_pixels;
+        self
+    }
+
+    pub fn with_min_psnr(mut self, min_psnr: f64) -> Self {
+        self.min_psnr = min_psnr;
+        self
+    }
+
+    pub fn with_min_ssim(mut self, min_ssim: f64) -> Self {
+        self.min_ssim = min_ssim;
+        self
+    }
+
+    /// Compare the render to the reference, or replace the reference when
+    /// `UPDATE_REFERENCES` is set.
+    /// On mismatches, the heat map of the differences is written to
+    /// `<name>.diff.ppm` and the render to `<name>.actual.ppm`.
+    pub fn check(&self, canvas: &Canvas) -> Result<Comparison, RegressionError> {
+        if std::env::var_os(UPDATE_REFERENCES_VARIABLE).is_some() {
+            self.update(canvas)?;
+        }
+        self.verify(canvas)
+    }
+
+    /// Compare the render to the reference, whatever the environment
+    fn verify(&self, canvas: &Canvas) -> Result<Comparison, RegressionError> {
+        let reference = read_ppm(&self.path)?;
+        let comparison = self.compare(&reference, canvas)?;
+        if comparison.differing_pixels <= self.max_differing_pixels
+            && comparison.psnr >= self.min_psnr
+            && comparison.ssim >= self.min_ssim
+        {
+            return Ok(comparison);
+        }
+
+        let heat_map = self.sibling_path("diff");
+        write_ppm(&heat_map, &difference_heat_map(&reference, canvas))?;
+        write_ppm(&self.sibling_path("actual"), canvas)?;
+        Err(RegressionError::Mismatch {
+            comparison,
+            heat_map,
+        })
+    }
+
+    /// Store the render as the new reference
+    pub fn update(&self, canvas: &Canvas) -> Result<(), RegressionError> {
+        if let Some(directory) = self.path.parent() {
+            std::fs::create_dir_all(directory).map_err(|error| RegressionError::Io {
+                path: directory.to_path_buf(),
+                error,
+            })?;
+        }
+        write_ppm(&self.path, canvas)
+    }
+
+    pub fn compare(
+        &self,
+        reference: &Canvas,
+        canvas: &Canvas,
+    ) -> Result<Comparison, RegressionError> {
+        let expected = (reference.width(), reference.height());
+        let actual = (canvas.width(), canvas.height());
+        if expected != actual {
+            return Err(RegressionError::SizeMismatch { expected, actual });
+        }
+
+        let mut max_difference: f64 = 0.0;
+        let mut differing_pixels = 0;
+        let mut squared_error = 0.0;
+        for (reference_row, row) in reference.iter_rows().zip(canvas.iter_rows()) {
+            for (reference_pixel, pixel) in reference_row.iter().zip(row) {
+                let differences = component_differences(*reference_pixel, *pixel);
+                let pixel_difference = differences.into_iter().fold(0.0, f64::max);
+                max_difference = max_difference.max(pixel_difference);
+                if pixel_difference > self.pixel_tolerance {
+                    differing_pixels += 1;
+                }
+                squared_error += differences.iter().map(|d| d * d).sum::<f64>();
+            }
+        }
+        let mean_squared_error =
+            squared_error / (3 * reference.width() * reference.height()) as f64;
+
+        Ok(Comparison {
+            max_difference,
+            differing_pixels,
+            psnr: -10.0 * mean_squared_error.log10(),
+            ssim: ssim(reference, canvas),
+        })
+    }
+
+    /// `<name>.<suffix>.ppm`, next to the reference
+    fn sibling_path(&self, suffix: &str) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.path.with_file_name(format!("{stem}.{suffix}.ppm"))
+    }
+}
+
+impl fmt::Display for RegressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegressionError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            RegressionError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Expected a {}x{} image, got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            RegressionError::Mismatch {
+                comparison,
+                heat_map,
+            } => write!(
+                f,
+                "{} pixels differ (by up to {:.4}), PSNR {:.2} dB, SSIM {:.4}; see {}",
+                comparison.differing_pixels,
+                comparison.max_difference,
+                comparison.psnr,
+                comparison.ssim,
+                heat_map.display()
+            ),
+        }
+    }
+}
+
+impl Error for RegressionError {}
+
+/// Image of the differences between two canvases of the same size, from black
+/// (identical) through red and yellow to white (the largest difference)
+pub fn difference_heat_map(reference: &Canvas, canvas: &Canvas) -> Canvas {
+    let difference_at = |x, y| {
+        component_differences(reference.pixel_at(x, y), canvas.pixel_at(x, y))
+            .into_iter()
+            .fold(0.0, f64::max)
+    };
+    let mut max_difference: f64 = 0.0;
+    for y in 0..reference.height() {
+        for x in 0..reference.width() {
+            max_difference = max_difference.max(difference_at(x, y));
+        }
+    }
+
+    Canvas::from_fn(reference.width(), reference.height(), |x, y| {
+        let heat = if max_difference > 0.0 {
+            3.0 * difference_at(x, y) / max_difference
+        } else {
+            0.0
+        };
+        let color = Color::new(
+            heat.clamp(0.0, 1.0),
+            (heat - 1.0).clamp(0.0, 1.0),
+            (heat - 2.0).clamp(0.0, 1.0),
+        );
+        (color, 1.0)
+    })
+}
+
+/// Mean structural similarity of the luminance of two canvases of the same
+/// size, over windows of 8 by 8 pixels (or the whole image, if smaller)
+pub fn ssim(reference: &Canvas, canvas: &Canvas) -> f64 {
+    const WINDOW: usize = 8;
+    const STEP: usize = 4;
+    // stabilize the divisions for dark and flat windows
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+
+    let window_width = WINDOW.min(reference.width());
+    let window_height = WINDOW.min(reference.height());
+    let luminance = |canvas: &Canvas, x, y| clamped(canvas.pixel_at(x, y)).luminance();
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for top in (0..=reference.height() - window_height).step_by(STEP) {
+        for left in (0..=reference.width() - window_width).step_by(STEP) {
+            let pixels: Vec<(f64, f64)> = (top..top + window_height)
+                .flat_map(|y| (left..left + window_width).map(move |x| (x, y)))
+                .map(|(x, y)| (luminance(reference, x, y), luminance(canvas, x, y)))
+                .collect();
+            let count = pixels.len() as f64;
+            let mean_a = pixels.iter().map(|(a, _)| a).sum::<f64>() / count;
+            let mean_b = pixels.iter().map(|(_, b)| b).sum::<f64>() / count;
+            let (mut variance_a, mut variance_b, mut covariance) = (0.0, 0.0, 0.0);
+            for (a, b) in &pixels {
+                variance_a += (a - mean_a).powi(2) / count;
+                variance_b += (b - mean_b).powi(2) / count;
+                covariance += (a - mean_a) * (b - mean_b) / count;
+            }
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+    total / windows as f64
+}
+
+fn component_differences(reference: Color, color: Color) -> [f64; 3] {
+    let (reference, color) = (clamped(reference), clamped(color));
+    [
+        (reference.red - color.red).abs(),
+        (reference.green - color.green).abs(),
+        (reference.blue - color.blue).abs(),
+    ]
+}
+
+fn clamped(color: Color) -> Color {
+    Color::new(
+        color.red.clamp(0.0, 1.0),
+        color.green.clamp(0.0, 1.0),
+        color.blue.clamp(0.0, 1.0),
+    )
+}
+
+fn read_ppm(path: &Path) -> Result<Canvas, RegressionError> {
+    let io_error = |error| RegressionError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    PpmReader::from_reader(file).read_canvas().map_err(io_error)
+}
+
+fn write_ppm(path: &Path, canvas: &Canvas) -> Result<(), RegressionError> {
+    let io_error = |error| RegressionError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    let file = File::create(path).map_err(io_error)?;
+    PpmWriter::from_writer(file)
+        .write_canvas(canvas)
+        .map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::scene::Scene;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    /// Width of the renders of the example scenes, small enough for debug builds
+    const EXAMPLE_WIDTH: usize = 48;
+    /// Samples per pixel of path traced examples, enough to cover the integrator
+    const EXAMPLE_MAX_SAMPLES: usize = 2;
+
+    fn gradient(width: usize, height: usize) -> Canvas {
+        Canvas::from_fn(width, height, |x, y| {
+            let value = (x + y) as f64 / (width + height) as f64;
+            (Color::new(value, 1.0 - value, 0.5), 1.0)
+        })
+    }
+
+    fn temp_reference(name: &str) -> ReferenceImage {
+        let directory = std::env::temp_dir().join(format!("raytracer-regression-{name}"));
+        fs::create_dir_all(&directory).unwrap();
+        ReferenceImage::new(directory.join(format!("{name}.ppm")))
+    }
+
+    #[test]
+    fn identical_images_match_perfectly() {
+        let canvas = gradient(12, 10);
+        let reference = ReferenceImage::new("unused.ppm");
+
+        let comparison = reference.compare(&canvas, &canvas).unwrap();
+
+        assert_eq!(0.0, comparison.max_difference);
+        assert_eq!(0, comparison.differing_pixels);
+        assert_eq!(f64::INFINITY, comparison.psnr);
+        assert_eq!(1.0, comparison.ssim);
+    }
+
+    #[test]
+    fn differences_are_measured() {
+        let reference = gradient(12, 10);
+        let mut canvas = gradient(12, 10);
+        canvas.write_pixel(3, 4, Color::white());
+        canvas.write_pixel(5, 5, canvas.pixel_at(5, 5) + Color::new(0.002, 0.0, 0.0));
+
+        let comparison = ReferenceImage::new("unused.ppm")
+            .compare(&reference, &canvas)
+            .unwrap();
+
+        assert_eq!(1, comparison.differing_pixels);
+        assert!(comparison.max_difference > 0.5);
+        assert!(comparison.psnr > 20.0 && comparison.psnr < 40.0);
+        assert!(comparison.ssim > 0.5 && comparison.ssim < 0.99);
+    }
+
+    #[test]
+    fn images_of_different_sizes_do_not_match() {
+        let error = ReferenceImage::new("unused.ppm")
+            .compare(&gradient(4, 4), &gradient(4, 5))
+            .unwrap_err();
+
+        assert_eq!("Expected a 4x4 image, got 4x5", error.to_string());
+    }
+
+    #[test]
+    fn heat_map_shows_largest_difference_in_white() {
+        let reference = gradient(3, 1);
+        let mut canvas = gradient(3, 1);
+        canvas.write_pixel(1, 0, Color::black());
+
+        let heat_map = difference_heat_map(&reference, &canvas);
+
+        assert_eq!(Color::black(), heat_map.pixel_at(0, 0));
+        assert_eq!(Color::white(), heat_map.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn mismatches_write_heat_map_and_render() {
+        let reference = temp_reference("mismatch");
+        reference.update(&gradient(6, 4)).unwrap();
+        let canvas = Canvas::new(6, 4);
+
+        let error = reference.verify(&canvas).unwrap_err();
+
+        let RegressionError::Mismatch {
+            comparison,
+            heat_map,
+        } = error
+        else {
+            panic!("Expected a mismatch, got {error:?}");
+        };
+        assert_eq!(24, comparison.differing_pixels);
+        assert_eq!(
+            reference.path().with_file_name("mismatch.diff.ppm"),
+            heat_map
+        );
+        assert!(heat_map.exists());
+        assert!(reference
+            .path()
+            .with_file_name("mismatch.actual.ppm")
+            .exists());
+    }
+
+    #[test]
+    fn renders_within_tolerance_match_references() {
+        let reference = temp_reference("within_tolerance")
+            .with_max_differing_pixels(1)
+            .with_min_psnr(25.0)
+            .with_min_ssim(0.9);
+        let canvas = gradient(16, 16);
+        reference.update(&canvas).unwrap();
+
+        // 8-bit quantization of the reference is within the tolerance
+        let mut changed = canvas.map(|color| color * 1.001);
+        changed.write_pixel(0, 0, Color::white());
+
+        assert!(reference.verify(&canvas).is_ok());
+        assert!(reference.verify(&changed).is_ok());
+        assert!(reference
+            .with_max_differing_pixels(0)
+            .verify(&changed)
+            .is_err());
+    }
+
+    #[test_case("scene")]
+    #[test_case("patterns")]
+    #[test_case("flat_sphere")]
+    #[test_case("glow")]
+    #[test_case("brdfs")]
+    #[test_case("outdoors")]
+    fn example_scenes_match_references(name: &str) {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let source = fs::read_to_string(root.join(format!("examples/scenes/{name}.json"))).unwrap();
+        let Scene { world, camera } = Scene::parse(&source).unwrap();
+
+        let height = (EXAMPLE_WIDTH * camera.vsize() / camera.hsize()).max(1);
+        let samples = camera.samples_per_pixel().min(EXAMPLE_MAX_SAMPLES);
+        let camera = camera
+            .with_size(EXAMPLE_WIDTH, height)
+            .with_samples_per_pixel(samples)
+            .with_progress(false);
+        let canvas = camera.render_parallel(world, None);
+
+        let reference = ReferenceImage::new(root.join(format!("tests/references/{name}.ppm")));
+        if let Err(error) = reference.check(&canvas) {
+            panic!("{name} differs from its reference: {error}");
+        }
+    }
+}
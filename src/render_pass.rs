@@ -0,0 +1,190 @@
+//! Arbitrary output variables: images of properties of the visible surfaces,
+//! rendered alongside the color for compositing and debugging.
+
+use crate::color::Color;
+use crate::float::Float;
+use crate::intersection::Intersection;
+use crate::intersection_state::IntersectionState;
+use crate::ray::Ray;
+use crate::world::World;
+
+/// Property of the surface seen through each pixel, stored in the three
+/// components of a color.
+///
+/// Passes are computed from a single ray through the center of the pixel,
+/// at the middle of the shutter interval, so that values are never blended
+/// across the edges of objects.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RenderPass {
+    /// Distance from the camera to the surface (`t` of the hit), infinite
+    /// where the ray misses everything
+    Depth,
+    /// World space normal of the surface, facing the camera (zero on misses)
+    Normal,
+    /// Color of the surface, before lighting (black on misses)
+    Albedo,
+    /// Index of the object in `World::objects`, -1 on misses
+    ObjectId,
+    /// Fraction of the lights which are hidden from the surface (0 on misses)
+    Shadowed,
+    /// Fraction of the lights which reach the surface (0 on misses)
+    Unshadowed,
+}
+
+impl RenderPass {
+    pub const ALL: [RenderPass; 6] = [
+        RenderPass::Depth,
+        RenderPass::Normal,
+        RenderPass::Albedo,
+        RenderPass::ObjectId,
+        RenderPass::Shadowed,
+        RenderPass::Unshadowed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderPass::Depth => "depth",
+            RenderPass::Normal => "normal",
+            RenderPass::Albedo => "albedo",
+            RenderPass::ObjectId => "object_id",
+            RenderPass::Shadowed => "shadowed",
+            RenderPass::Unshadowed => "unshadowed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pass| pass.name() == name)
+    }
+
+    /// Value of the pass for the surface seen along the ray
+    pub fn value<T: Float>(self, world: &World<T>, ray: Ray<T>) -> Color {
+        let Some((index, hit)) = first_hit(world, ray) else {
+            return match self {
+                RenderPass::Depth => gray(f64::INFINITY),
+                RenderPass::ObjectId => gray(-1.0),
+                _ => Color::black(),
+            };
+        };
+        let state = IntersectionState::prepare(hit, ray);
+
+        match self {
+            RenderPass::Depth => gray(state.t),
+            RenderPass::Normal => {
+                let normal = state.normal_v;
+                Color::new(normal.x, normal.y, normal.z)
+            }
+            RenderPass::Albedo => state.surface_color,
+            RenderPass::ObjectId => gray(index as f64),
+            RenderPass::Shadowed | RenderPass::Unshadowed => {
+                if world.lights.is_empty() {
+                    return Color::black();
+                }
+                let shadowed = world
+                    .lights
+                    .iter()
+                    .filter(|light| world.is_shadowed(**light, state.point, state.time))
+                    .count() as f64
+                    / world.lights.len() as f64;
+                if self == RenderPass::Shadowed {
+                    gray(shadowed)
+                } else {
+                    gray(1.0 - shadowed)
+                }
+            }
+        }
+    }
+}
+
+/// Closest hit along the ray, with the index of the object hit
+fn first_hit<T: Float>(world: &World<T>, ray: Ray<T>) -> Option<(usize, Intersection<'_, T>)> {
+    world
+        .objects
+        .iter()
+        .enumerate()
+        .filter_map(|(index, object)| ray.intersect(object).hit().map(|hit| (index, hit)))
+        .min_by(|(_, first), (_, second)| first.t.total_cmp(&second.t))
+}
+
+fn gray(value: f64) -> Color {
+    Color::new(value, value, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::matrix::transformations;
+    use crate::object::Object;
+    use crate::pattern::Pattern;
+    use crate::point::Point;
+    use crate::vector::Vector;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    /// Red sphere on a floor, lit from the front
+    fn world() -> World {
+        let floor = Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material::default(),
+        );
+        let sphere = Object::sphere(
+            transformations::translation(0.0, 0.0, 0.0),
+            Material {
+                pattern: Pattern::solid(Color::new(0.8, 0.1, 0.1)),
+                ..Material::default()
+            },
+        );
+        let light = Light::new(Point::new(0.0, 10.0, -10.0), Color::white());
+        World::new(vec![light], vec![floor, sphere])
+    }
+
+    #[test]
+    fn passes_describe_the_visible_surface() {
+        let world = world();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(gray(4.0), RenderPass::Depth.value(&world, ray));
+        assert_eq!(
+            Color::new(0.0, 0.0, -1.0),
+            RenderPass::Normal.value(&world, ray)
+        );
+        assert_eq!(
+            Color::new(0.8, 0.1, 0.1),
+            RenderPass::Albedo.value(&world, ray)
+        );
+        assert_eq!(gray(1.0), RenderPass::ObjectId.value(&world, ray));
+        assert_eq!(gray(0.0), RenderPass::Shadowed.value(&world, ray));
+        assert_eq!(gray(1.0), RenderPass::Unshadowed.value(&world, ray));
+    }
+
+    #[test]
+    fn shadow_masks_cover_shadowed_surfaces() {
+        let world = world();
+        // looking straight down, behind the sphere
+        let ray = Ray::new(Point::new(0.0, 5.0, 2.0), Vector::new(0.0, -1.0, 0.0));
+
+        assert_eq!(gray(0.0), RenderPass::ObjectId.value(&world, ray));
+        assert_eq!(gray(1.0), RenderPass::Shadowed.value(&world, ray));
+        assert_eq!(gray(0.0), RenderPass::Unshadowed.value(&world, ray));
+    }
+
+    #[test]
+    fn missing_everything_gives_background_values() {
+        let world = world();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(f64::INFINITY, RenderPass::Depth.value(&world, ray).red);
+        assert_eq!(gray(-1.0), RenderPass::ObjectId.value(&world, ray));
+        assert_eq!(Color::black(), RenderPass::Normal.value(&world, ray));
+    }
+
+    #[test]
+    fn passes_can_be_found_by_name() {
+        for pass in RenderPass::ALL {
+            assert_eq!(Some(pass), RenderPass::from_name(pass.name()));
+        }
+        assert_eq!(None, RenderPass::from_name("beauty"));
+    }
+}
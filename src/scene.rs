@@ -0,0 +1,794 @@
+//! Declarative description of a scene, to be loaded at runtime instead of
+//! being hard-coded in Rust.
+//!
+//! Scenes are described in JSON (line comments starting with `//` are allowed):
+//!
+//! ```json
+//! {
+//!   "camera": {
+//!     "width": 1000, "height": 500, "field_of_view": 1.0472,
+//!     "from": [0, 1.5, -5], "to": [0, 1, 0], "up": [0, 1, 0]
+//!   },
+//!   "lights": [{ "position": [-10, 10, -10], "intensity": [1, 1, 1] }],
+//!   "define": {
+//!     "wall": { "color": [1, 0.9, 0.9], "specular": 0 },
+//!     "half-size": [["scale", 0.5, 0.5, 0.5]]
+//!   },
+//!   "objects": [
+//!     { "shape": "plane", "material": "wall" },
+//!     {
+//!       "shape": "sphere",
+//!       "transform": ["half-size", ["translate", 1.5, 0.5, -0.5]],
+//!       "material": { "extends": "wall", "diffuse": 0.7 }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! * `camera` takes the canvas size, the field of view (in radians, strictly
+//!   between 0 and π), the parameters of `view_transform` (or directly its
+//!   `transform`) and optionally `shutter` (`[open, close]`, with
+//!   `0 <= open <= close <= 1`), `samples_per_pixel`, `integrator` (`"whitted"`
+//!   or `"path_tracing"`) and the `seed` of the random sampling.
+//! * `transform` (and `end_transform`, for moving objects) is a chain of operations
+//!   applied in the order they are listed: `translate`, `scale`, `rotate_x`,
+//!   `rotate_y`, `rotate_z` (in radians), `rotate` (around an axis: `["rotate",
+//!   x, y, z, radians]`), `shear` and `matrix` (followed by the four rows of
+//!   the matrix). Moving objects turn smoothly between their two rotations, and
+//!   both of their transforms must either mirror them or not.
+//! * A material has a `color` or a `pattern` (`{"type": "solid", "color": ..}` or
+//!   `{"type": "stripe", "colors": [.., ..], "transform": ..}`), together with the
+//!   `ambient`, `diffuse`, `specular` and `shininess` coefficients, and the
+//!   color of the light it emits (`emission`, black by default).
+//!   Its reflection model (`brdf`) is `"phong"` by default, or one of `"lambert"`,
+//!   `"blinn_phong"`, `{"type": "oren_nayar", "roughness": ..}` (in radians),
+//!   `{"type": "ggx", "roughness": ..}` (between 0 and 1) and the physically
+//!   based `{"type": "metallic_roughness", "metallic": .., "roughness": ..,
+//!   "ior": ..}` (`ior` is 1.5 by default; `diffuse` and `specular` then
+//!   default to 1, and smooth surfaces reflect the scene).
+//!   It can `extends` another material, overriding some of its properties.
+//! * `background` is seen by rays missing every object, and lights the scene
+//!   with the path tracing integrator. It is either a color (black by default),
+//!   `{"type": "gradient", "bottom": .., "top": ..}` (colors straight down and
+//!   straight up) or the daylight `{"type": "sky", "sun": .., "turbidity": ..,
+//!   "intensity": ..}`, with the direction towards the sun (`turbidity` is 3
+//!   and `intensity` 1 by default), or an equirectangular panorama
+//!   `{"type": "image", "path": ..}`, in the `.hdr` or `.pfm` format (relative
+//!   paths start from the working directory).
+//! * Entries in `define` can be referenced by name wherever a material, pattern,
+//!   transform, color, point or vector is expected.
+//!
+//! A scene can be saved back to this format with `Scene::to_snapshot`, which
+//! is lossless: parsing the snapshot gives back exactly the same scene.
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::camera::Camera;
+use crate::scene::builder::SceneBuilder;
+use crate::scene::json::Position;
+use crate::world::World;
+
+mod builder;
+mod json;
+mod snapshot;
+
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+impl Scene {
+    /// Parse the description of a scene
+    pub fn parse(source: &str) -> Result<Self, SceneError> {
+        let root = json::parse(source)?;
+        SceneBuilder::build(&root)
+    }
+
+    /// Complete description of the scene, which can be read back with `parse`,
+    /// unless it contains values which can't be written (see `SnapshotError`)
+    pub fn to_snapshot(&self) -> Result<String, SnapshotError> {
+        snapshot::write(self)
+    }
+}
+
+/// Error in the description of a scene, at the given line and column (starting from 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SceneError {
+    fn new(message: impl Into<String>, position: Position) -> Self {
+        Self {
+            message: message.into(),
+            line: position.line,
+            column: position.column,
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for SceneError {}
+
+/// Value of a scene which can't be written in a snapshot
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// NaN or infinite number, which JSON can't represent
+    NotFinite { value: f64 },
+    /// Background image created in memory rather than loaded from a file,
+    /// since snapshots refer to images by path
+    BackgroundImageWithoutPath,
+    /// Path of a background image which isn't valid UTF-8
+    NonUtf8Path { path: PathBuf },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFinite { value } => {
+                write!(f, "Can't write non-finite number {value} in a snapshot")
+            }
+            Self::BackgroundImageWithoutPath => write!(
+                f,
+                "Can't write a background image not loaded from a file in a snapshot"
+            ),
+            Self::NonUtf8Path { path } => write!(
+                f,
+                "Can't write the path {} in a snapshot, as it isn't valid UTF-8",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::fs::File;
+    use std::path::Path;
+
+    use crate::background::{Background, EnvironmentMap, Sky};
+    use crate::brdf::BrdfModel;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::hdr_writer::HdrWriter;
+    use crate::integrator::Integrator;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::matrix::transformations;
+    use crate::object::Object;
+    use crate::pattern::Pattern;
+    use crate::point::Point;
+    use crate::vector::Vector;
+    use crate::view_transform::view_transform;
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    const CAMERA: &str = r#""camera": {"width": 20, "height": 10, "field_of_view": 1.5}"#;
+
+    fn parse_objects(objects: &str) -> Result<Vec<Object>, SceneError> {
+        Scene::parse(&format!("{{{CAMERA}, \"objects\": {objects}}}"))
+            .map(|scene| scene.world.objects)
+    }
+
+    fn error_at(source: &str) -> (usize, usize, String) {
+        let error = Scene::parse(source).err().expect("Expected an error");
+        (error.line, error.column, error.message)
+    }
+
+    #[test]
+    fn parses_camera() {
+        let scene = Scene::parse(
+            r#"{
+                "camera": {
+                    "width": 100, "height": 50, "field_of_view": 0.5,
+                    "from": [1, 3, 2], "to": [4, -2, 8], "up": [1, 1, 0]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(100, scene.camera.hsize());
+        assert_eq!(50, scene.camera.vsize());
+        assert_eq!(0.5, scene.camera.field_of_view());
+        assert_eq!(
+            view_transform(
+                Point::new(1.0, 3.0, 2.0),
+                Point::new(4.0, -2.0, 8.0),
+                Vector::new(1.0, 1.0, 0.0)
+            ),
+            scene.camera.transform()
+        );
+    }
+
+    #[test]
+    fn parses_lights() {
+        let scene = Scene::parse(&format!(
+            r#"{{{CAMERA}, "lights": [
+                {{"position": [-10, 10, -10], "intensity": [0.5, 0.5, 0.5]}},
+                {{"position": [0, 1, 0]}}
+            ]}}"#
+        ))
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Light::new(Point::new(-10.0, 10.0, -10.0), Color::new(0.5, 0.5, 0.5)),
+                Light::new(Point::new(0.0, 1.0, 0.0), Color::white())
+            ],
+            scene.world.lights
+        );
+    }
+
+    #[test]
+    fn parses_objects_with_materials_and_transforms() {
+        let objects = parse_objects(
+            r#"[
+                {"shape": "plane"},
+                {
+                    "shape": "sphere",
+                    "transform": [["scale", 0.5, 0.5, 0.5], ["translate", 1.5, 0.5, -0.5]],
+                    "material": {"color": [0.5, 1, 0.1], "diffuse": 0.7, "specular": 0.3}
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(Object::default().material, objects[0].material);
+        assert_eq!(crate::shape::Shape::Plane, objects[0].shape);
+        assert_eq!(
+            Object::sphere(
+                transformations::scaling(0.5, 0.5, 0.5)
+                    >> transformations::translation(1.5, 0.5, -0.5),
+                Material {
+                    pattern: Pattern::solid(Color::new(0.5, 1.0, 0.1)),
+                    diffuse: 0.7,
+                    specular: 0.3,
+                    ..Material::default()
+                }
+            ),
+            objects[1]
+        );
+    }
+
+    #[test]
+    fn parses_stripe_patterns() {
+        let objects = parse_objects(
+            r#"[{"shape": "sphere", "material": {"pattern": {
+                "type": "stripe",
+                "colors": [[1, 1, 1], [1, 0, 0]],
+                "transform": [["rotate_z", 1.5707963267948966]]
+            }}}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Pattern::stripe(
+                Color::white(),
+                Color::red(),
+                transformations::rotation_z(PI / 2.0)
+            ),
+            objects[0].material.pattern
+        );
+    }
+
+    #[test]
+    fn parses_moving_objects() {
+        let objects =
+            parse_objects(r#"[{"shape": "sphere", "end_transform": [["translate", 1, 0, 0]]}]"#)
+                .unwrap();
+
+        assert_eq!(
+            Some(transformations::translation(1.0, 0.0, 0.0)),
+            objects[0].end_transformation()
+        );
+    }
+
+    #[test]
+    fn definitions_can_be_reused_and_extended() {
+        let scene = Scene::parse(&format!(
+            r#"{{
+                {CAMERA},
+                "define": {{
+                    "red": [1, 0, 0],
+                    "base": {{"color": "red", "ambient": 0.5}},
+                    "shiny": {{"extends": "base", "shininess": 50}},
+                    "small": [["scale", 0.5, 0.5, 0.5]],
+                    "small-up": ["small", ["translate", 0, 1, 0]]
+                }},
+                "objects": [
+                    {{"shape": "sphere", "material": "shiny", "transform": "small-up"}},
+                    {{"shape": "sphere", "material": {{"extends": "shiny", "ambient": 0.1}}}}
+                ]
+            }}"#
+        ))
+        .unwrap();
+        let objects = scene.world.objects;
+
+        let expected_material = Material {
+            pattern: Pattern::solid(Color::red()),
+            ambient: 0.5,
+            shininess: 50.0,
+            ..Material::default()
+        };
+        assert_eq!(expected_material, objects[0].material);
+        assert_eq!(
+            transformations::scaling(0.5, 0.5, 0.5) >> transformations::translation(0.0, 1.0, 0.0),
+            objects[0].transformation()
+        );
+        assert_eq!(
+            Material {
+                ambient: 0.1,
+                ..expected_material
+            },
+            objects[1].material
+        );
+    }
+
+    #[test]
+    fn errors_report_position_of_invalid_values() {
+        assert_eq!(
+            (1, 1, "Missing required key \"camera\"".to_string()),
+            error_at("{}")
+        );
+        assert_eq!(
+            (
+                3,
+                15,
+                "Unknown shape \"cube\", expected \"sphere\" or \"plane\"".to_string()
+            ),
+            error_at(&format!(
+                "{{{CAMERA},\n  \"objects\": [\n    {{\"shape\": \"cube\"}}\n]}}"
+            ))
+        );
+        assert_eq!(
+            (
+                2,
+                3,
+                "Unknown key \"colour\", expected one of: extends, color, pattern, ambient, diffuse, specular, shininess, emission, brdf"
+                    .to_string()
+            ),
+            error_at(&format!(
+                "{{{CAMERA}, \"objects\": [{{\"shape\": \"sphere\", \"material\": {{\n  \"colour\": [1, 0, 0]}}}}]}}"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let errors = [
+            r#"[{"shape": "sphere", "material": {"ambient": -1}}]"#,
+            r#"[{"shape": "sphere", "material": {"color": [1, 0]}}]"#,
+            r#"[{"shape": "sphere", "material": "undefined"}]"#,
+            r#"[{"shape": "sphere", "transform": [["translate", 1]]}]"#,
+            r#"[{"shape": "sphere", "transform": [["spin", 1]]}]"#,
+            r#"[{"shape": 1}]"#,
+        ];
+
+        for objects in errors {
+            assert!(parse_objects(objects).is_err(), "{objects} should fail");
+        }
+    }
+
+    #[test]
+    fn rejects_negative_colors() {
+        assert_eq!(
+            (
+                1,
+                107,
+                "Invalid material: emission must be at least 0, found -1".to_string()
+            ),
+            error_at(&format!(
+                r#"{{{CAMERA}, "objects": [{{"shape": "sphere", "material": {{"emission": [0, -1, 0]}}}}]}}"#
+            ))
+        );
+        assert!(Scene::parse(&format!(
+            r#"{{{CAMERA}, "lights": [{{"position": [0, 0, 0], "intensity": [1, 1, -2]}}]}}"#
+        ))
+        .is_err());
+        assert!(parse_objects(
+            r#"[{"shape": "sphere", "material": {"pattern": {"type": "stripe", "colors": [[1, 1, 1], [-1, 0, 0]]}}}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_camera_settings() {
+        let camera =
+            |settings: &str| format!(r#"{{"camera": {{"width": 20, "height": 10, {settings}}}}}"#);
+
+        assert_eq!(
+            (
+                1,
+                57,
+                "Invalid camera: field_of_view must be strictly between 0 and 3.141592653589793, found 0"
+                    .to_string()
+            ),
+            error_at(&camera(r#""field_of_view": 0"#))
+        );
+        assert!(Scene::parse(&camera(r#""field_of_view": 3.5"#)).is_err());
+        assert_eq!(
+            (
+                1,
+                73,
+                "Invalid camera: shutter close must be between 1 and 1, found 0".to_string()
+            ),
+            error_at(&camera(r#""field_of_view": 1.5, "shutter": [1, 0]"#))
+        );
+        assert!(Scene::parse(&camera(r#""field_of_view": 1.5, "shutter": [-0.5, 0.5]"#)).is_err());
+        assert!(Scene::parse(&camera(r#""field_of_view": 1.5, "shutter": [0.5, 2]"#)).is_err());
+        assert!(Scene::parse(&camera(r#""field_of_view": 1.5, "shutter": [0.5, 0.5]"#)).is_ok());
+    }
+
+    #[test]
+    fn rejects_transforms_which_cant_be_inverted() {
+        assert_eq!(
+            (
+                2,
+                36,
+                "Invalid transform: Cannot invert matrix with determinant 0".to_string()
+            ),
+            error_at(&format!(
+                "{{{CAMERA}, \"objects\": [\n  {{\"shape\": \"sphere\", \"transform\": [[\"scale\", 1, 0, 1]]}}]}}"
+            ))
+        );
+
+        let errors = [
+            r#"[{"shape": "plane", "end_transform": [["scale", 0, 0, 0]]}]"#,
+            r#"[{"shape": "sphere", "material": {"pattern": {"type": "stripe", "colors": [[1, 1, 1], [0, 0, 0]], "transform": [["scale", 0, 1, 1]]}}}]"#,
+        ];
+        for objects in errors {
+            assert!(parse_objects(objects).is_err(), "{objects} should fail");
+        }
+
+        let camera = Scene::parse(
+            r#"{"camera": {"width": 20, "height": 10, "field_of_view": 1.5, "from": [0, 1, 0], "to": [0, 1, 0]}}"#,
+        );
+        assert!(camera.is_err());
+    }
+
+    #[test]
+    fn rejects_motions_which_mirror_objects_halfway() {
+        // the object would be flattened at time 0.5, when the shutter is open
+        assert_eq!(
+            (
+                1,
+                135,
+                "Invalid transform: Cannot interpolate between a mirroring transformation and one which doesn't mirror"
+                    .to_string()
+            ),
+            error_at(
+                r#"{"camera": {"width": 20, "height": 10, "field_of_view": 1.5, "shutter": [0.5, 0.5]}, "objects": [{"shape": "sphere", "end_transform": [["scale", -1, 1, 1]]}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn parses_reflection_models() {
+        let objects = parse_objects(
+            r#"[
+                {"shape": "sphere"},
+                {"shape": "sphere", "material": {"brdf": "blinn_phong"}},
+                {"shape": "sphere", "material": {"brdf": {"type": "ggx", "roughness": 0.3}}},
+                {"shape": "sphere", "material": {"brdf": {"type": "oren_nayar", "roughness": 1.2}}},
+                {"shape": "sphere", "material": {"brdf": {"type": "metallic_roughness", "metallic": 1, "roughness": 0.2}}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                BrdfModel::Phong,
+                BrdfModel::BlinnPhong,
+                BrdfModel::Ggx { roughness: 0.3 },
+                BrdfModel::OrenNayar { roughness: 1.2 },
+                BrdfModel::MetallicRoughness {
+                    metallic: 1.0,
+                    roughness: 0.2,
+                    ior: 1.5
+                }
+            ],
+            objects
+                .iter()
+                .map(|object| object.material.brdf)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1.0, objects[4].material.diffuse);
+        assert_eq!(1.0, objects[4].material.specular);
+        for invalid in [
+            r#""ggx""#,
+            r#""unknown""#,
+            r#"{"type": "ggx", "roughness": 2}"#,
+            r#"{"type": "lambert", "roughness": 0.5}"#,
+            r#"{"type": "metallic_roughness", "metallic": 1.5}"#,
+            r#"{"type": "metallic_roughness", "ior": 0.5}"#,
+            r#"{"type": "ggx", "metallic": 1}"#,
+        ] {
+            let objects = format!(r#"[{{"shape": "sphere", "material": {{"brdf": {invalid}}}}}]"#);
+            assert!(parse_objects(&objects).is_err(), "{invalid} should fail");
+        }
+    }
+
+    #[test]
+    fn circular_definitions_are_rejected() {
+        let error = Scene::parse(&format!(
+            r#"{{{CAMERA}, "define": {{"a": {{"extends": "b"}}, "b": {{"extends": "a"}}}},
+                "objects": [{{"shape": "sphere", "material": "a"}}]}}"#
+        ))
+        .err()
+        .unwrap();
+
+        assert!(error.message.contains("circular"));
+    }
+
+    fn procedural_scene() -> Scene {
+        let mut rng = crate::random::Rng::seeded(42);
+        let mut random = || rng.next_f64() * 2.0 - 1.0;
+
+        let mut objects = vec![Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material {
+                pattern: Pattern::stripe(
+                    Color::new(random(), 0.1, 0.3),
+                    Color::white(),
+                    transformations::rotation_y(random())
+                        >> transformations::scaling(0.3, 1.0, 1.0),
+                ),
+                ..Material::default()
+            },
+        )];
+        for _ in 0..5 {
+            let transformation = transformations::scaling(0.4, random().abs(), 0.4)
+                >> transformations::rotation_z(random())
+                >> transformations::translation(random(), random(), random());
+            let material = Material {
+                pattern: Pattern::solid(Color::new(random().abs(), random().abs(), 1.0 / 3.0)),
+                diffuse: random().abs(),
+                shininess: 1.0 + random().abs() * 100.0,
+                emission: Color::new(random().abs(), 0.0, 0.0),
+                brdf: BrdfModel::Ggx {
+                    roughness: random().abs(),
+                },
+                ..Material::default()
+            };
+            objects.push(
+                Object::sphere(transformation, material)
+                    .with_motion(transformation >> transformations::translation(0.1, -0.0, 0.0)),
+            );
+        }
+        let lights = vec![
+            Light::new(Point::new(-10.0, 10.0, -10.0), Color::white()),
+            Light::new(Point::new(random(), 5.0, -7.0), Color::new(0.2, 0.1, 0.1)),
+        ];
+
+        let sky = Sky::new(Vector::new(random(), 0.3, 1.0), 2.0 + random().abs(), 0.5);
+
+        Scene {
+            world: World::new(lights, objects).with_background(Background::Sky(sky)),
+            camera: Camera::new(24, 16, PI / 3.0)
+                .with_transform(view_transform(
+                    Point::new(random(), 1.5, -5.0),
+                    Point::new(0.0, 0.5, 0.0),
+                    Vector::new(0.0, 1.0, 0.0),
+                ))
+                .with_shutter(0.0, 1.0)
+                .with_samples_per_pixel(3)
+                .with_integrator(Integrator::PathTracing)
+                .with_seed(1234)
+                .with_progress(false),
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trip_is_lossless() {
+        let scene = procedural_scene();
+
+        let snapshot = scene.to_snapshot().unwrap();
+        let loaded = Scene::parse(&snapshot).unwrap();
+
+        // `Debug` prints the exact value of every number
+        assert_eq!(
+            format!("{:?}", scene.world.objects),
+            format!("{:?}", loaded.world.objects)
+        );
+        assert_eq!(
+            format!("{:?}", scene.world.lights),
+            format!("{:?}", loaded.world.lights)
+        );
+        assert_eq!(
+            format!("{:?}", scene.camera.transform()),
+            format!("{:?}", loaded.camera.transform())
+        );
+        assert_eq!(
+            format!("{:?}", scene.world.background),
+            format!("{:?}", loaded.world.background)
+        );
+        assert_eq!(Integrator::PathTracing, loaded.camera.integrator());
+        assert_eq!(1234, loaded.camera.seed());
+        assert_eq!(snapshot, loaded.to_snapshot().unwrap());
+    }
+
+    #[test]
+    fn loaded_snapshot_renders_identically() {
+        let scene = procedural_scene();
+        let loaded = Scene::parse(&scene.to_snapshot().unwrap()).unwrap();
+
+        let original_canvas = scene.camera.render(scene.world);
+        let loaded_canvas = loaded.camera.with_progress(false).render(loaded.world);
+
+        assert_eq!(format!("{original_canvas:?}"), format!("{loaded_canvas:?}"));
+    }
+
+    #[test]
+    fn parses_matrix_transforms() {
+        let objects = parse_objects(
+            r#"[{"shape": "sphere", "transform": [
+                ["matrix", [1, 0, 0, 2], [0, 1, 0, 3], [0, 0, 1, 4], [0, 0, 0, 1]]
+            ]}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            transformations::translation(2.0, 3.0, 4.0),
+            objects[0].transformation()
+        );
+        assert!(
+            parse_objects(r#"[{"shape": "sphere", "transform": [["matrix", [1, 0, 0, 0]]]}]"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_rotations_around_arbitrary_axes() {
+        let objects =
+            parse_objects(r#"[{"shape": "sphere", "transform": [["rotate", 0, 2, 0, 0.5]]}]"#)
+                .unwrap();
+
+        assert_eq!(
+            transformations::rotation_y(0.5),
+            objects[0].transformation()
+        );
+        assert!(
+            parse_objects(r#"[{"shape": "sphere", "transform": [["rotate", 0, 0, 0, 1]]}]"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_backgrounds() {
+        let background = |background: &str| {
+            Scene::parse(&format!(
+                r#"{{{CAMERA}, "define": {{"blue": [0, 0, 1]}}, "background": {background}}}"#
+            ))
+            .map(|scene| format!("{:?}", scene.world.background))
+        };
+
+        assert_eq!(
+            Ok(format!(
+                "{:?}",
+                Background::Constant(Color::new(0.0, 0.0, 1.0))
+            )),
+            background(r#""blue""#)
+        );
+        assert_eq!(
+            Ok(format!(
+                "{:?}",
+                Background::Gradient {
+                    bottom: Color::white(),
+                    top: Color::new(0.0, 0.0, 1.0)
+                }
+            )),
+            background(r#"{"type": "gradient", "bottom": [1, 1, 1], "top": "blue"}"#)
+        );
+        assert_eq!(
+            Ok(format!(
+                "{:?}",
+                Background::Sky(Sky::new(
+                    Vector::new(0.0, 1.0, 1.0),
+                    Sky::DEFAULT_TURBIDITY,
+                    1.0
+                ))
+            )),
+            background(r#"{"type": "sky", "sun": [0, 1, 1]}"#)
+        );
+        for invalid in [
+            r#"{"type": "sky"}"#,
+            r#"{"type": "sky", "sun": [0, 0, 0]}"#,
+            r#"{"type": "sky", "sun": [0, 1, 0], "turbidity": 0}"#,
+            r#"{"type": "sky", "sun": [0, 1, 0], "top": [1, 1, 1]}"#,
+            r#"{"type": "gradient", "bottom": [1, 1, 1]}"#,
+            r#"{"type": "stars"}"#,
+            r#"{"type": "image", "path": "does/not/exist.hdr"}"#,
+        ] {
+            assert!(background(invalid).is_err(), "{invalid} should fail");
+        }
+    }
+
+    #[test]
+    fn background_images_are_written_by_path() {
+        let path = std::env::temp_dir().join("raytracer \"panorama\".hdr");
+        let mut image = Canvas::new(2, 1);
+        image.write_pixel(1, 0, Color::new(4.0, 2.0, 1.0));
+        HdrWriter::from_writer(File::create(&path).unwrap())
+            .write_canvas(&image)
+            .unwrap();
+        let path = path.to_str().unwrap();
+
+        let scene = Scene::parse(&format!(
+            r#"{{{CAMERA}, "background": {{"type": "image", "path": {path:?}}}}}"#
+        ))
+        .unwrap();
+        let snapshot = scene.to_snapshot().unwrap();
+        let loaded = Scene::parse(&snapshot).unwrap();
+
+        let Background::Image(environment_map) = &loaded.world.background else {
+            panic!("{:?} is not an image", loaded.world.background);
+        };
+        assert_eq!(Some(Path::new(path)), environment_map.path());
+        // up to the precision of the format
+        assert_eq!(
+            Color::new(4.015625, 2.015625, 1.015625),
+            environment_map.image().pixel_at(1, 0)
+        );
+        assert_eq!(snapshot, loaded.to_snapshot().unwrap());
+    }
+
+    #[test]
+    fn values_which_can_not_be_written_are_reported() {
+        let mut scene = procedural_scene();
+        scene.world.lights[0].intensity = Color::new(f64::INFINITY, 1.0, 1.0);
+        assert_eq!(
+            Err(SnapshotError::NotFinite {
+                value: f64::INFINITY
+            }),
+            scene.to_snapshot()
+        );
+
+        let mut scene = procedural_scene();
+        scene.world.background = Background::Image(EnvironmentMap::new(Canvas::new(2, 1)));
+        assert_eq!(
+            Err(SnapshotError::BackgroundImageWithoutPath),
+            scene.to_snapshot()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn background_images_with_non_utf8_paths_are_reported() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::env::temp_dir().join(OsStr::from_bytes(b"raytracer \xff.hdr"));
+        HdrWriter::from_writer(File::create(&path).unwrap())
+            .write_canvas(&Canvas::new(2, 1))
+            .unwrap();
+        let mut scene = procedural_scene();
+        scene.world.background = Background::Image(EnvironmentMap::load(&path).unwrap());
+
+        assert_eq!(
+            Err(SnapshotError::NonUtf8Path { path }),
+            scene.to_snapshot()
+        );
+    }
+
+    #[test]
+    fn example_scene_can_be_parsed() {
+        let scene = Scene::parse(include_str!("../examples/scenes/patterns.json")).unwrap();
+
+        assert_eq!(1, scene.world.lights.len());
+        assert_eq!(6, scene.world.objects.len());
+    }
+}
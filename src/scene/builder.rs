@@ -0,0 +1,712 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::background::{Background, EnvironmentMap, Sky};
+use crate::brdf::{BrdfModel, MetallicRoughness};
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::error::ValidationError;
+use crate::integrator::Integrator;
+use crate::light::Light;
+use crate::material::Material;
+use crate::matrix::{transformations, Matrix};
+use crate::object::Object;
+use crate::pattern::Pattern;
+use crate::point::Point;
+use crate::scene::json::{Key, Node, Value};
+use crate::scene::{Scene, SceneError};
+use crate::vector::Vector;
+use crate::view_transform::view_transform;
+use crate::world::World;
+
+/// Maximum number of nested references between definitions,
+/// used to detect circular definitions.
+const MAX_DEFINITION_DEPTH: usize = 32;
+
+/// Builds a scene out of the parsed JSON document
+pub struct SceneBuilder<'a> {
+    definitions: HashMap<&'a str, &'a Node>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub fn build(root: &'a Node) -> Result<Scene, SceneError> {
+        let fields = Fields::of(
+            root,
+            &["camera", "lights", "objects", "background", "define"],
+        )?;
+
+        let mut builder = Self {
+            definitions: HashMap::new(),
+        };
+        if let Some(definitions) = fields.get("define") {
+            for (key, value) in Fields::of(definitions, &[])?.members {
+                builder.definitions.insert(&key.name, value);
+            }
+        }
+
+        let camera = builder.camera(fields.required("camera")?)?;
+        let lights = match fields.get("lights") {
+            None => vec![],
+            Some(lights) => array(lights)?
+                .iter()
+                .map(|light| builder.light(light))
+                .collect::<Result<_, _>>()?,
+        };
+        let objects = match fields.get("objects") {
+            None => vec![],
+            Some(objects) => array(objects)?
+                .iter()
+                .map(|object| builder.object(object))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let background = match fields.get("background") {
+            None => Background::default(),
+            Some(background) => builder.background(background)?,
+        };
+
+        Ok(Scene {
+            world: World::new(lights, objects).with_background(background),
+            camera,
+        })
+    }
+
+    fn camera(&self, node: &'a Node) -> Result<Camera, SceneError> {
+        let fields = Fields::of(
+            node,
+            &[
+                "width",
+                "height",
+                "field_of_view",
+                "from",
+                "to",
+                "up",
+                "transform",
+                "shutter",
+                "samples_per_pixel",
+                "integrator",
+                "seed",
+            ],
+        )?;
+
+        let width = positive_integer(fields.required("width")?)?;
+        let height = positive_integer(fields.required("height")?)?;
+        let field_of_view_node = fields.required("field_of_view")?;
+        let field_of_view = number(field_of_view_node)?;
+        ValidationError::check_open_range("field_of_view", field_of_view, 0.0, PI)
+            .map_err(|error| invalid_camera(field_of_view_node, error))?;
+
+        // node blamed when the transformation can't be inverted
+        let (transform, transform_node) = match fields.get("transform") {
+            Some(transform) => {
+                fields.reject(&["from", "to", "up"])?;
+                (self.transform(transform, 0)?, transform)
+            }
+            None => {
+                let from = fields
+                    .get("from")
+                    .map(|from| self.point(from))
+                    .transpose()?
+                    .unwrap_or_else(Point::origin);
+                let to = fields
+                    .get("to")
+                    .map(|to| self.point(to))
+                    .transpose()?
+                    .unwrap_or_else(|| Point::new(0.0, 0.0, -1.0));
+                let up = fields
+                    .get("up")
+                    .map(|up| self.vector(up))
+                    .transpose()?
+                    .unwrap_or_else(|| Vector::new(0.0, 1.0, 0.0));
+                (view_transform(from, to, up), node)
+            }
+        };
+
+        let mut camera = Camera::new(width, height, field_of_view)
+            .try_with_view_transform(transform)
+            .map_err(|error| invalid_transform(transform_node, error))?;
+
+        if let Some(shutter) = fields.get("shutter") {
+            let [open, close] = numbers(shutter)?;
+            ValidationError::check_range("shutter open", open, 0.0, 1.0)
+                .and_then(|()| ValidationError::check_range("shutter close", close, open, 1.0))
+                .map_err(|error| invalid_camera(shutter, error))?;
+            camera = camera.with_shutter(open, close);
+        }
+        if let Some(samples) = fields.get("samples_per_pixel") {
+            camera = camera.with_samples_per_pixel(positive_integer(samples)?);
+        }
+        if let Some(integrator) = fields.get("integrator") {
+            camera = camera.with_integrator(match string(integrator)? {
+                "whitted" => Integrator::Whitted,
+                "path_tracing" => Integrator::PathTracing,
+                other => {
+                    return Err(integrator.error(format!(
+                        "Unknown integrator \"{other}\", expected \"whitted\" or \"path_tracing\""
+                    )))
+                }
+            });
+        }
+        if let Some(seed) = fields.get("seed") {
+            camera = camera.with_seed(non_negative_integer(seed)?);
+        }
+
+        Ok(camera)
+    }
+
+    fn light(&self, node: &'a Node) -> Result<Light, SceneError> {
+        let fields = Fields::of(node, &["position", "intensity"])?;
+
+        let position = self.point(fields.required("position")?)?;
+        let intensity = fields
+            .get("intensity")
+            .map(|intensity| self.color(intensity))
+            .transpose()?
+            .unwrap_or_else(Color::white);
+
+        let light = Light::new(position, intensity);
+        light
+            .validate()
+            .map_err(|error| node.error(format!("Invalid light: {error}")))?;
+        Ok(light)
+    }
+
+    /// Either a constant color, or an object with the `type` of background
+    /// and its parameters
+    fn background(&self, node: &'a Node) -> Result<Background, SceneError> {
+        let node = self.resolve(node)?;
+        if let Value::Array(_) = node.value {
+            return Ok(Background::Constant(self.color(node)?));
+        }
+
+        let fields = Fields::of(
+            node,
+            &[
+                "type",
+                "bottom",
+                "top",
+                "sun",
+                "turbidity",
+                "intensity",
+                "path",
+            ],
+        )?;
+        let background_type = fields.required("type")?;
+        match string(background_type)? {
+            "gradient" => {
+                fields.reject(&["sun", "turbidity", "intensity", "path"])?;
+                Ok(Background::Gradient {
+                    bottom: self.color(fields.required("bottom")?)?,
+                    top: self.color(fields.required("top")?)?,
+                })
+            }
+            "sky" => {
+                fields.reject(&["bottom", "top", "path"])?;
+                let sun = fields.required("sun")?;
+                let sun_direction = self.vector(sun)?;
+                if sun_direction.magnitude() == 0.0 {
+                    return Err(sun.error("Expected a non-zero direction towards the sun"));
+                }
+                let turbidity = match fields.get("turbidity") {
+                    None => Sky::DEFAULT_TURBIDITY,
+                    Some(turbidity) => {
+                        let value = number(turbidity)?;
+                        if !(1.0..=20.0).contains(&value) {
+                            return Err(turbidity.error(format!(
+                                "Expected a turbidity between 1 and 20, found {value}"
+                            )));
+                        }
+                        value
+                    }
+                };
+                let intensity = match fields.get("intensity") {
+                    None => 1.0,
+                    Some(intensity) => non_negative_number(intensity)?,
+                };
+                Ok(Background::Sky(Sky::new(
+                    sun_direction,
+                    turbidity,
+                    intensity,
+                )))
+            }
+            "image" => {
+                fields.reject(&["bottom", "top", "sun", "turbidity", "intensity"])?;
+                let path_node = fields.required("path")?;
+                let path = string(path_node)?;
+                let environment_map = EnvironmentMap::load(path).map_err(|error| {
+                    path_node.error(format!("Can't load image \"{path}\": {error}"))
+                })?;
+                Ok(Background::Image(environment_map))
+            }
+            other => Err(background_type.error(format!(
+                "Unknown background type \"{other}\", expected one of: gradient, sky, image"
+            ))),
+        }
+    }
+
+    fn object(&self, node: &'a Node) -> Result<Object, SceneError> {
+        let fields = Fields::of(node, &["shape", "transform", "end_transform", "material"])?;
+
+        let transform = fields.get("transform");
+        let transformation = match transform {
+            None => Matrix::identity(),
+            Some(transform) => self.transform(transform, 0)?,
+        };
+        let material = match fields.get("material") {
+            None => Material::default(),
+            Some(material) => self.material(material, 0)?,
+        };
+
+        let shape = fields.required("shape")?;
+        let object = match string(shape)? {
+            "sphere" => Object::try_sphere(transformation, material),
+            "plane" => Object::try_plane(transformation, material),
+            other => {
+                return Err(shape.error(format!(
+                    "Unknown shape \"{other}\", expected \"sphere\" or \"plane\""
+                )))
+            }
+        };
+        let mut object =
+            object.map_err(|error| invalid_transform(transform.unwrap_or(node), error))?;
+
+        if let Some(end_transform) = fields.get("end_transform") {
+            object = object
+                .try_with_motion(self.transform(end_transform, 0)?)
+                .map_err(|error| invalid_transform(end_transform, error))?;
+        }
+
+        Ok(object)
+    }
+
+    fn material(&self, node: &'a Node, depth: usize) -> Result<Material, SceneError> {
+        let node = self.resolve(node)?;
+        let fields = Fields::of(
+            node,
+            &[
+                "extends",
+                "color",
+                "pattern",
+                "ambient",
+                "diffuse",
+                "specular",
+                "shininess",
+                "emission",
+                "brdf",
+            ],
+        )?;
+
+        let mut material = match fields.get("extends") {
+            None => Material::default(),
+            Some(_) if depth >= MAX_DEFINITION_DEPTH => {
+                return Err(node.error("Materials extended too deeply (circular definition?)"))
+            }
+            Some(base) => self.material(base, depth + 1)?,
+        };
+
+        if let Some(color) = fields.get("color") {
+            if fields.get("pattern").is_some() {
+                return Err(color.error("A material can't have both a color and a pattern"));
+            }
+            material.pattern = Pattern::solid(self.color(color)?);
+        }
+        if let Some(pattern) = fields.get("pattern") {
+            material.pattern = self.pattern(pattern)?;
+        }
+        if let Some(brdf) = fields.get("brdf") {
+            let brdf = self.brdf(brdf, 0)?;
+            let is_metallic_roughness =
+                |brdf: BrdfModel| matches!(brdf, BrdfModel::MetallicRoughness { .. });
+            if is_metallic_roughness(brdf) && !is_metallic_roughness(material.brdf) {
+                // physically based materials are fully described by their model
+                material.diffuse = 1.0;
+                material.specular = 1.0;
+            }
+            material.brdf = brdf;
+        }
+        if let Some(ambient) = fields.get("ambient") {
+            material.ambient = non_negative_number(ambient)?;
+        }
+        if let Some(diffuse) = fields.get("diffuse") {
+            material.diffuse = non_negative_number(diffuse)?;
+        }
+        if let Some(specular) = fields.get("specular") {
+            material.specular = non_negative_number(specular)?;
+        }
+        if let Some(shininess) = fields.get("shininess") {
+            material.shininess = non_negative_number(shininess)?;
+        }
+        if let Some(emission) = fields.get("emission") {
+            material.emission = self.color(emission)?;
+        }
+
+        material
+            .validate()
+            .map_err(|error| node.error(format!("Invalid material: {error}")))?;
+        Ok(material)
+    }
+
+    /// Either the name of a reflection model without parameters (eg. `"lambert"`),
+    /// or an object with its `type` and parameters
+    fn brdf(&self, node: &'a Node, depth: usize) -> Result<BrdfModel, SceneError> {
+        if let Value::String(name) = &node.value {
+            return match name.as_str() {
+                "lambert" => Ok(BrdfModel::Lambert),
+                "phong" => Ok(BrdfModel::Phong),
+                "blinn_phong" => Ok(BrdfModel::BlinnPhong),
+                "oren_nayar" | "ggx" | "metallic_roughness" => Err(node.error(format!(
+                    "Reflection model \"{name}\" needs parameters, eg. {{\"type\": \"{name}\", \"roughness\": 0.5}}"
+                ))),
+                _ if depth >= MAX_DEFINITION_DEPTH => {
+                    Err(node.error("Definitions nested too deeply (circular definition?)"))
+                }
+                _ => self.brdf(self.definition(node, name)?, depth + 1),
+            };
+        }
+
+        let fields = Fields::of(node, &["type", "roughness", "metallic", "ior"])?;
+        let brdf_type = fields.required("type")?;
+        if string(brdf_type)? != "metallic_roughness" {
+            fields.reject(&["metallic", "ior"])?;
+        }
+        match string(brdf_type)? {
+            "oren_nayar" => Ok(BrdfModel::OrenNayar {
+                roughness: non_negative_number(fields.required("roughness")?)?,
+            }),
+            "ggx" => Ok(BrdfModel::Ggx {
+                roughness: unit_number(fields.required("roughness")?)?,
+            }),
+            "metallic_roughness" => Ok(BrdfModel::MetallicRoughness {
+                metallic: unit_number(fields.required("metallic")?)?,
+                roughness: unit_number(fields.required("roughness")?)?,
+                ior: match fields.get("ior") {
+                    None => MetallicRoughness::DEFAULT_IOR,
+                    Some(ior) => {
+                        let value = number(ior)?;
+                        if value < 1.0 {
+                            return Err(ior.error(format!(
+                                "Expected an index of refraction of at least 1, found {value}"
+                            )));
+                        }
+                        value
+                    }
+                },
+            }),
+            "lambert" | "phong" | "blinn_phong" => {
+                fields.reject(&["roughness"])?;
+                self.brdf(brdf_type, depth)
+            }
+            other => Err(brdf_type.error(format!(
+                "Unknown reflection model \"{other}\", expected one of: lambert, phong, blinn_phong, oren_nayar, ggx, metallic_roughness"
+            ))),
+        }
+    }
+
+    fn pattern(&self, node: &'a Node) -> Result<Pattern, SceneError> {
+        let node = self.resolve(node)?;
+        let fields = Fields::of(node, &["type", "color", "colors", "transform"])?;
+
+        let pattern_type = fields.required("type")?;
+        match string(pattern_type)? {
+            "solid" => {
+                fields.reject(&["colors", "transform"])?;
+                Ok(Pattern::solid(self.color(fields.required("color")?)?))
+            }
+            "stripe" => {
+                fields.reject(&["color"])?;
+                let colors = fields.required("colors")?;
+                let [first_color, second_color] = self.colors(colors)?;
+                let transform = fields.get("transform");
+                let transformation = match transform {
+                    None => Matrix::identity(),
+                    Some(transform) => self.transform(transform, 0)?,
+                };
+                Pattern::try_stripe(first_color, second_color, transformation)
+                    .map_err(|error| invalid_transform(transform.unwrap_or(node), error))
+            }
+            other => Err(pattern_type.error(format!(
+                "Unknown pattern type \"{other}\", expected \"solid\" or \"stripe\""
+            ))),
+        }
+    }
+
+    /// Chain of transformations, applied in the order they are listed.
+    /// Each element is either an operation (eg. `["translate", 1, 2, 3]`)
+    /// or the name of a defined chain of transformations.
+    fn transform(&self, node: &'a Node, depth: usize) -> Result<Matrix<4, 4>, SceneError> {
+        if depth >= MAX_DEFINITION_DEPTH {
+            return Err(node.error("Transforms nested too deeply (circular definition?)"));
+        }
+        let node = self.resolve(node)?;
+
+        // the first transformation is taken as is rather than composed with the
+        // identity, so that matrices written in a snapshot are read back exactly
+        let mut result: Option<Matrix<4, 4>> = None;
+        for element in array(node)? {
+            let transformation = match &element.value {
+                Value::String(_) => self.transform(element, depth + 1)?,
+                _ => self.transform_operation(element)?,
+            };
+            result = Some(match result {
+                None => transformation,
+                Some(result) => result >> transformation,
+            });
+        }
+        Ok(result.unwrap_or_else(Matrix::identity))
+    }
+
+    fn transform_operation(&self, node: &'a Node) -> Result<Matrix<4, 4>, SceneError> {
+        let elements = array(node)?;
+        let Some((operation, arguments)) = elements.split_first() else {
+            return Err(node.error("Expected a transform operation, eg. [\"translate\", 1, 2, 3]"));
+        };
+
+        let name = string(operation)?;
+        if name == "matrix" {
+            return self.matrix(node, arguments);
+        }
+
+        let arguments = arguments
+            .iter()
+            .map(number)
+            .collect::<Result<Vec<f64>, _>>()?;
+        let expected_arguments = match name {
+            "translate" | "scale" => 3,
+            "rotate_x" | "rotate_y" | "rotate_z" => 1,
+            "rotate" => 4,
+            "shear" => 6,
+            other => {
+                return Err(operation.error(format!("Unknown transform operation \"{other}\"")))
+            }
+        };
+        if arguments.len() != expected_arguments {
+            return Err(node.error(format!(
+                "Transform operation \"{name}\" expects {expected_arguments} arguments, found {}",
+                arguments.len()
+            )));
+        }
+
+        let a = &arguments;
+        Ok(match name {
+            "translate" => transformations::translation(a[0], a[1], a[2]),
+            "scale" => transformations::scaling(a[0], a[1], a[2]),
+            "rotate_x" => transformations::rotation_x(a[0]),
+            "rotate_y" => transformations::rotation_y(a[0]),
+            "rotate_z" => transformations::rotation_z(a[0]),
+            "rotate" => {
+                let axis = Vector::new(a[0], a[1], a[2]);
+                if axis.magnitude() == 0.0 {
+                    return Err(node.error("The axis of a rotation can't be zero"));
+                }
+                transformations::rotation(axis, a[3])
+            }
+            "shear" => transformations::shearing(a[0], a[1], a[2], a[3], a[4], a[5]),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Matrix given by its four rows, eg. `["matrix", [1, 0, 0, 0], [0, 1, 0, 0], ...]`
+    fn matrix(&self, node: &'a Node, rows: &'a [Node]) -> Result<Matrix<4, 4>, SceneError> {
+        if rows.len() != 4 {
+            return Err(node.error(format!(
+                "Transform operation \"matrix\" expects 4 rows, found {}",
+                rows.len()
+            )));
+        }
+
+        let mut matrix = Matrix::zeros();
+        for (r, row) in rows.iter().enumerate() {
+            let row: [f64; 4] = numbers(row)?;
+            for (c, value) in row.into_iter().enumerate() {
+                matrix[(r, c)] = value;
+            }
+        }
+        Ok(matrix)
+    }
+
+    fn colors(&self, node: &'a Node) -> Result<[Color; 2], SceneError> {
+        let node = self.resolve(node)?;
+        match array(node)? {
+            [first, second] => Ok([self.color(first)?, self.color(second)?]),
+            _ => Err(node.error("Expected an array of two colors")),
+        }
+    }
+
+    fn color(&self, node: &'a Node) -> Result<Color, SceneError> {
+        let [red, green, blue] = numbers(self.resolve(node)?)?;
+        Ok(Color::new(red, green, blue))
+    }
+
+    fn point(&self, node: &'a Node) -> Result<Point, SceneError> {
+        let [x, y, z] = numbers(self.resolve(node)?)?;
+        Ok(Point::new(x, y, z))
+    }
+
+    fn vector(&self, node: &'a Node) -> Result<Vector, SceneError> {
+        let [x, y, z] = numbers(self.resolve(node)?)?;
+        Ok(Vector::new(x, y, z))
+    }
+
+    /// Entry of `define` with the given name
+    fn definition(&self, node: &'a Node, name: &str) -> Result<&'a Node, SceneError> {
+        self.definitions
+            .get(name)
+            .copied()
+            .ok_or_else(|| node.error(format!("Unknown definition \"{name}\"")))
+    }
+
+    /// Follow references to definitions, ie. strings naming an entry in `define`
+    fn resolve(&self, node: &'a Node) -> Result<&'a Node, SceneError> {
+        let mut resolved = node;
+        for _ in 0..MAX_DEFINITION_DEPTH {
+            match &resolved.value {
+                Value::String(name) => resolved = self.definition(resolved, name)?,
+                _ => return Ok(resolved),
+            }
+        }
+        Err(node.error("Definitions nested too deeply (circular definition?)"))
+    }
+}
+
+/// Members of a JSON object, restricted to a set of allowed keys
+struct Fields<'a> {
+    node: &'a Node,
+    members: &'a [(Key, Node)],
+}
+
+impl<'a> Fields<'a> {
+    /// Fields of an object node. Keys not in `allowed_keys` are rejected,
+    /// unless `allowed_keys` is empty.
+    fn of(node: &'a Node, allowed_keys: &[&str]) -> Result<Self, SceneError> {
+        let Value::Object(members) = &node.value else {
+            return Err(unexpected_type(node, "object"));
+        };
+
+        if !allowed_keys.is_empty() {
+            if let Some((key, _)) = members
+                .iter()
+                .find(|(key, _)| !allowed_keys.contains(&key.name.as_str()))
+            {
+                return Err(SceneError::new(
+                    format!(
+                        "Unknown key \"{}\", expected one of: {}",
+                        key.name,
+                        allowed_keys.join(", ")
+                    ),
+                    key.position,
+                ));
+            }
+        }
+
+        Ok(Self { node, members })
+    }
+
+    fn get(&self, name: &str) -> Option<&'a Node> {
+        self.members
+            .iter()
+            .find(|(key, _)| key.name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn required(&self, name: &str) -> Result<&'a Node, SceneError> {
+        self.get(name)
+            .ok_or_else(|| self.node.error(format!("Missing required key \"{name}\"")))
+    }
+
+    /// Reject keys that are allowed in general, but not in the current context
+    fn reject(&self, names: &[&str]) -> Result<(), SceneError> {
+        for (key, _) in self.members {
+            if names.contains(&key.name.as_str()) {
+                return Err(SceneError::new(
+                    format!("Unexpected key \"{}\"", key.name),
+                    key.position,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unexpected_type(node: &Node, expected: &str) -> SceneError {
+    node.error(format!("Expected {expected}, found {}", node.type_name()))
+}
+
+fn invalid_transform(node: &Node, error: impl fmt::Display) -> SceneError {
+    node.error(format!("Invalid transform: {error}"))
+}
+
+fn invalid_camera(node: &Node, error: ValidationError) -> SceneError {
+    node.error(format!("Invalid camera: {error}"))
+}
+
+fn array(node: &Node) -> Result<&[Node], SceneError> {
+    match &node.value {
+        Value::Array(elements) => Ok(elements),
+        _ => Err(unexpected_type(node, "array")),
+    }
+}
+
+fn string(node: &Node) -> Result<&str, SceneError> {
+    match &node.value {
+        Value::String(value) => Ok(value),
+        _ => Err(unexpected_type(node, "string")),
+    }
+}
+
+fn number(node: &Node) -> Result<f64, SceneError> {
+    match node.value {
+        Value::Number(value) => Ok(value),
+        _ => Err(unexpected_type(node, "number")),
+    }
+}
+
+fn non_negative_number(node: &Node) -> Result<f64, SceneError> {
+    let value = number(node)?;
+    if value < 0.0 {
+        return Err(node.error(format!("Expected a nonnegative number, found {value}")));
+    }
+    Ok(value)
+}
+
+/// Number between 0 and 1
+fn unit_number(node: &Node) -> Result<f64, SceneError> {
+    let value = number(node)?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(node.error(format!("Expected a number between 0 and 1, found {value}")));
+    }
+    Ok(value)
+}
+
+fn positive_integer(node: &Node) -> Result<usize, SceneError> {
+    let value = number(node)?;
+    if value < 1.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+        return Err(node.error(format!("Expected a positive integer, found {value}")));
+    }
+    Ok(value as usize)
+}
+
+fn non_negative_integer(node: &Node) -> Result<u32, SceneError> {
+    let value = number(node)?;
+    if value < 0.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+        return Err(node.error(format!("Expected a nonnegative integer, found {value}")));
+    }
+    Ok(value as u32)
+}
+
+/// Fixed-size array of numbers, eg. the three components of a point
+fn numbers<const N: usize>(node: &Node) -> Result<[f64; N], SceneError> {
+    let elements = array(node)?;
+    if elements.len() != N {
+        return Err(node.error(format!(
+            "Expected an array of {N} numbers, found {} elements",
+            elements.len()
+        )));
+    }
+
+    let mut result = [0.0; N];
+    for (value, element) in result.iter_mut().zip(elements) {
+        *value = number(element)?;
+    }
+    Ok(result)
+}
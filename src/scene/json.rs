@@ -0,0 +1,486 @@
+//! Minimal JSON parser keeping track of the position of every value,
+//! so that errors in the scene description can point to the offending line.
+//!
+//! On top of standard JSON, line comments starting with `//` are allowed.
+
+use crate::scene::SceneError;
+
+/// Maximum number of nested arrays and objects, so that deeply nested
+/// documents are reported as errors instead of overflowing the stack
+const MAX_NESTING_DEPTH: usize = 128;
+
+/// Line and column (both starting from 1) of a value in the source
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub value: Value,
+    pub position: Position,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Node>),
+    /// Members of the object, in the order they appear in the source
+    Object(Vec<(Key, Node)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Key {
+    pub name: String,
+    pub position: Position,
+}
+
+impl Node {
+    pub fn error(&self, message: impl Into<String>) -> SceneError {
+        SceneError::new(message, self.position)
+    }
+
+    /// Human readable name of the type of the node, used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self.value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Node, SceneError> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        index: 0,
+        position: Position { line: 1, column: 1 },
+        depth: 0,
+    };
+
+    let node = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("Unexpected content after the end of the document"));
+    }
+    Ok(node)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    position: Position,
+    /// Number of arrays and objects containing the current value
+    depth: usize,
+}
+
+impl Parser {
+    fn parse_value(&mut self) -> Result<Node, SceneError> {
+        self.skip_whitespace();
+        let position = self.position;
+
+        let value = match self.peek() {
+            None => return Err(self.error("Unexpected end of document")),
+            Some('{') => self.parse_nested(Self::parse_object)?,
+            Some('[') => self.parse_nested(Self::parse_array)?,
+            Some('"') => Value::String(self.parse_string()?),
+            Some('t') => self.parse_literal("true", Value::Bool(true))?,
+            Some('f') => self.parse_literal("false", Value::Bool(false))?,
+            Some('n') => self.parse_literal("null", Value::Null)?,
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number()?,
+            Some(c) => return Err(self.error(format!("Unexpected character '{c}'"))),
+        };
+
+        Ok(Node { value, position })
+    }
+
+    /// Parse an array or an object, unless it is nested too deeply
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value, SceneError>,
+    ) -> Result<Value, SceneError> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(self.error(format!(
+                "Arrays and objects can't be nested more than {MAX_NESTING_DEPTH} levels deep"
+            )));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<Value, SceneError> {
+        self.expect('{')?;
+        let mut members = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.advance();
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let position = self.position;
+            if self.peek() != Some('"') {
+                return Err(self.error("Expected a string as object key"));
+            }
+            let name = self.parse_string()?;
+            if members
+                .iter()
+                .any(|(key, _): &(Key, Node)| key.name == name)
+            {
+                return Err(SceneError::new(
+                    format!("Duplicate key \"{name}\""),
+                    position,
+                ));
+            }
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            members.push((Key { name, position }, value));
+
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(members)),
+                _ => return Err(self.error_before("Expected ',' or '}' after object member")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, SceneError> {
+        self.expect('[')?;
+        let mut elements = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.advance();
+            return Ok(Value::Array(elements));
+        }
+
+        loop {
+            elements.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(elements)),
+                _ => return Err(self.error_before("Expected ',' or ']' after array element")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, SceneError> {
+        self.expect('"')?;
+        let mut result = String::new();
+
+        loop {
+            match self.advance() {
+                None | Some('\n') => return Err(self.error_before("Unterminated string")),
+                Some('"') => return Ok(result),
+                Some('\\') => {
+                    let escaped = match self.advance() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error_before("Invalid escape sequence")),
+                    };
+                    result.push(escaped);
+                }
+                Some(c) => result.push(c),
+            }
+        }
+    }
+
+    /// Character escaped as `\uXXXX`, or as a surrogate pair `\uXXXX\uXXXX` for
+    /// characters outside of the basic multilingual plane
+    fn parse_unicode_escape(&mut self) -> Result<char, SceneError> {
+        let mut code = self.parse_code_unit()?;
+        if (0xd800..0xdc00).contains(&code) && self.chars[self.index..].starts_with(&['\\', 'u']) {
+            self.advance();
+            self.advance();
+            let low_surrogate = self.parse_code_unit()?;
+            if !(0xdc00..0xe000).contains(&low_surrogate) {
+                return Err(self.error_before("Invalid unicode surrogate pair"));
+            }
+            code = 0x10000 + ((code - 0xd800) << 10) + (low_surrogate - 0xdc00);
+        }
+        char::from_u32(code).ok_or_else(|| self.error_before("Invalid unicode code point"))
+    }
+
+    /// UTF-16 code unit made of the 4 hexadecimal digits of a unicode escape
+    fn parse_code_unit(&mut self) -> Result<u32, SceneError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .advance()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error_before("Invalid unicode escape sequence"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Value, SceneError> {
+        let start = self.index;
+        let position = self.position;
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        let literal: String = self.chars[start..self.index].iter().collect();
+        if !is_json_number(&literal) {
+            return Err(SceneError::new(
+                format!("Invalid number \"{literal}\""),
+                position,
+            ));
+        }
+        // `parse` rounds correctly, so numbers written with enough digits
+        // are read back exactly
+        let number = literal
+            .parse()
+            .map_err(|_| SceneError::new(format!("Invalid number \"{literal}\""), position))?;
+        Ok(Value::Number(number))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Value) -> Result<Value, SceneError> {
+        let position = self.position;
+        for expected in literal.chars() {
+            if self.advance() != Some(expected) {
+                return Err(SceneError::new(
+                    format!("Invalid literal, expected \"{literal}\""),
+                    position,
+                ));
+            }
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.advance();
+                }
+                Some('/') if self.chars.get(self.index + 1) == Some(&'/') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.advance();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SceneError> {
+        if self.peek() == Some(expected) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{expected}'")))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Error at the current position
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        SceneError::new(message, self.position)
+    }
+
+    /// Error at the character that was just consumed
+    fn error_before(&self, message: impl Into<String>) -> SceneError {
+        let position = Position {
+            column: self.position.column.saturating_sub(1).max(1),
+            ..self.position
+        };
+        SceneError::new(message, position)
+    }
+}
+
+/// Whether `literal` follows the JSON grammar for numbers,
+/// ie. `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+fn is_json_number(literal: &str) -> bool {
+    let bytes = literal.as_bytes();
+    let mut i = 0;
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    };
+
+    if bytes.get(i) == Some(&b'-') {
+        i += 1;
+    }
+    let integer_start = i;
+    let integer_digits = digits(&mut i);
+    if integer_digits == 0 || (integer_digits > 1 && bytes[integer_start] == b'0') {
+        return false;
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        if digits(&mut i) == 0 {
+            return false;
+        }
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        if digits(&mut i) == 0 {
+            return false;
+        }
+    }
+    i == bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn value(source: &str) -> Value {
+        parse(source).unwrap().value
+    }
+
+    fn error_position(source: &str) -> (usize, usize) {
+        let error = parse(source).unwrap_err();
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn parses_scalars() {
+        assert_eq!(Value::Null, value("null"));
+        assert_eq!(Value::Bool(true), value("true"));
+        assert_eq!(Value::Bool(false), value(" false "));
+        assert_eq!(Value::Number(-1.5e3), value("-1.5e3"));
+        assert_eq!(
+            Value::String("a\"b\n\u{e9}".to_string()),
+            value(r#""a\"b\né""#)
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs_are_combined() {
+        assert_eq!(
+            Value::String("\u{1f600}".to_string()),
+            value(r#""\ud83d\ude00""#)
+        );
+        assert!(parse(r#""\ud83d""#).is_err());
+        assert!(parse(r#""\ud83d\u0041""#).is_err());
+        assert!(parse(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn numbers_are_parsed_exactly() {
+        let number = 0.1 + 0.2;
+
+        assert_eq!(Value::Number(number), value(&format!("{number:?}")));
+    }
+
+    #[test]
+    fn parses_nested_arrays_and_objects() {
+        let node = parse("{\"a\": [1, {\"b\": null}], \"c\": {}}").unwrap();
+
+        let Value::Object(members) = node.value else {
+            panic!("Expected object")
+        };
+        assert_eq!(2, members.len());
+        assert_eq!("a", members[0].0.name);
+        assert_eq!("c", members[1].0.name);
+        let Value::Array(elements) = &members[0].1.value else {
+            panic!("Expected array")
+        };
+        assert_eq!(Value::Number(1.0), elements[0].value);
+    }
+
+    #[test]
+    fn tracks_position_of_values() {
+        let node = parse("{\n  \"a\": [1,\n    2]\n}").unwrap();
+
+        let Value::Object(members) = node.value else {
+            panic!("Expected object")
+        };
+        let (key, array) = &members[0];
+        assert_eq!(Position { line: 2, column: 3 }, key.position);
+        assert_eq!(Position { line: 2, column: 8 }, array.position);
+        let Value::Array(elements) = &array.value else {
+            panic!("Expected array")
+        };
+        assert_eq!(Position { line: 3, column: 5 }, elements[1].position);
+    }
+
+    #[test]
+    fn line_comments_are_ignored() {
+        assert_eq!(
+            Value::Array(vec![]),
+            value("// a comment\n[ // another comment\n]")
+        );
+    }
+
+    #[test]
+    fn errors_report_line_and_column() {
+        assert_eq!((2, 8), error_position("{\n  \"a\": tru\n}"));
+        assert_eq!((1, 7), error_position("[1, 2 3]"));
+        assert_eq!((1, 2), error_position("[01]"));
+        assert_eq!((1, 4), error_position("[1,"));
+        assert_eq!((3, 1), error_position("{\"a\": 1,\n\n\"a\": 2}"));
+    }
+
+    #[test]
+    fn rejects_trailing_content() {
+        assert_eq!((1, 4), error_position("[] []"));
+    }
+
+    #[test]
+    fn rejects_values_nested_too_deeply() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            (1, MAX_NESTING_DEPTH + 1),
+            error_position(&nested(MAX_NESTING_DEPTH + 1))
+        );
+        // would overflow the stack without a limit
+        assert!(parse(&"[{\"a\": ".repeat(100_000)).is_err());
+    }
+}
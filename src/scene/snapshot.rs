@@ -0,0 +1,306 @@
+//! Serialization of a scene to the scene description format.
+//!
+//! Every number is written with the shortest representation that reads back
+//! to the same `f64`, and transformations are written as plain matrices,
+//! so parsing a snapshot gives back exactly the same scene.
+
+use std::fmt::Write;
+
+use crate::background::Background;
+use crate::brdf::BrdfModel;
+use crate::color::Color;
+use crate::integrator::Integrator;
+use crate::light::Light;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::object::Object;
+use crate::pattern::Pattern;
+use crate::point::Point;
+use crate::scene::{Scene, SnapshotError};
+use crate::shape::Shape;
+
+/// Value of the JSON document being written
+enum Json {
+    Integer(usize),
+    Number(f64),
+    String(&'static str),
+    /// Arbitrary string, escaped when written
+    Text(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+pub fn write(scene: &Scene) -> Result<String, SnapshotError> {
+    let mut output = String::new();
+    scene_json(scene)?.write(&mut output, 0)?;
+    output.push('\n');
+    Ok(output)
+}
+
+fn scene_json(scene: &Scene) -> Result<Json, SnapshotError> {
+    let camera = &scene.camera;
+    let (shutter_open, shutter_close) = camera.shutter();
+
+    Ok(Json::Object(vec![
+        (
+            "camera",
+            Json::Object(vec![
+                ("width", Json::Integer(camera.hsize())),
+                ("height", Json::Integer(camera.vsize())),
+                ("field_of_view", Json::Number(camera.field_of_view())),
+                ("transform", transform(camera.transform())),
+                (
+                    "shutter",
+                    Json::Array(vec![
+                        Json::Number(shutter_open),
+                        Json::Number(shutter_close),
+                    ]),
+                ),
+                (
+                    "samples_per_pixel",
+                    Json::Integer(camera.samples_per_pixel()),
+                ),
+                ("integrator", integrator(camera.integrator())),
+                ("seed", Json::Integer(camera.seed() as usize)),
+            ]),
+        ),
+        (
+            "lights",
+            Json::Array(scene.world.lights.iter().map(light).collect()),
+        ),
+        (
+            "objects",
+            Json::Array(scene.world.objects.iter().map(object).collect()),
+        ),
+        ("background", background(&scene.world.background)?),
+    ]))
+}
+
+fn background(background: &Background) -> Result<Json, SnapshotError> {
+    Ok(match background {
+        Background::Constant(constant) => color(*constant),
+        Background::Gradient { bottom, top } => Json::Object(vec![
+            ("type", Json::String("gradient")),
+            ("bottom", color(*bottom)),
+            ("top", color(*top)),
+        ]),
+        Background::Sky(sky) => Json::Object(vec![
+            ("type", Json::String("sky")),
+            (
+                "sun",
+                Json::Array(vec![
+                    Json::Number(sky.sun_direction.x),
+                    Json::Number(sky.sun_direction.y),
+                    Json::Number(sky.sun_direction.z),
+                ]),
+            ),
+            ("turbidity", Json::Number(sky.turbidity)),
+            ("intensity", Json::Number(sky.intensity)),
+        ]),
+        Background::Image(environment_map) => {
+            let path = environment_map
+                .path()
+                .ok_or(SnapshotError::BackgroundImageWithoutPath)?;
+            let path = path.to_str().ok_or_else(|| SnapshotError::NonUtf8Path {
+                path: path.to_path_buf(),
+            })?;
+            Json::Object(vec![
+                ("type", Json::String("image")),
+                ("path", Json::Text(path.to_string())),
+            ])
+        }
+    })
+}
+
+fn integrator(integrator: Integrator) -> Json {
+    Json::String(match integrator {
+        Integrator::Whitted => "whitted",
+        Integrator::PathTracing => "path_tracing",
+    })
+}
+
+fn light(light: &Light) -> Json {
+    Json::Object(vec![
+        ("position", point(light.position)),
+        ("intensity", color(light.intensity)),
+    ])
+}
+
+fn object(object: &Object) -> Json {
+    let shape = match object.shape {
+        Shape::Sphere => "sphere",
+        Shape::Plane => "plane",
+    };
+
+    let mut members = vec![
+        ("shape", Json::String(shape)),
+        ("transform", transform(object.transformation())),
+    ];
+    if let Some(end_transformation) = object.end_transformation() {
+        members.push(("end_transform", transform(end_transformation)));
+    }
+    members.push(("material", material(&object.material)));
+
+    Json::Object(members)
+}
+
+fn material(material: &Material) -> Json {
+    let pattern = match material.pattern {
+        Pattern::Solid(solid_color) => ("color", color(solid_color)),
+        Pattern::Stripe(stripe) => (
+            "pattern",
+            Json::Object(vec![
+                ("type", Json::String("stripe")),
+                (
+                    "colors",
+                    Json::Array(vec![
+                        color(stripe.first_stripe()),
+                        color(stripe.second_stripe()),
+                    ]),
+                ),
+                ("transform", transform(stripe.transformation())),
+            ]),
+        ),
+    };
+
+    Json::Object(vec![
+        pattern,
+        ("ambient", Json::Number(material.ambient)),
+        ("diffuse", Json::Number(material.diffuse)),
+        ("specular", Json::Number(material.specular)),
+        ("shininess", Json::Number(material.shininess)),
+        ("emission", color(material.emission)),
+        ("brdf", brdf(material.brdf)),
+    ])
+}
+
+fn brdf(brdf: BrdfModel) -> Json {
+    let with_roughness = |name, roughness| {
+        Json::Object(vec![
+            ("type", Json::String(name)),
+            ("roughness", Json::Number(roughness)),
+        ])
+    };
+
+    match brdf {
+        BrdfModel::Lambert => Json::String("lambert"),
+        BrdfModel::Phong => Json::String("phong"),
+        BrdfModel::BlinnPhong => Json::String("blinn_phong"),
+        BrdfModel::OrenNayar { roughness } => with_roughness("oren_nayar", roughness),
+        BrdfModel::Ggx { roughness } => with_roughness("ggx", roughness),
+        BrdfModel::MetallicRoughness {
+            metallic,
+            roughness,
+            ior,
+        } => Json::Object(vec![
+            ("type", Json::String("metallic_roughness")),
+            ("metallic", Json::Number(metallic)),
+            ("roughness", Json::Number(roughness)),
+            ("ior", Json::Number(ior)),
+        ]),
+    }
+}
+
+/// Chain made of a single `matrix` operation
+fn transform(matrix: Matrix<4, 4>) -> Json {
+    let mut operation = vec![Json::String("matrix")];
+    for r in 0..4 {
+        operation.push(Json::Array(
+            (0..4).map(|c| Json::Number(matrix[(r, c)])).collect(),
+        ));
+    }
+    Json::Array(vec![Json::Array(operation)])
+}
+
+fn point(point: Point) -> Json {
+    Json::Array(vec![
+        Json::Number(point.x),
+        Json::Number(point.y),
+        Json::Number(point.z),
+    ])
+}
+
+fn color(color: Color) -> Json {
+    Json::Array(vec![
+        Json::Number(color.red),
+        Json::Number(color.green),
+        Json::Number(color.blue),
+    ])
+}
+
+impl Json {
+    const INDENT: &'static str = "  ";
+
+    /// Write the value, with objects and arrays of non-scalar values spread
+    /// over several lines to keep snapshots easy to read and diff
+    fn write(&self, output: &mut String, depth: usize) -> Result<(), SnapshotError> {
+        match self {
+            Json::Integer(integer) => write!(output, "{integer}").unwrap(),
+            Json::Number(number) => {
+                if !number.is_finite() {
+                    return Err(SnapshotError::NotFinite { value: *number });
+                }
+                // `Debug` gives the shortest representation that reads back exactly
+                write!(output, "{number:?}").unwrap();
+            }
+            Json::String(string) => write!(output, "\"{string}\"").unwrap(),
+            Json::Text(text) => {
+                output.push('"');
+                for c in text.chars() {
+                    match c {
+                        '"' => output.push_str("\\\""),
+                        '\\' => output.push_str("\\\\"),
+                        c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+                        c => output.push(c),
+                    }
+                }
+                output.push('"');
+            }
+            Json::Array(elements) if elements.iter().all(Json::is_scalar) => {
+                output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    element.write(output, depth)?;
+                }
+                output.push(']');
+            }
+            Json::Array(elements) => {
+                output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    Self::new_line(output, depth + 1, i > 0);
+                    element.write(output, depth + 1)?;
+                }
+                Self::new_line(output, depth, false);
+                output.push(']');
+            }
+            Json::Object(members) => {
+                output.push('{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    Self::new_line(output, depth + 1, i > 0);
+                    write!(output, "\"{key}\": ").unwrap();
+                    value.write(output, depth + 1)?;
+                }
+                Self::new_line(output, depth, false);
+                output.push('}');
+            }
+        }
+        Ok(())
+    }
+
+    fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            Json::Integer(_) | Json::Number(_) | Json::String(_) | Json::Text(_)
+        )
+    }
+
+    fn new_line(output: &mut String, depth: usize, after_element: bool) {
+        if after_element {
+            output.push(',');
+        }
+        output.push('\n');
+        output.push_str(&Self::INDENT.repeat(depth));
+    }
+}
@@ -1,7 +1,9 @@
+use crate::float::Float;
 use crate::intersections::Intersections;
 use crate::object::Object;
 use crate::point::Point;
 use crate::ray::Ray;
+use crate::ray_packet::{Coordinates, Lanes};
 use crate::vector::Vector;
 
 pub mod plane;
@@ -14,17 +16,36 @@ pub enum Shape {
 }
 
 impl Shape {
-    pub fn object_normal_at(self, object_point: Point) -> Vector {
+    pub fn object_normal_at<T: Float>(self, object_point: Point<T>) -> Vector<T> {
         match self {
             Shape::Sphere => sphere::object_normal_at(object_point),
             Shape::Plane => plane::object_normal_at(object_point),
         }
     }
 
-    pub fn object_intersect_at(self, object: Object, transformed_ray: Ray) -> Intersections {
+    pub fn object_intersect_at<T: Float>(
+        self,
+        object: &Object<T>,
+        transformed_ray: Ray<T>,
+    ) -> Intersections<'_, T> {
         match object.shape {
             Shape::Sphere => sphere::object_intersect_at(object, transformed_ray),
             Shape::Plane => plane::object_intersect_at(object, transformed_ray),
         }
     }
+
+    /// Smallest t with `t_min <= t < t_max` at which each object-space ray of a
+    /// packet intersects the shape, or infinity if it misses it (see `RayPacket`)
+    pub fn packet_intersect_at<T: Float>(
+        self,
+        origin: Coordinates<T>,
+        direction: Coordinates<T>,
+        t_min: Lanes<T>,
+        t_max: Lanes<T>,
+    ) -> Lanes<T> {
+        match self {
+            Shape::Sphere => sphere::packet_intersect_at(origin, direction, t_min, t_max),
+            Shape::Plane => plane::packet_intersect_at(origin, direction, t_min, t_max),
+        }
+    }
 }
@@ -1,27 +1,50 @@
+use crate::float::Float;
 use crate::float_eq::FloatEq;
 use crate::intersection::Intersection;
 use crate::intersections::Intersections;
 use crate::object::Object;
 use crate::point::Point;
 use crate::ray::Ray;
+use crate::ray_packet::{Coordinates, Lanes, SimdLanes};
 use crate::vector::Vector;
 
-pub fn object_normal_at(_object_point: Point) -> Vector {
+pub fn object_normal_at<T: Float>(_object_point: Point<T>) -> Vector<T> {
     // The plane will extend in both x and z dimentions
     // so the normal will always be x=0, y=1, z=0
-    Vector::new(0.0, 1.0, 0.0)
+    Vector::new(T::ZERO, T::ONE, T::ZERO)
 }
 
-pub fn object_intersect_at(object: Object, ray: Ray) -> Intersections {
-    if ray.direction.y.float_eq(0.0) {
+pub fn object_intersect_at<T: Float>(object: &Object<T>, ray: Ray<T>) -> Intersections<'_, T> {
+    if ray.direction.y.float_eq(T::ZERO) {
         return Intersections::empty();
     }
 
     let t = (-ray.origin.y) / (ray.direction.y);
+    if !ray.in_interval(t) {
+        return Intersections::empty();
+    }
 
     Intersections::of(&[Intersection::new(t, object)])
 }
 
+/// Intersection in the interval of each object-space ray of a packet with the
+/// plane, or infinity (see `object_intersect_at`)
+pub fn packet_intersect_at<T: Float>(
+    origin: Coordinates<T>,
+    direction: Coordinates<T>,
+    t_min: Lanes<T>,
+    t_max: Lanes<T>,
+) -> Lanes<T> {
+    let t = -origin.y / direction.y;
+
+    let parallel = direction
+        .y
+        .abs()
+        .simd_lt(Lanes::<T>::splat(T::ERROR_MARGIN));
+    let hit = !parallel & t.simd_ge(t_min) & t.simd_lt(t_max);
+    Lanes::<T>::select(hit, t, Lanes::<T>::splat(T::INFINITY))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,9 +67,10 @@ mod tests {
 
     #[test]
     fn ray_parallel_to_plane_wont_intersect_it() {
+        let plane = Object::default();
         let ray = Ray::new(Point::new(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 1.0));
 
-        assert_eq!(0, object_intersect_at(Object::default(), ray).count())
+        assert_eq!(0, object_intersect_at(&plane, ray).count())
     }
 
     #[test]
@@ -56,30 +80,51 @@ mod tests {
         // however from the raytracer perspective it won't intersect it at all since the
         // plane is infinitely thin and wouldn't be visible when viewed like this.
 
+        let plane = Object::default();
         let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
 
-        assert_eq!(0, object_intersect_at(Object::default(), ray).count())
+        assert_eq!(0, object_intersect_at(&plane, ray).count())
     }
 
     #[test]
     fn ray_intersecting_plane_from_above() {
+        let plane = Object::default();
         let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
 
-        let intersections = object_intersect_at(Object::default(), ray);
+        let intersections = object_intersect_at(&plane, ray);
 
         assert_eq!(1, intersections.count());
         assert_eq!(1.0, intersections[0].t);
-        assert_eq!(Object::default(), intersections[0].object)
+        assert_eq!(&plane, intersections[0].object)
     }
 
     #[test]
     fn ray_intersecting_plane_from_below() {
+        let plane = Object::default();
         let ray = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
 
-        let intersections = object_intersect_at(Object::default(), ray);
+        let intersections = object_intersect_at(&plane, ray);
 
         assert_eq!(1, intersections.count());
         assert_eq!(1.0, intersections[0].t);
-        assert_eq!(Object::default(), intersections[0].object)
+        assert_eq!(&plane, intersections[0].object)
+    }
+
+    #[test]
+    fn ray_misses_plane_outside_its_interval() {
+        let plane = Object::default();
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        assert_eq!(
+            0,
+            object_intersect_at(&plane, ray.with_interval(0.0, 1.0)).count()
+        );
+        assert_eq!(
+            0,
+            object_intersect_at(&plane, ray.with_interval(1.5, 2.0)).count()
+        );
+        // the plane is behind the ray
+        let away = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(0, object_intersect_at(&plane, away).count());
     }
 }
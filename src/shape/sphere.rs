@@ -1,40 +1,78 @@
+use crate::float::Float;
 use crate::intersection::Intersection;
 use crate::intersections::Intersections;
 use crate::object::Object;
 use crate::point::Point;
 use crate::ray::Ray;
+use crate::ray_packet::{Coordinates, Lanes, SimdLanes};
 use crate::vector::Vector;
 
 /// Normal of sphere at the given point in the object-space.
-pub fn object_normal_at(object_point: Point) -> Vector {
+pub fn object_normal_at<T: Float>(object_point: Point<T>) -> Vector<T> {
     (object_point - Point::origin()).normalize()
 }
 
 /// Intersections of object-space ray with sphere.
 ///
-/// Returns the values of t at which the ray will intersect a sphere.
-/// Returns None if no intersection exists.
-/// For single intersections (ie. tangent lines), it will return the same t two times.
+/// Returns the values of t in the interval of the ray at which it will intersect
+/// a sphere. For single intersections (ie. tangent lines), it will return the same
+/// t two times.
 ///
 /// See https://en.wikipedia.org/wiki/Line%E2%80%93sphere_intersection
-pub fn object_intersect_at(sphere: Object, object_ray: Ray) -> Intersections {
+pub fn object_intersect_at<T: Float>(
+    sphere: &Object<T>,
+    object_ray: Ray<T>,
+) -> Intersections<'_, T> {
     let sphere_center = Point::origin();
     let sphere_center_to_ray = object_ray.origin - sphere_center;
+    let (two, four) = (T::from_f64(2.0), T::from_f64(4.0));
 
     let a = object_ray.direction.dot(object_ray.direction);
-    let b = 2.0 * object_ray.direction.dot(sphere_center_to_ray);
-    let c = sphere_center_to_ray.dot(sphere_center_to_ray) - 1.0;
+    let b = two * object_ray.direction.dot(sphere_center_to_ray);
+    let c = sphere_center_to_ray.dot(sphere_center_to_ray) - T::ONE;
 
-    let discriminant = b.powf(2.0) - 4.0 * a * c;
+    let discriminant = b.powi(2) - four * a * c;
 
-    if discriminant < 0.0 {
+    if discriminant < T::ZERO {
         return Intersections::empty();
     };
 
-    let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
-    let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+    let t1 = (-b - discriminant.sqrt()) / (two * a);
+    let t2 = (-b + discriminant.sqrt()) / (two * a);
 
-    Intersections::of(&[Intersection::new(t1, sphere), Intersection::new(t2, sphere)])
+    let mut intersections = Intersections::empty();
+    for t in [t1, t2] {
+        if object_ray.in_interval(t) {
+            intersections.push(Intersection::new(t, sphere));
+        }
+    }
+    intersections
+}
+
+/// Nearest intersection in the interval of each object-space ray of a packet
+/// with the sphere, or infinity (see `object_intersect_at`)
+pub fn packet_intersect_at<T: Float>(
+    origin: Coordinates<T>,
+    direction: Coordinates<T>,
+    t_min: Lanes<T>,
+    t_max: Lanes<T>,
+) -> Lanes<T> {
+    let splat = Lanes::<T>::splat;
+    let (two, four) = (splat(T::from_f64(2.0)), splat(T::from_f64(4.0)));
+
+    // the center of the sphere is the origin
+    let a = direction.dot(direction);
+    let b = two * direction.dot(origin);
+    let c = origin.dot(origin) - splat(T::ONE);
+
+    let discriminant = b * b - four * a * c;
+    let root = discriminant.sqrt();
+    let t1 = (-b - root) / (two * a);
+    let t2 = (-b + root) / (two * a);
+
+    let t = Lanes::<T>::select(t1.simd_ge(t_min), t1, t2);
+    let hit = discriminant.simd_ge(splat(T::ZERO)) & t.simd_ge(t_min) & t.simd_lt(t_max);
+    Lanes::<T>::select(hit, t, splat(T::INFINITY))
 }
 
 #[cfg(test)]
@@ -79,7 +117,7 @@ mod tests {
         let sphere = Object::default();
 
         // t values at which the ray intersects the sphere
-        let intersections = object_intersect_at(sphere, ray);
+        let intersections = object_intersect_at(&sphere, ray);
 
         assert_eq!(2, intersections.count());
         assert_eq!(4.0, intersections[0].t);
@@ -91,17 +129,18 @@ mod tests {
         let ray = Ray::new(Point::new(0.0, 2.0, 5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Object::default();
 
-        let intersect_ts = object_intersect_at(sphere, ray);
+        let intersect_ts = object_intersect_at(&sphere, ray);
 
         assert_eq!(Intersections::empty(), intersect_ts)
     }
 
     #[test]
     fn a_ray_originating_inside_the_sphere_intersects_the_sphere_in_two_points() {
-        let ray = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0))
+            .with_interval(f64::NEG_INFINITY, f64::INFINITY);
         let sphere = Object::default();
 
-        let intersections = ray.intersect(sphere);
+        let intersections = ray.intersect(&sphere);
 
         assert_eq!(2, intersections.count());
         assert_eq!(-1.0, intersections[0].t);
@@ -110,24 +149,42 @@ mod tests {
 
     #[test]
     fn a_ray_can_intersect_a_sphere_behind_it_two_times() {
-        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0))
+            .with_interval(f64::NEG_INFINITY, f64::INFINITY);
         let sphere = Object::default();
 
-        let intersections = object_intersect_at(sphere, ray);
+        let intersections = object_intersect_at(&sphere, ray);
 
         assert_eq!(2, intersections.count());
         assert_eq!(-6.0, intersections[0].t);
         assert_eq!(-4.0, intersections[1].t);
     }
 
+    #[test]
+    fn intersections_outside_the_interval_of_the_ray_are_left_out() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let sphere = Object::default();
+
+        let intersections = object_intersect_at(&sphere, ray.with_interval(5.0, 10.0));
+        assert_eq!(1, intersections.count());
+        assert_eq!(6.0, intersections[0].t);
+
+        let intersections = object_intersect_at(&sphere, ray.with_interval(0.0, 6.0));
+        assert_eq!(1, intersections.count());
+        assert_eq!(4.0, intersections[0].t);
+
+        let inside = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(1, object_intersect_at(&sphere, inside).count());
+    }
+
     #[test]
     fn intersect_sets_the_object_of_the_intersection() {
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Object::default();
 
-        let intersections = object_intersect_at(sphere, ray);
+        let intersections = object_intersect_at(&sphere, ray);
 
-        assert_eq!(sphere, intersections[0].object);
-        assert_eq!(sphere, intersections[1].object);
+        assert_eq!(&sphere, intersections[0].object);
+        assert_eq!(&sphere, intersections[1].object);
     }
 }
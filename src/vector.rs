@@ -1,23 +1,24 @@
 use std::ops;
 
+use crate::float::Float;
 use crate::float_eq::FloatEq;
 
 #[derive(Copy, Clone, Debug)]
-pub struct Vector {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+pub struct Vector<T: Float = f64> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
-impl Vector {
-    pub const W: f64 = 0.0;
+impl<T: Float> Vector<T> {
+    pub const W: T = T::ZERO;
 
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: T, y: T, z: T) -> Self {
         Self { x, y, z }
     }
 
     /// Length of the vector
-    pub fn magnitude(self) -> f64 {
+    pub fn magnitude(self) -> T {
         (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
     }
 
@@ -34,7 +35,7 @@ impl Vector {
     /// If two unit vectors have same dot product, they are the same
     /// If dot product is -1, vectors point in opposite directions
     /// Dot product of two unit vectors is the cosine of the angle between them
-    pub fn dot(self, other: Self) -> f64 {
+    pub fn dot(self, other: Self) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
@@ -48,53 +49,62 @@ impl Vector {
         )
     }
 
-    pub fn reflect(self, normal: Vector) -> Vector {
-        self - normal * 2.0 * self.dot(normal)
+    pub fn reflect(self, normal: Vector<T>) -> Vector<T> {
+        self - normal * (T::ONE + T::ONE) * self.dot(normal)
+    }
+
+    /// Same vector in precision `U`
+    pub fn cast<U: Float>(self) -> Vector<U> {
+        Vector::new(
+            U::from_f64(self.x.to_f64()),
+            U::from_f64(self.y.to_f64()),
+            U::from_f64(self.z.to_f64()),
+        )
     }
 }
 
-impl PartialEq for Vector {
+impl<T: Float> PartialEq for Vector<T> {
     fn eq(&self, other: &Self) -> bool {
         self.x.float_eq(other.x) && self.y.float_eq(other.y) && self.z.float_eq(other.z)
     }
 }
 
-impl ops::Add<Vector> for Vector {
-    type Output = Vector;
+impl<T: Float> ops::Add<Vector<T>> for Vector<T> {
+    type Output = Vector<T>;
 
-    fn add(self, other: Vector) -> Self::Output {
+    fn add(self, other: Vector<T>) -> Self::Output {
         Vector::new(self.x + other.x, self.y + other.y, self.z + other.z)
     }
 }
 
-impl ops::Sub<Vector> for Vector {
-    type Output = Vector;
+impl<T: Float> ops::Sub<Vector<T>> for Vector<T> {
+    type Output = Vector<T>;
 
-    fn sub(self, other: Vector) -> Self::Output {
+    fn sub(self, other: Vector<T>) -> Self::Output {
         Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
     }
 }
 
-impl ops::Neg for Vector {
-    type Output = Vector;
+impl<T: Float> ops::Neg for Vector<T> {
+    type Output = Vector<T>;
 
     fn neg(self) -> Self::Output {
         Vector::new(-self.x, -self.y, -self.z)
     }
 }
 
-impl ops::Mul<f64> for Vector {
-    type Output = Vector;
+impl<T: Float> ops::Mul<T> for Vector<T> {
+    type Output = Vector<T>;
 
-    fn mul(self, factor: f64) -> Self::Output {
+    fn mul(self, factor: T) -> Self::Output {
         Vector::new(self.x * factor, self.y * factor, self.z * factor)
     }
 }
 
-impl ops::Div<f64> for Vector {
-    type Output = Vector;
+impl<T: Float> ops::Div<T> for Vector<T> {
+    type Output = Vector<T>;
 
-    fn div(self, factor: f64) -> Self::Output {
+    fn div(self, factor: T) -> Self::Output {
         Vector::new(self.x / factor, self.y / factor, self.z / factor)
     }
 }
@@ -1,17 +1,27 @@
+use crate::background::Background;
+use crate::brdf::{BrdfModel, Ggx, MetallicRoughness};
 use crate::color::Color;
+use crate::error::ValidationError;
+use crate::float::Float;
+use crate::intersection::Intersection;
 use crate::intersection_state::IntersectionState;
 use crate::light::Light;
 use crate::lighting::lighting;
 use crate::material::Material;
-use crate::matrix::transformations;
+use crate::matrix::{transformations, Matrix};
 use crate::object::Object;
 use crate::pattern::Pattern;
 use crate::point::Point;
-use crate::ray::Ray;
+use crate::ray::{self, Ray};
 
-pub struct World {
+/// Objects and lights of a scene, the geometry of the objects being in
+/// precision `T`
+#[derive(Clone)]
+pub struct World<T: Float = f64> {
     pub lights: Vec<Light>,
-    pub objects: Vec<Object>,
+    pub objects: Vec<Object<T>>,
+    /// Seen by rays missing every object
+    pub background: Background,
 }
 
 impl Default for World {
@@ -20,110 +30,230 @@ impl Default for World {
             Point::new(-10.0, 10.0, -10.0),
             Color::new(1.0, 1.0, 1.0),
         )];
-        let sphere_1 = Object {
-            material: Material {
+        let sphere_1 = Object::sphere(
+            Matrix::identity(),
+            Material {
                 pattern: Pattern::solid(Color::new(0.8, 1.0, 0.6)),
                 diffuse: 0.7,
                 specular: 0.2,
                 ..Default::default()
             },
-            ..Default::default()
-        };
+        );
 
-        let sphere_2 = Object {
-            transformation: transformations::scaling(0.5, 0.5, 0.5),
-            ..Default::default()
-        };
+        let sphere_2 = Object::sphere(transformations::scaling(0.5, 0.5, 0.5), Material::default());
 
         Self {
             lights,
             objects: vec![sphere_1, sphere_2],
+            background: Background::default(),
         }
     }
 }
 
 impl World {
-    pub fn new(lights: Vec<Light>, objects: Vec<Object>) -> Self {
-        Self { lights, objects }
+    /// Maximum number of successive reflections, eg. between two facing mirrors
+    pub const MAX_REFLECTION_DEPTH: usize = 5;
+}
+
+impl<T: Float> World<T> {
+    pub fn new(lights: Vec<Light>, objects: Vec<Object<T>>) -> Self {
+        Self {
+            lights,
+            objects,
+            background: Background::default(),
+        }
     }
 
-    pub fn color_at_intersection_with(&self, ray: Ray) -> Color {
-        let intersections = ray.intersect_world(self);
-        let hit = match intersections.hit() {
-            None => return Color::black(),
-            Some(value) => value,
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Same world, with the geometry of its objects in precision `U`
+    pub fn cast<U: Float>(&self) -> World<U> {
+        World {
+            lights: self.lights.clone(),
+            objects: self.objects.iter().map(Object::cast).collect(),
+            background: self.background.clone(),
+        }
+    }
+
+    /// Check every light and object, reporting the first invalid one
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (index, light) in self.lights.iter().enumerate() {
+            light.validate().map_err(|error| ValidationError::Light {
+                index,
+                error: Box::new(error),
+            })?;
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            object.validate().map_err(|error| ValidationError::Object {
+                index,
+                error: Box::new(error),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn color_at_intersection_with(&self, ray: Ray<T>) -> Color {
+        self.color_at(ray, World::MAX_REFLECTION_DEPTH)
+    }
+
+    /// Color seen along the ray, given its closest hit (eg. computed for a
+    /// whole `RayPacket`)
+    pub(crate) fn color_at_hit(&self, ray: Ray<T>, hit: Option<Intersection<T>>) -> Color {
+        self.color_of_hit(ray, hit, World::MAX_REFLECTION_DEPTH)
+    }
+
+    /// Color seen along the ray, following at most `remaining` reflections
+    fn color_at(&self, ray: Ray<T>, remaining: usize) -> Color {
+        self.color_of_hit(ray, ray.intersect_world(self).hit(), remaining)
+    }
+
+    fn color_of_hit(&self, ray: Ray<T>, hit: Option<Intersection<T>>, remaining: usize) -> Color {
+        let Some(hit) = hit else {
+            return self.background.color(ray.direction.cast());
         };
 
         let intersection_state = IntersectionState::prepare(hit, ray);
-        self.shade_hit(intersection_state)
+        self.shade_hit(intersection_state, remaining)
     }
 
-    fn shade_hit(&self, intersection_state: IntersectionState) -> Color {
-        let mut color = Color::black();
+    fn shade_hit(&self, intersection_state: IntersectionState<T>, remaining: usize) -> Color {
+        let mut color = intersection_state.object.material.emission
+            + self.reflected_color(&intersection_state, remaining);
 
         for light in self.lights.iter() {
             color += lighting(
-                intersection_state.object,
+                intersection_state.object.material,
+                intersection_state.surface_color,
                 *light,
                 intersection_state.point,
                 intersection_state.eye_v,
                 intersection_state.normal_v,
-                self.is_shadowed(*light, intersection_state.over_point),
+                self.is_shadowed(*light, intersection_state.point, intersection_state.time),
             );
         }
 
         color
     }
 
+    /// Light reflected in the mirror direction by physically based materials.
+    ///
+    /// Rough surfaces blur their reflections, which a single ray can't capture:
+    /// their reflections fade instead as the roughness increases.
+    /// The path tracing integrator samples rough reflections accurately.
+    fn reflected_color(
+        &self,
+        intersection_state: &IntersectionState<T>,
+        remaining: usize,
+    ) -> Color {
+        let object = intersection_state.object;
+        let BrdfModel::MetallicRoughness {
+            metallic,
+            roughness,
+            ior,
+        } = object.material.brdf
+        else {
+            return Color::black();
+        };
+        if remaining == 0 {
+            return Color::black();
+        }
+
+        let brdf =
+            MetallicRoughness::new(intersection_state.surface_color, metallic, roughness, ior);
+        let fresnel = Ggx::fresnel(
+            brdf.reflectance_at_normal(),
+            intersection_state.eye_v.dot(intersection_state.normal_v),
+        );
+        let glossiness = (1.0 - roughness).powi(2);
+
+        let reflected_ray = Ray::new(intersection_state.point, intersection_state.reflect_v)
+            .with_time(intersection_state.time)
+            .cast()
+            .with_interval(ray::epsilon_at(intersection_state.point), T::INFINITY);
+        self.color_at(reflected_ray, remaining - 1)
+            * fresnel
+            * (glossiness * object.material.specular)
+    }
+
+    /// Check if any object blocks the ray in its interval, without looking for
+    /// the closest one (see `Ray::intersects_any`)
+    pub fn is_occluded(&self, ray: Ray<T>) -> bool {
+        ray.intersects_any(self)
+    }
+
     /// Check if a point is shadowed.
     /// Creates a ray from the point to the light source, and checks
     /// if it intersects any object before reaching the light.
-    fn is_shadowed(&self, light: Light, point: Point) -> bool {
+    /// The surface the point lies on doesn't shadow it (see `ray::epsilon_at`).
+    /// The shadow ray is cast at the given `time`, to account for moving objects.
+    pub(crate) fn is_shadowed(&self, light: Light, point: Point, time: f64) -> bool {
         let shadow_v = light.position - point;
         let distance = shadow_v.magnitude();
         let direction = shadow_v.normalize();
 
-        let shadow_ray = Ray::new(point, direction);
-        let intersections = shadow_ray.intersect_world(self);
-        match intersections.hit() {
-            // intersection between point and light
-            Some(hit) if hit.t < distance => true,
-            // the object is on the other side of the light
-            Some(_hit) => false,
-            // no intersections
-            None => false,
-        }
+        // objects on the other side of the light don't cast shadows
+        let shadow_ray = Ray::new(point, direction)
+            .with_time(time)
+            .cast()
+            .with_interval(ray::epsilon_at(point), T::from_f64(distance));
+        self.is_occluded(shadow_ray)
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::f64::consts::FRAC_1_SQRT_2;
+
     use crate::intersection::Intersection;
+    use crate::matrix::Matrix;
     use crate::pattern::Pattern;
     use crate::vector::Vector;
 
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn validation_reports_invalid_lights_and_objects() {
+        assert_eq!(Ok(()), World::default().validate());
+
+        let mut world = World::default();
+        world.objects[1].material.ambient = f64::NAN;
+        assert_eq!(
+            Err(ValidationError::Object {
+                index: 1,
+                error: Box::new(ValidationError::NotFinite { field: "ambient" })
+            }),
+            world.validate()
+        );
+
+        let mut world = World::default();
+        world.lights[0].intensity = Color::new(-1.0, 1.0, 1.0);
+        assert_eq!(
+            "light 0: intensity must be at least 0, found -1",
+            world.validate().unwrap_err().to_string()
+        );
+    }
+
     use super::*;
 
     #[test]
     fn default_world_contains_two_spheres() {
         let expected_light = Light::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let expected_sphere_1 = Object {
-            material: Material {
+        let expected_sphere_1 = Object::sphere(
+            Matrix::identity(),
+            Material {
                 pattern: Pattern::solid(Color::new(0.8, 1.0, 0.6)),
                 diffuse: 0.7,
                 specular: 0.2,
                 ..Default::default()
             },
-            ..Default::default()
-        };
+        );
 
-        let expected_sphere_2 = Object {
-            transformation: transformations::scaling(0.5, 0.5, 0.5),
-            ..Default::default()
-        };
+        let expected_sphere_2 =
+            Object::sphere(transformations::scaling(0.5, 0.5, 0.5), Material::default());
 
         let default_world = World::default();
 
@@ -136,31 +266,33 @@ mod tests {
     fn shading_an_intersection_from_the_outside() {
         let world = World::default();
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let shape = world.objects.first().unwrap().clone();
-        let intersection = Intersection::new(4.0, shape);
+        let shape = *world.objects.first().unwrap();
+        let intersection = Intersection::new(4.0, &shape);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
-        let color = world.shade_hit(intersection_state);
+        let color = world.shade_hit(intersection_state, World::MAX_REFLECTION_DEPTH);
 
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), color);
     }
 
     #[test]
     fn shading_an_intersection_from_the_inside() {
-        let mut world = World::default();
-        world.lights = vec![Light::new(
-            Point::new(0.0, 0.25, 0.0),
-            Color::new(1.0, 1.0, 1.0),
-        )];
+        let world = World {
+            lights: vec![Light::new(
+                Point::new(0.0, 0.25, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            ..World::default()
+        };
 
         let ray = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
-        let shape = world.objects[1].clone();
-        let intersection = Intersection::new(0.5, shape);
+        let shape = world.objects[1];
+        let intersection = Intersection::new(0.5, &shape);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
-        let color = world.shade_hit(intersection_state);
+        let color = world.shade_hit(intersection_state, World::MAX_REFLECTION_DEPTH);
 
-        assert_eq!(Color::new(0.1, 0.1, 0.1), color);
+        assert_eq!(Color::new(0.90498, 0.90498, 0.90498), color);
     }
 
     #[test]
@@ -171,11 +303,11 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let shape = world.objects.first().unwrap().clone();
-        let intersection = Intersection::new(4.0, shape);
+        let shape = *world.objects.first().unwrap();
+        let intersection = Intersection::new(4.0, &shape);
 
         let intersection_state = IntersectionState::prepare(intersection, ray);
-        let color = world.shade_hit(intersection_state);
+        let color = world.shade_hit(intersection_state, World::MAX_REFLECTION_DEPTH);
 
         assert_eq!(Color::new(0.75092, 0.93865, 0.56319), color);
     }
@@ -184,16 +316,16 @@ mod tests {
     fn shading_an_intersection_in_the_shadow() {
         let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let sphere_1 = Object::default();
-        let sphere_2 = Object {
-            transformation: transformations::translation(0.0, 0.0, 10.0),
-            ..Object::default()
-        };
+        let sphere_2 = Object::sphere(
+            transformations::translation(0.0, 0.0, 10.0),
+            Material::default(),
+        );
         let world = World::new(vec![light], vec![sphere_1, sphere_2]);
         let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
-        let intersection = Intersection::new(4.0, sphere_2);
+        let intersection = Intersection::new(4.0, &sphere_2);
         let intersection_state = IntersectionState::prepare(intersection, ray);
 
-        let color = world.shade_hit(intersection_state);
+        let color = world.shade_hit(intersection_state, World::MAX_REFLECTION_DEPTH);
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), color);
     }
@@ -208,6 +340,35 @@ mod tests {
         assert_eq!(Color::black(), color)
     }
 
+    #[test]
+    fn ray_missing_everything_shows_background() {
+        let world = World::default().with_background(Background::Gradient {
+            bottom: Color::black(),
+            top: Color::white(),
+        });
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        let color = world.color_at_intersection_with(ray);
+
+        assert_eq!(Color::white(), color)
+    }
+
+    #[test]
+    fn background_is_seen_in_reflections() {
+        let sky = Color::new(0.2, 0.4, 0.8);
+        let floor = Object::plane(Matrix::identity(), mirror());
+        let world = World::new(vec![], vec![floor]).with_background(Background::Constant(sky));
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+
+        let color = world.color_at_intersection_with(ray);
+
+        // the Fresnel reflectance of a white metal is 1 at every angle
+        assert_eq!(sky, color)
+    }
+
     #[test]
     fn color_is_computed_appropriately_on_ray_hit() {
         let world = World::default();
@@ -226,11 +387,10 @@ mod tests {
         {
             let outer = world.objects.get_mut(0).unwrap();
             outer.material.ambient = 1.0;
-            let mut inner = world.objects.get_mut(1).unwrap();
+            let inner = world.objects.get_mut(1).unwrap();
             inner.material.pattern = Pattern::solid(inner_color);
             inner.material.ambient = 1.0;
         }
-        let inner = world.objects[1];
         let ray = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
 
         let color = world.color_at_intersection_with(ray);
@@ -238,12 +398,105 @@ mod tests {
         assert_eq!(inner_color, color)
     }
 
+    #[test]
+    fn emissive_objects_are_visible_without_lights() {
+        let emission = Color::new(2.0, 1.5, 0.5);
+        let mut world = World::default();
+        world.lights.clear();
+        world.objects[0].material.emission = emission;
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let color = world.color_at_intersection_with(ray);
+
+        assert_eq!(emission, color)
+    }
+
+    fn mirror() -> Material {
+        Material::metallic_roughness(Pattern::solid(Color::white()), 1.0, 0.0, 1.5)
+    }
+
+    #[test]
+    fn reflected_color_for_non_physically_based_material_is_black() {
+        let world = World::default();
+        let ray = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(1.0, &world.objects[1]);
+        let intersection_state = IntersectionState::prepare(intersection, ray);
+
+        let color = world.reflected_color(&intersection_state, World::MAX_REFLECTION_DEPTH);
+
+        assert_eq!(Color::black(), color);
+    }
+
+    #[test]
+    fn smooth_metal_reflects_the_world() {
+        let mut world = World::default();
+        let floor = Object::plane(transformations::translation(0.0, -1.0, 0.0), mirror());
+        world.objects.push(floor);
+        let ray = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let intersection = Intersection::new(2.0_f64.sqrt(), &floor);
+        let intersection_state = IntersectionState::prepare(intersection, ray);
+
+        let color = world.reflected_color(&intersection_state, World::MAX_REFLECTION_DEPTH);
+
+        // the reflected ray hits the outer sphere of the default world
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), color);
+        assert_eq!(
+            Color::black(),
+            world.reflected_color(&intersection_state, 0)
+        );
+    }
+
+    #[test]
+    fn rough_metal_reflections_fade() {
+        let mut world = World::default();
+        let floor = Object::plane(
+            transformations::translation(0.0, -1.0, 0.0),
+            Material::metallic_roughness(Pattern::solid(Color::white()), 1.0, 0.5, 1.5),
+        );
+        world.objects.push(floor);
+        let ray = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let intersection = Intersection::new(2.0_f64.sqrt(), &floor);
+        let intersection_state = IntersectionState::prepare(intersection, ray);
+
+        let color = world.reflected_color(&intersection_state, World::MAX_REFLECTION_DEPTH);
+
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855) * 0.25, color);
+    }
+
+    #[test]
+    fn mutually_reflective_surfaces_terminate() {
+        let light = Light::new(Point::origin(), Color::white());
+        let lower = Object::plane(transformations::translation(0.0, -1.0, 0.0), mirror());
+        let upper = Object::plane(transformations::translation(0.0, 1.0, 0.0), mirror());
+        let world = World::new(vec![light], vec![lower, upper]);
+        let ray = Ray::new(Point::origin(), Vector::new(0.0, 1.0, 0.0));
+
+        // would overflow the stack without a limit on the number of reflections
+        world.color_at_intersection_with(ray);
+    }
+
+    #[test]
+    fn ray_is_occluded_by_objects_within_the_interval() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(world.is_occluded(ray.with_interval(0.0, 4.5)));
+        assert!(!world.is_occluded(ray.with_interval(0.0, 3.0)));
+        assert!(!world.is_occluded(ray.with_time(1.0).with_interval(7.0, 100.0)));
+    }
+
     #[test]
     fn point_is_not_in_shadow_when_nothing_is_collinear_between_point_and_light() {
         let world = World::default();
         let point = Point::new(0.0, 10.0, 0.0);
 
-        assert_eq!(false, world.is_shadowed(world.lights[0], point))
+        assert_eq!(false, world.is_shadowed(world.lights[0], point, 0.0))
     }
 
     #[test]
@@ -251,7 +504,7 @@ mod tests {
         let world = World::default();
         let point = Point::new(10.0, -10.0, 10.0);
 
-        assert!(world.is_shadowed(world.lights[0], point))
+        assert!(world.is_shadowed(world.lights[0], point, 0.0))
     }
 
     #[test]
@@ -259,7 +512,62 @@ mod tests {
         let world = World::default();
         let point = Point::new(-20.0, 20.0, -20.0);
 
-        assert_eq!(false, world.is_shadowed(world.lights[0], point))
+        assert_eq!(false, world.is_shadowed(world.lights[0], point, 0.0))
+    }
+
+    #[test]
+    fn moving_object_casts_shadow_only_while_it_is_between_point_and_light() {
+        let light = Light::new(Point::new(0.0, 10.0, 0.0), Color::white());
+        let sphere = Object::default().with_motion(transformations::translation(5.0, 0.0, 0.0));
+        let world = World::new(vec![light], vec![sphere]);
+        let point = Point::new(0.0, -5.0, 0.0);
+
+        assert!(world.is_shadowed(light, point, 0.0));
+        assert!(!world.is_shadowed(light, point, 1.0));
+    }
+
+    #[test]
+    fn surface_does_not_shadow_its_own_points() {
+        let light = Light::new(Point::new(0.0, 0.0, -10.0), Color::white());
+        let sphere = Object::sphere(
+            transformations::translation(0.0, 0.0, 1.0),
+            Material::default(),
+        );
+        let world = World::new(vec![light], vec![sphere]);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let intersection_state = IntersectionState::prepare(Intersection::new(5.0, &sphere), ray);
+
+        assert!(!world.is_shadowed(light, intersection_state.point, 0.0));
+    }
+
+    #[test]
+    fn distant_surfaces_do_not_shadow_their_own_points() {
+        // lit from the eye, every visible point is in the light
+        let light = Light::new(Point::origin(), Color::white());
+        let large_sphere = Object::sphere(
+            transformations::translation(0.0, 0.0, 1e6) * transformations::scaling(1e4, 1e4, 1e4),
+            Material::default(),
+        );
+        let floor = Object::plane(
+            transformations::translation(0.0, -100.0, 0.0),
+            Material::default(),
+        );
+
+        for object in [large_sphere, floor] {
+            let world = World::new(vec![light], vec![object]);
+            for x in -10..=10 {
+                for y in -10..=10 {
+                    let direction = Vector::new(x as f64 * 1e-3, y as f64 * 1e-3, 1.0).normalize();
+                    let ray = Ray::new(Point::origin(), direction);
+                    let Some(hit) = ray.intersect_world(&world).hit() else {
+                        continue;
+                    };
+                    let point = IntersectionState::prepare(hit, ray).point;
+
+                    assert!(!world.is_shadowed(light, point, 0.0), "{point:?}");
+                }
+            }
+        }
     }
 
     #[test]
@@ -267,6 +575,6 @@ mod tests {
         let world = World::default();
         let point = Point::new(-2.0, 2.0, -2.0);
 
-        assert_eq!(false, world.is_shadowed(world.lights[0], point))
+        assert_eq!(false, world.is_shadowed(world.lights[0], point, 0.0))
     }
 }